@@ -11,6 +11,16 @@ extern crate lazy_static;
 extern crate paste;
 
 pub mod style;
+pub mod style_animation;
+pub mod style_binary;
+pub mod style_color;
+pub mod style_css;
+pub mod style_length;
+pub mod style_media;
 pub mod style_parse;
+pub mod style_selector;
+pub mod style_timeline;
+pub mod style_transform;
 pub mod style_type;
+pub mod style_var;
 
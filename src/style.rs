@@ -5,6 +5,7 @@
 /// 
 use std::default::Default;
 use std::{
+    borrow::Cow,
     hash::{Hash, Hasher},
     mem::transmute,
 };
@@ -21,6 +22,10 @@ use pi_atom::Atom;
 use pi_flex_layout::prelude::{INode, Number, Rect, Size as FlexSize};
 
 pub type Point2 = nalgebra::Point2<f32>;
+/// 2D齐次变换矩阵
+pub type Matrix3 = nalgebra::Matrix3<f32>;
+/// 3D齐次变换矩阵
+pub type Matrix4 = nalgebra::Matrix4<f32>;
 
 
 pub type Aabb2 = parry2d::bounding_volume::Aabb;
@@ -69,8 +74,8 @@ pub enum BaseShape {
 		border_radius: BorderRadius,
 	},
 	Sector {
-		rotate: f32, // 旋转 （单位： 弧度）
-		angle: f32, // 弧度角
+		rotate: Deg, // 旋转
+		angle: Deg, // 扇形的角度
 		radius: LengthUnit, // 半径
 		center: Center
 	}
@@ -256,6 +261,8 @@ pub enum AnimationTimingFunction {
     Step(usize, EStepMode),
     /// 贝塞尔曲线
     CubicBezier(f32, f32, f32, f32),
+    /// 分段线性（linear(...)函数），每个点为(输入进度, 输出进度)，输入进度单调不减
+    LinearPoints(Vec<(f32, f32)>),
 }
 
 
@@ -383,7 +390,7 @@ pub struct AllTransform {
     pub transform: Vec<TransformFunc>,
 	pub translate: Option<[LengthUnit;2]>, // 平移，单位：px
 	pub scale: Option<[f32;2]>, // 缩放 0~1
-	pub rotate: Option<f32>, // 旋转，单位： 弧度
+	pub rotate: Option<f32>, // 旋转，单位：deg
 }
 
 pub type TransformFuncs = Vec<TransformFunc>;
@@ -403,11 +410,13 @@ pub struct BorderColor(pub CgColor);
 #[derive(Debug, Deref, DerefMut, Clone, Serialize, Deserialize, Default, Hash)]
 pub struct BackgroundImage(pub Atom);
 
-// 遮罩图片是图片路径或线性渐变色
+// 遮罩图片是图片路径或渐变色
 #[derive(Clone, Debug, Serialize, Deserialize, EnumDefault)]
 pub enum MaskImage {
     Path(Atom),
     LinearGradient(LinearGradientColor),
+    RadialGradient(RadialGradientColor),
+    ConicGradient(ConicGradientColor),
 }
 
 
@@ -417,7 +426,7 @@ pub struct MaskImageClip(pub NotNanRect);
 // 滤镜， 与CSS的Filter不同， 该滤镜不依赖Filter 函数的先后顺序， 且同种滤镜设置多次，会覆盖前面的设置（css是一种叠加效果）
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Hsi {
-    pub hue_rotate: f32,  //色相转换  -0.5 ~ 0.5 , 对应ps的-180 ~180
+    pub hue_rotate: Turn,  //色相转换  -0.5 ~ 0.5 , 对应ps的-180 ~180
     pub saturate: f32,    // 饱和度  -1。0 ~1.0 ， 对应ps的 -100 ~ 100
     pub bright_ness: f32, //亮度 -1。0 ~1.0 ， 对应ps的 -100 ~ 100
 }
@@ -585,10 +594,31 @@ pub struct TransformWillChange(pub TransformFuncs);
 // #[derive(Debug)]
 // pub struct Quad(pub Point2, pub Point2, pub Point2, pub Point2);
 
-#[derive(Clone, Copy, Debug, EnumDefault, Serialize, Deserialize)]
+#[derive(Clone, Debug, EnumDefault, Serialize, Deserialize)]
 pub enum LengthUnit {
     Pixel(f32),
     Percent(f32),
+	/// calc()、min()、max()、clamp()，且不能化简为单一单位的值
+	Calc(Box<CalcExpr>),
+}
+
+impl Hash for LengthUnit {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		match self {
+			LengthUnit::Pixel(r) => {
+				0u8.hash(state);
+				NotNan::new(*r).unwrap().hash(state);
+			}
+			LengthUnit::Percent(r) => {
+				1u8.hash(state);
+				NotNan::new(*r).unwrap().hash(state);
+			}
+			LengthUnit::Calc(r) => {
+				2u8.hash(state);
+				r.hash(state);
+			}
+		}
+	}
 }
 
 impl LengthUnit {
@@ -597,6 +627,210 @@ impl LengthUnit {
 		match self {
 			LengthUnit::Pixel(r) => *r,
 			LengthUnit::Percent(r) => refer * r,
+			LengthUnit::Calc(r) => r.eval(refer),
+		}
+	}
+
+	/// 从表达式创建，能化简为单一单位时，返回Pixel或Percent
+	pub fn from_calc(expr: CalcExpr) -> Self {
+		match expr {
+			CalcExpr::Value { pixel, percent } if percent == 0.0 => LengthUnit::Pixel(pixel),
+			CalcExpr::Value { pixel, percent } if pixel == 0.0 => LengthUnit::Percent(percent),
+			r => LengthUnit::Calc(Box::new(r)),
+		}
+	}
+
+	pub fn to_calc(&self) -> CalcExpr {
+		match self {
+			LengthUnit::Pixel(r) => CalcExpr::Value { pixel: *r, percent: 0.0 },
+			LengthUnit::Percent(r) => CalcExpr::Value { pixel: 0.0, percent: *r },
+			LengthUnit::Calc(r) => (**r).clone(),
+		}
+	}
+}
+
+/// 角度单位
+/// 样式中的角度（旋转、斜切、渐变方向、sector()等）统一换算为deg保存；Hsi的色相例外，以圈数保存（见Hsi）
+/// sector()的角度、Hsi的色相分别用Deg、Turn表示，使用时需显式换算单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AngleUnit {
+	Deg,
+	Rad,
+	Grad,
+	Turn,
+}
+
+impl AngleUnit {
+	/// 根据单位名取到角度单位，不区分大小写
+	pub fn from_name(name: &str) -> Option<Self> {
+		match &*name.to_ascii_lowercase() {
+			"deg" => Some(AngleUnit::Deg),
+			"rad" => Some(AngleUnit::Rad),
+			"grad" => Some(AngleUnit::Grad),
+			"turn" => Some(AngleUnit::Turn),
+			_ => None,
+		}
+	}
+
+	/// 换算为deg
+	pub fn to_deg(self, value: f32) -> f32 {
+		match self {
+			AngleUnit::Deg => value,
+			AngleUnit::Rad => value.to_degrees(),
+			AngleUnit::Grad => value * 0.9,
+			AngleUnit::Turn => value * 360.0,
+		}
+	}
+}
+
+/// 角度，单位：deg
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Deg(pub f32);
+
+impl Deg {
+	pub fn to_radians(self) -> f32 { self.0.to_radians() }
+
+	pub fn to_turn(self) -> Turn { Turn(self.0 / 360.0) }
+}
+
+/// 角度，单位：圈（1turn = 360deg）
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Turn(pub f32);
+
+impl Turn {
+	pub fn to_radians(self) -> f32 { self.0 * std::f32::consts::TAU }
+
+	pub fn to_deg(self) -> Deg { Deg(self.0 * 360.0) }
+}
+
+/// 长度表达式，百分比与LengthUnit::Percent一样，以小数表示（50% => 0.5）
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CalcExpr {
+	/// pixel + percent * refer
+	Value { pixel: f32, percent: f32 },
+	Add(Box<CalcExpr>, Box<CalcExpr>),
+	Mul(Box<CalcExpr>, f32),
+	Min(Vec<CalcExpr>),
+	Max(Vec<CalcExpr>),
+	/// clamp(min, value, max)
+	Clamp(Box<CalcExpr>, Box<CalcExpr>, Box<CalcExpr>),
+}
+
+impl Hash for CalcExpr {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		std::mem::discriminant(self).hash(state);
+		match self {
+			CalcExpr::Value { pixel, percent } => {
+				NotNan::new(*pixel).unwrap().hash(state);
+				NotNan::new(*percent).unwrap().hash(state);
+			}
+			CalcExpr::Add(r1, r2) => {
+				r1.hash(state);
+				r2.hash(state);
+			}
+			CalcExpr::Mul(r, factor) => {
+				r.hash(state);
+				NotNan::new(*factor).unwrap().hash(state);
+			}
+			CalcExpr::Min(list) | CalcExpr::Max(list) => list.hash(state),
+			CalcExpr::Clamp(min, value, max) => {
+				min.hash(state);
+				value.hash(state);
+				max.hash(state);
+			}
+		}
+	}
+}
+
+impl CalcExpr {
+	/// 计算表达式的值，refer为百分比的参照值
+	pub fn eval(&self, refer: f32) -> f32 {
+		match self {
+			CalcExpr::Value { pixel, percent } => pixel + percent * refer,
+			CalcExpr::Add(r1, r2) => r1.eval(refer) + r2.eval(refer),
+			CalcExpr::Mul(r, factor) => r.eval(refer) * factor,
+			CalcExpr::Min(list) => list.iter().map(|r| r.eval(refer)).fold(f32::INFINITY, f32::min),
+			CalcExpr::Max(list) => list.iter().map(|r| r.eval(refer)).fold(f32::NEG_INFINITY, f32::max),
+			// 与css一致，min大于max时，取min
+			CalcExpr::Clamp(min, value, max) => value.eval(refer).min(max.eval(refer)).max(min.eval(refer)),
+		}
+	}
+}
+
+impl std::ops::Add for CalcExpr {
+	type Output = Self;
+	fn add(self, rhs: Self) -> Self {
+		match (self, rhs) {
+			(CalcExpr::Value { pixel: p1, percent: r1 }, CalcExpr::Value { pixel: p2, percent: r2 }) => CalcExpr::Value { pixel: p1 + p2, percent: r1 + r2 },
+			(r1, r2) => CalcExpr::Add(Box::new(r1), Box::new(r2)),
+		}
+	}
+}
+
+impl std::ops::Mul<f32> for CalcExpr {
+	type Output = Self;
+	fn mul(self, rhs: f32) -> Self {
+		match self {
+			CalcExpr::Value { pixel, percent } => CalcExpr::Value { pixel: pixel * rhs, percent: percent * rhs },
+			CalcExpr::Add(r1, r2) => (*r1 * rhs) + (*r2 * rhs),
+			CalcExpr::Mul(r, factor) => CalcExpr::Mul(r, factor * rhs),
+			r => CalcExpr::Mul(Box::new(r), rhs),
+		}
+	}
+}
+
+/// 布局属性（width、margin-top、flex-basis等）的长度
+/// 在布局使用的Dimension的基础上，增加了混合px与%的表达式，如：width: calc(100% - 20px)
+#[derive(Clone, Debug, EnumDefault, Serialize, Deserialize)]
+pub enum LayoutDimension {
+	Undefined,
+	Auto,
+	Points(f32),
+	Percent(f32),
+	/// calc()、min()、max()、clamp()，且不能化简为单一单位的值
+	Calc(Box<CalcExpr>),
+}
+
+impl LayoutDimension {
+	/// 从表达式创建，能化简为单一单位时，返回Points或Percent
+	pub fn from_calc(expr: CalcExpr) -> Self {
+		match LengthUnit::from_calc(expr) {
+			LengthUnit::Pixel(r) => LayoutDimension::Points(r),
+			LengthUnit::Percent(r) => LayoutDimension::Percent(r),
+			LengthUnit::Calc(r) => LayoutDimension::Calc(r),
+		}
+	}
+
+	/// 转换为表达式，Undefined、Auto返回None
+	pub fn to_calc(&self) -> Option<CalcExpr> {
+		match self {
+			LayoutDimension::Undefined | LayoutDimension::Auto => None,
+			LayoutDimension::Points(r) => Some(CalcExpr::Value { pixel: *r, percent: 0.0 }),
+			LayoutDimension::Percent(r) => Some(CalcExpr::Value { pixel: 0.0, percent: *r }),
+			LayoutDimension::Calc(r) => Some((**r).clone()),
+		}
+	}
+
+	/// 转换为布局使用的Dimension，表达式根据refer（百分比的参照值）计算为Dimension::Points
+	/// 与css一致，height、top、bottom、min-height、max-height的refer为父节点的高度，其余（含margin、padding、flex-basis）为父节点的宽度
+	pub fn resolve(&self, refer: f32) -> Dimension {
+		match self {
+			LayoutDimension::Undefined => Dimension::Undefined,
+			LayoutDimension::Auto => Dimension::Auto,
+			LayoutDimension::Points(r) => Dimension::Points(*r),
+			LayoutDimension::Percent(r) => Dimension::Percent(*r),
+			LayoutDimension::Calc(r) => Dimension::Points(r.eval(refer)),
+		}
+	}
+}
+
+impl From<Dimension> for LayoutDimension {
+	fn from(value: Dimension) -> Self {
+		match value {
+			Dimension::Undefined => LayoutDimension::Undefined,
+			Dimension::Auto => LayoutDimension::Auto,
+			Dimension::Points(r) => LayoutDimension::Points(r),
+			Dimension::Percent(r) => LayoutDimension::Percent(r),
 		}
 	}
 }
@@ -612,7 +846,8 @@ pub enum Color {
     // RGB(CgColor),
     RGBA(CgColor),
     LinearGradient(LinearGradientColor),
-    // RadialGradient(RadialGradientColor),
+    RadialGradient(RadialGradientColor),
+    ConicGradient(ConicGradientColor),
     // SvgLinearGradient(u64),
 }
 impl Hash for Color {
@@ -627,6 +862,12 @@ impl Hash for Color {
 			Color::LinearGradient(color) =>  {
 				color.hash(hasher);
 			},
+			Color::RadialGradient(color) =>  {
+				color.hash(hasher);
+			},
+			Color::ConicGradient(color) =>  {
+				color.hash(hasher);
+			},
 		}
     }
 }
@@ -636,38 +877,61 @@ impl Color {
     pub fn is_opaque(&self) -> bool {
         match self {
             Color::RGBA(c) => c.w >= 1.0,
-            Color::LinearGradient(l) => {
-                for c in l.list.iter() {
-                    if c.rgba.w < 1.0 {
-                        return false;
-                    }
-                }
-                true
-            } // Color::RadialGradient(g) => {
-              //     for c in g.list.iter() {
-              //         if c.rgba.a < 1.0 {
-              //             return false
-              //         }
-              //     }
-              //     return true;
-              // }
+            Color::LinearGradient(l) => l.is_opacity(),
+            Color::RadialGradient(g) => g.is_opacity(),
+            Color::ConicGradient(g) => g.is_opacity(),
         }
     }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LinearGradientColor {
+    // css角度减去90度（0表示从左向右）
     pub direction: f32,
     pub list: Vec<ColorAndPosition>,
+    // 是否重复（repeating-linear-gradient）
+    #[serde(default)]
+    pub repeat: bool,
+    // 位置含有长度（如20px）的色标，需要根据渐变线的长度计算，此时list为空，见resolve
+    #[serde(default)]
+    pub stops: Vec<GradientStop>,
+    // 指向角的方向（如to top right），(水平, 垂直)，-1表示left、top，1表示right、bottom
+    // 实际角度与渐变区域的宽高比有关，见angle；此时direction为正方形区域下的角度
+    #[serde(default)]
+    pub corner: Option<(i8, i8)>,
 }
 
 impl LinearGradientColor {
     pub fn is_opacity(&self) -> bool {
-        let mut is_opacity = true;
-        for i in self.list.iter() {
-            is_opacity = is_opacity & i.rgba.is_opacity();
+        self.list.iter().all(|r| r.rgba.is_opacity())
+            && self.stops.iter().all(|r| match r {
+                GradientStop::Color(rgba, _) => rgba.is_opacity(),
+                GradientStop::Hint(_) => true,
+            })
+    }
+
+    /// 渐变方向（css角度减去90度），width、height为渐变区域的尺寸
+    /// 指向角时，渐变线垂直于相邻两个角的连线，见https://drafts.csswg.org/css-images-3/#linear-gradient-syntax
+    pub fn angle(&self, width: f32, height: f32) -> f32 {
+        match self.corner {
+            Some((x, y)) if width > 0.0 && height > 0.0 => (x as f32 * height).atan2(-y as f32 * width).to_degrees() - 90.0,
+            _ => self.direction,
+        }
+    }
+
+    /// 渐变线的长度，width、height为渐变区域的尺寸
+    pub fn line_length(&self, width: f32, height: f32) -> f32 {
+        let angle = (self.angle(width, height) + 90.0).to_radians();
+        (width * angle.sin()).abs() + (height * angle.cos()).abs()
+    }
+
+    /// 取到色标列表，width、height为渐变区域的尺寸（只有色标位置含有长度时才会用到）
+    pub fn resolve(&self, width: f32, height: f32) -> Cow<'_, [ColorAndPosition]> {
+        if self.stops.is_empty() {
+            Cow::Borrowed(&self.list)
+        } else {
+            Cow::Owned(resolve_color_stops(&self.stops, self.line_length(width, height)))
         }
-        is_opacity
     }
 }
 
@@ -681,15 +945,74 @@ impl Hash for LinearGradientColor {
             NotNan::new(l.rgba.z).unwrap().hash(hasher);
             NotNan::new(l.rgba.w).unwrap().hash(hasher);
         }
+        self.repeat.hash(hasher);
+        self.stops.hash(hasher);
+        self.corner.hash(hasher);
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RadialGradientColor {
-    pub center: (f32, f32),
+    pub center: Center,
     pub shape: RadialGradientShape,
     pub size: RadialGradientSize,
+    // 位置百分比0~1，相对于渐变半径
     pub list: Vec<ColorAndPosition>,
+    // 是否重复（repeating-radial-gradient）
+    #[serde(default)]
+    pub repeat: bool,
+}
+
+impl RadialGradientColor {
+    pub fn is_opacity(&self) -> bool { self.list.iter().all(|r| r.rgba.is_opacity()) }
+}
+
+impl Hash for RadialGradientColor {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.center.x.hash(hasher);
+        self.center.y.hash(hasher);
+        (self.shape as u8).hash(hasher);
+        (self.size as u8).hash(hasher);
+        hash_color_stops(&self.list, hasher);
+        self.repeat.hash(hasher);
+    }
+}
+
+// 锥形渐变
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConicGradientColor {
+    // 起始角度（单位：deg），0表示从正上方开始，顺时针方向
+    pub from: f32,
+    pub center: Center,
+    // 位置百分比0~1，对应0~360deg
+    pub list: Vec<ColorAndPosition>,
+    // 是否重复（repeating-conic-gradient）
+    #[serde(default)]
+    pub repeat: bool,
+}
+
+impl ConicGradientColor {
+    pub fn is_opacity(&self) -> bool { self.list.iter().all(|r| r.rgba.is_opacity()) }
+}
+
+impl Hash for ConicGradientColor {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        NotNan::new(self.from).unwrap().hash(hasher);
+        self.center.x.hash(hasher);
+        self.center.y.hash(hasher);
+        hash_color_stops(&self.list, hasher);
+        self.repeat.hash(hasher);
+    }
+}
+
+fn hash_color_stops<H: Hasher>(list: &[ColorAndPosition], hasher: &mut H) {
+    for l in list.iter() {
+        NotNan::new(l.position).unwrap().hash(hasher);
+        NotNan::new(l.rgba.x).unwrap().hash(hasher);
+        NotNan::new(l.rgba.y).unwrap().hash(hasher);
+        NotNan::new(l.rgba.z).unwrap().hash(hasher);
+        NotNan::new(l.rgba.w).unwrap().hash(hasher);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -699,6 +1022,132 @@ pub struct ColorAndPosition {
     pub rgba: CgColor,
 }
 
+// 未计算位置的色标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GradientStop {
+    // 颜色及位置，位置缺省时根据前后色标计算
+    Color(CgColor, Option<LengthUnit>),
+    // 过渡提示，前后两个颜色在该位置各占一半
+    Hint(LengthUnit),
+}
+
+impl Hash for GradientStop {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        match self {
+            GradientStop::Color(rgba, position) => {
+                0u8.hash(hasher);
+                rgba.hash(hasher);
+                position.hash(hasher);
+            }
+            GradientStop::Hint(position) => {
+                1u8.hash(hasher);
+                position.hash(hasher);
+            }
+        }
+    }
+}
+
+/// 按css的规则计算色标的位置（0~1），length为渐变线的长度，用于计算长度单位的位置
+/// * 首、尾色标没有位置时，分别为0和1
+/// * 位置小于前面的位置时，取前面的最大位置
+/// * 连续的没有位置的色标，在前后有位置的色标之间均分
+/// * 过渡提示以插入若干色标的方式近似
+pub fn resolve_color_stops(stops: &[GradientStop], length: f32) -> Vec<ColorAndPosition> {
+    let to_position = |r: &LengthUnit| match r {
+        LengthUnit::Percent(r) => *r,
+        r if length > 0.0 => r.get_absolute_value(length) / length,
+        _ => 0.0,
+    };
+    let last = stops.len().saturating_sub(1);
+    let mut max = f32::NEG_INFINITY;
+    let mut positions: Vec<Option<f32>> = stops
+        .iter()
+        .enumerate()
+        .map(|(i, stop)| {
+            let r = match stop {
+                GradientStop::Color(_, Some(r)) | GradientStop::Hint(r) => to_position(r),
+                GradientStop::Color(_, None) if i == 0 => 0.0,
+                GradientStop::Color(_, None) if i == last => 1.0,
+                GradientStop::Color(_, None) => return None,
+            };
+            max = max.max(r);
+            Some(max)
+        })
+        .collect();
+
+    // 均分没有位置的色标（过渡提示不参与）
+    let colors: Vec<usize> = (0..stops.len()).filter(|i| matches!(stops[*i], GradientStop::Color(..))).collect();
+    let mut i = 1;
+    while i < colors.len() {
+        if positions[colors[i]].is_none() {
+            let start = i - 1;
+            let mut end = i;
+            while positions[colors[end]].is_none() {
+                end += 1;
+            }
+            let (p0, p1) = (positions[colors[start]].unwrap(), positions[colors[end]].unwrap());
+            for (j, index) in colors.iter().enumerate().take(end).skip(i) {
+                positions[*index] = Some(p0 + (p1 - p0) * (j - start) as f32 / (end - start) as f32);
+            }
+            i = end;
+        }
+        i += 1;
+    }
+
+    let mut ret = Vec::with_capacity(stops.len());
+    for (i, stop) in stops.iter().enumerate() {
+        match stop {
+            GradientStop::Color(rgba, _) => ret.push(ColorAndPosition {
+                position: positions[i].unwrap(),
+                rgba: rgba.clone(),
+            }),
+            // 过渡提示前后都是颜色（解析时保证）
+            GradientStop::Hint(_) => {
+                if let (GradientStop::Color(c0, _), GradientStop::Color(c1, _)) = (&stops[i - 1], &stops[i + 1]) {
+                    let (p0, p1) = (positions[i - 1].unwrap(), positions[i + 1].unwrap());
+                    push_hint_stops(c0, c1, p0, p1, positions[i].unwrap().clamp(p0, p1.max(p0)), &mut ret);
+                }
+            }
+        }
+    }
+    ret
+}
+
+// 以9个色标近似过渡提示的插值曲线
+fn push_hint_stops(c0: &CgColor, c1: &CgColor, p0: f32, p1: f32, hint: f32, ret: &mut Vec<ColorAndPosition>) {
+    let range = p1 - p0;
+    if range <= f32::EPSILON {
+        return;
+    }
+    let m = (hint - p0) / range;
+    if (m - 0.5).abs() <= f32::EPSILON {
+        // 中点即线性插值
+        return;
+    }
+    if m <= 0.0 {
+        ret.push(ColorAndPosition { position: p0, rgba: c1.clone() });
+        return;
+    }
+    if m >= 1.0 {
+        ret.push(ColorAndPosition { position: p1, rgba: c0.clone() });
+        return;
+    }
+    let exp = 0.5f32.ln() / m.ln();
+    for k in 1..10 {
+        let t = k as f32 / 10.0;
+        let w = t.powf(exp);
+        ret.push(ColorAndPosition {
+            position: p0 + range * t,
+            rgba: CgColor::new(
+                c0.x + (c1.x - c0.x) * w,
+                c0.y + (c1.y - c0.y) * w,
+                c0.z + (c1.z - c0.z) * w,
+                c0.w + (c1.w - c0.w) * w,
+            ),
+        });
+    }
+}
+
 #[derive(Debug, Clone, Copy, EnumDefault, Serialize, Deserialize)]
 pub enum RadialGradientSize {
     ClosestSide,
@@ -830,6 +1279,23 @@ pub enum TransformFunc {
 
     SkewX(f32),
     SkewY(f32),
+
+    TranslateZ(f32), // 单位：px
+    Translate3D(LengthUnit, LengthUnit, f32),
+    ScaleZ(f32),
+    Scale3D(f32, f32, f32),
+    Rotate3D(f32, f32, f32, f32), // 绕向量(x, y, z)旋转，单位：deg
+    Skew(f32, f32),
+    Perspective(f32), // 透视距离，单位：px，小于等于0表示none
+    Matrix(f32, f32, f32, f32, f32, f32), // matrix(a, b, c, d, e, f)
+    Matrix3D(Box<[f32; 16]>), // matrix3d，按列排列
+}
+
+// 子节点是否保留在3D空间中
+#[derive(Debug, Clone, Copy, EnumDefault, Serialize, Deserialize)]
+pub enum TransformStyle {
+    Flat,
+    Preserve3D,
 }
 
 #[derive(Debug, Clone, EnumDefault, Serialize, Deserialize)]
@@ -842,16 +1308,7 @@ impl TransformOrigin {
     pub fn to_value(&self, width: f32, height: f32) -> Point2 {
         match self {
             TransformOrigin::Center => Point2::new(0.5 * width, 0.5 * height),
-            TransformOrigin::XY(x, y) => Point2::new(
-                match x {
-                    LengthUnit::Pixel(v) => v.clone(),
-                    LengthUnit::Percent(v) => v * width,
-                },
-                match y {
-                    LengthUnit::Pixel(v) => v.clone(),
-                    LengthUnit::Percent(v) => v * height,
-                },
-            ),
+            TransformOrigin::XY(x, y) => Point2::new(x.get_absolute_value(width), y.get_absolute_value(height)),
         }
     }
 }
@@ -870,38 +1327,6 @@ pub enum Enable {
     Visible = 2,
 }
 
-// pub fn tanslate_to_matrix(x: f32, y: f32, z: f32) -> Matrix4{
-// 	Matrix4::new(
-// 		1.0, 0.0, 0.0, 0.0,
-// 		0.0, 1.0, 0.0, 0.0,
-// 		0.0, 0.0, 1.0, 0.0,
-// 		x, y, z, 1.0,
-// 	)
-// }
-
-// pub fn sacle_to_matrix(x: f32, y: f32, z: f32) -> Matrix4{
-// 	Matrix4::new(
-// 		x, 0.0, 0.0, 0.0,
-// 		0.0, y, 0.0, 0.0,
-// 		0.0, 0.0, z, 0.0,
-// 		0.0, 0.0, 0.0, 1.0,
-// 	)
-// }
-
-// pub fn angle_z_to_matrix(theta: f32) -> Matrix4 {
-// 	let r = theta/180.0*(std::f32::consts::PI);
-// 	let (s, c) = r.sin_cos();
-// 	// http://en.wikipedia.org/wiki/Rotation_matrix#Basic_rotations
-// 	// let (s, c) = Rad::sin_cos(theta.into());
-
-// 	Matrix4::new(
-// 		c, s, 0.0, 0.0,
-// 		-s, c, 0.0, 0.0,
-// 		0.0, 0.0, 1.0, 0.0,
-// 		0.0, 0.0, 0.0, 1.0,
-// 	)
-// }
-
 //对齐元素中的文本
 #[derive(Debug, Clone, Copy, EnumDefault, Hash, Serialize, Deserialize)]
 pub enum TextAlign {
@@ -1017,7 +1442,7 @@ pub enum AsImage {
 	/// 强制缓存为图像
 	Force,
 }
-pub const GUI_STYLE_COUNT: u16 =  99;
+pub const GUI_STYLE_COUNT: u16 =  101;
 // 枚举样式的类型
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[repr(u16)]
@@ -1149,6 +1574,20 @@ pub enum StyleType {
     RowGap = 97,
     ColumnGap = 98,
     AutoReduce = 99,
+
+    Perspective = 100,
+    TransformStyle = 101,
+}
+
+impl StyleType {
+    /// 从样式索引转换为StyleType，索引超出范围时返回None
+    pub fn from_u16(value: u16) -> Option<Self> {
+        if value <= GUI_STYLE_COUNT {
+            Some(unsafe { transmute(value) })
+        } else {
+            None
+        }
+    }
 }
 
 // // 可插值属性
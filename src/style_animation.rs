@@ -0,0 +1,530 @@
+//! 关键帧动画
+//!
+//! KeyFrameList中的关键帧按进度保存，每个关键帧含有多个属性；
+//! 播放动画时需要的是每个属性单独的曲线，build_curves将关键帧按属性重新分组，
+//! 每个属性得到一个按进度排序的KeyFrames，可直接转换为pi_curves的FrameCurve，交给pi_animation播放
+//!
+//! 关键帧中可以声明animation-timing-function，作用于该关键帧到下一个关键帧之间（只影响该关键帧中声明的属性），
+//! 没有声明时使用动画的animation-timing-function
+//!
+//! 颜色插值使用的颜色空间按曲线设置（见KeyFrames::color_space），默认为sRGB空间（预乘透明度，与css相同）
+
+use std::collections::{BTreeMap, VecDeque};
+
+use ordered_float::NotNan;
+use pi_atom::Atom;
+use pi_curves::curve::{frame::FrameDataValue, frame_curve::FrameCurve, FrameIndex};
+use pi_curves::easing::get_easing_call;
+use pi_curves::steps::EStepMode;
+use pi_hash::XHashMap;
+
+use crate::style::{AnimationTimingFunction, StyleType};
+use crate::style_color::ColorSpace;
+use crate::style_length::LengthContext;
+use crate::style_parse::{style_list, Attribute, KeyFrameList, ParseReport};
+use crate::style_type::*;
+
+/// 单个关键帧
+#[derive(Debug, Clone)]
+pub struct KeyFrame<T> {
+    /// 进度，0~1
+    pub progress: f32,
+    pub value: T,
+    /// 到下一个关键帧之间的缓动函数，None表示使用动画的animation-timing-function
+    pub timing_function: Option<AnimationTimingFunction>,
+}
+
+/// 单个属性的关键帧，按进度（0~1）排序，每个进度只有一个值
+#[derive(Debug, Clone)]
+pub struct KeyFrames<T> {
+    pub frames: Vec<KeyFrame<T>>,
+    /// 颜色插值使用的颜色空间（只影响含有颜色的属性）
+    pub color_space: ColorSpace,
+}
+
+impl<T: InterpolateIn + Clone> KeyFrames<T> {
+    /// 在进度progress处取值，progress在首尾关键帧之外时，取首尾关键帧的值
+    /// timing_function为动画的animation-timing-function
+    pub fn sample(&self, progress: f32, timing_function: &AnimationTimingFunction) -> Option<T> {
+        let first = self.frames.first()?;
+        let i = match self.frames.iter().rposition(|r| r.progress <= progress) {
+            Some(r) => r,
+            None => return Some(first.value.clone()),
+        };
+        let from = &self.frames[i];
+        let to = match self.frames.get(i + 1) {
+            Some(r) => r,
+            None => return Some(from.value.clone()),
+        };
+        let amount = (progress - from.progress) / (to.progress - from.progress);
+        let easing = from.timing_function.as_ref().unwrap_or(timing_function);
+        Some(from.value.interpolate_in(&to.value, easing.evaluate(amount), self.color_space))
+    }
+
+    /// 转换为pi_curves的帧曲线
+    /// 曲线的设计帧率为frame_count，即进度0~1对应第0~frame_count帧，播放时按动画时长缩放
+    /// 缓动函数（关键帧的或动画的timing_function）逐帧烘焙到曲线中，因此曲线应按线性播放
+    /// 曲线自身按sRGB空间插值，color_space为其它颜色空间时，也逐帧烘焙
+    pub fn to_frame_curve(&self, frame_count: FrameIndex, timing_function: &AnimationTimingFunction) -> FrameCurve<T> {
+        let mut curve = FrameCurve::curve_frame_values(frame_count);
+        let frame = |progress: f32| (progress * frame_count as f32).round() as FrameIndex;
+        for (i, from) in self.frames.iter().enumerate() {
+            curve.curve_frame_values_frame(frame(from.progress), from.value.clone());
+            let easing = from.timing_function.as_ref().unwrap_or(timing_function);
+            match self.frames.get(i + 1) {
+                Some(to) if !matches!(easing, AnimationTimingFunction::Linear) || self.color_space != ColorSpace::Srgb => {
+                    for f in frame(from.progress) + 1..frame(to.progress) {
+                        let amount = (f as f32 / frame_count as f32 - from.progress) / (to.progress - from.progress);
+                        curve.curve_frame_values_frame(f, from.value.interpolate_in(&to.value, easing.evaluate(amount), self.color_space));
+                    }
+                }
+                _ => (),
+            }
+        }
+        curve
+    }
+}
+
+impl AnimationTimingFunction {
+    /// 缓动函数在输入进度progress（0~1）处的输出进度
+    pub fn evaluate(&self, progress: f32) -> f32 {
+        match self {
+            AnimationTimingFunction::Linear => progress,
+            AnimationTimingFunction::Ease(mode) => get_easing_call(*mode)(progress),
+            AnimationTimingFunction::Step(count, mode) => evaluate_steps(*count, mode, progress),
+            AnimationTimingFunction::CubicBezier(x1, y1, x2, y2) => evaluate_cubic_bezier(*x1, *y1, *x2, *y2, progress),
+            AnimationTimingFunction::LinearPoints(points) => evaluate_linear_points(points, progress),
+        }
+    }
+}
+
+// steps(count, mode)，与css的计算方式一致
+fn evaluate_steps(count: usize, mode: &EStepMode, progress: f32) -> f32 {
+    let count = count.max(1) as f32;
+    let mut step = (progress * count).floor();
+    if matches!(mode, EStepMode::JumpStart | EStepMode::JumpBoth) {
+        step += 1.0;
+    }
+    // 跳跃的次数：jump-both在首尾各多一次，jump-none在首尾都不跳跃
+    let jumps = match mode {
+        EStepMode::JumpBoth => count + 1.0,
+        EStepMode::JumpNone => (count - 1.0).max(1.0),
+        _ => count,
+    };
+    step.clamp(0.0, jumps) / jumps
+}
+
+// cubic-bezier(x1, y1, x2, y2)，端点为(0, 0)和(1, 1)
+// 先求出x对应的参数t，再求t处的y；为了精度，用f64计算
+fn evaluate_cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, progress: f32) -> f32 {
+    let (x1, y1, x2, y2, x) = (x1 as f64, y1 as f64, x2 as f64, y2 as f64, progress as f64);
+    // 0~1之外，沿端点的切线延长
+    if x <= 0.0 {
+        let slope = if x1 > 0.0 {
+            y1 / x1
+        } else if y1 == 0.0 && x2 > 0.0 {
+            y2 / x2
+        } else {
+            0.0
+        };
+        return (slope * x) as f32;
+    }
+    if x >= 1.0 {
+        let slope = if x2 < 1.0 {
+            (y2 - 1.0) / (x2 - 1.0)
+        } else if y2 == 1.0 && x1 < 1.0 {
+            (y1 - 1.0) / (x1 - 1.0)
+        } else {
+            0.0
+        };
+        return (1.0 + slope * (x - 1.0)) as f32;
+    }
+    bezier(y1, y2, solve_bezier(x1, x2, x)) as f32
+}
+
+// 一个分量的贝塞尔多项式在t处的值，p1、p2为两个控制点的该分量
+fn bezier(p1: f64, p2: f64, t: f64) -> f64 {
+    let c = 3.0 * p1;
+    let b = 3.0 * (p2 - p1) - c;
+    let a = 1.0 - c - b;
+    ((a * t + b) * t + c) * t
+}
+
+fn bezier_derivative(p1: f64, p2: f64, t: f64) -> f64 {
+    let c = 3.0 * p1;
+    let b = 3.0 * (p2 - p1) - c;
+    let a = 1.0 - c - b;
+    (3.0 * a * t + 2.0 * b) * t + c
+}
+
+// 求x(t) = x对应的t
+// 先用牛顿迭代（收敛快），导数过小或不收敛时用二分（x1、x2在0~1之间时，x(t)单调不减）
+fn solve_bezier(x1: f64, x2: f64, x: f64) -> f64 {
+    const EPSILON: f64 = 1e-7;
+    let mut t = x;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, t) - x;
+        if error.abs() < EPSILON {
+            return t;
+        }
+        let derivative = bezier_derivative(x1, x2, t);
+        if derivative.abs() < 1e-6 {
+            break;
+        }
+        t -= error / derivative;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    t = x;
+    for _ in 0..64 {
+        let value = bezier(x1, x2, t);
+        if (value - x).abs() < EPSILON {
+            break;
+        }
+        if value < x {
+            low = t;
+        } else {
+            high = t;
+        }
+        t = (low + high) * 0.5;
+    }
+    t
+}
+
+// linear(...)，points为(输入进度, 输出进度)，输入进度单调不减
+// 两点之间线性插值，首尾之外沿首尾两段延长
+fn evaluate_linear_points(points: &[(f32, f32)], progress: f32) -> f32 {
+    if points.len() < 2 {
+        return points.first().map_or(progress, |r| r.1);
+    }
+    let index = points.iter().rposition(|r| r.0 <= progress).unwrap_or(0).min(points.len() - 2);
+    let (a, b) = (points[index], points[index + 1]);
+    if a.0 == b.0 {
+        return b.1;
+    }
+    a.1 + (b.1 - a.1) * (progress - a.0) / (b.0 - a.0)
+}
+
+macro_rules! define_key_frames {
+    (; $($style_ty: ident => $variant: ident($value_ty: ident)),* $(,)?) => {
+        /// 一个属性的关键帧，变体与Attribute一一对应
+        #[derive(Debug, Clone)]
+        pub enum AttributeKeyFrames {
+            $($variant(KeyFrames<$value_ty>),)*
+        }
+
+        impl AttributeKeyFrames {
+            /// 样式类型
+            pub fn get_type(&self) -> StyleType {
+                match self {
+                    $(AttributeKeyFrames::$variant(_) => StyleType::$style_ty,)*
+                }
+            }
+
+            /// 设置颜色插值使用的颜色空间，见KeyFrames::color_space
+            pub fn set_color_space(&mut self, space: ColorSpace) {
+                match self {
+                    $(AttributeKeyFrames::$variant(r) => r.color_space = space,)*
+                }
+            }
+
+            /// 在进度progress处取值，见KeyFrames::sample
+            pub fn sample(&self, progress: f32, timing_function: &AnimationTimingFunction) -> Option<Attribute> {
+                match self {
+                    $(AttributeKeyFrames::$variant(r) => r.sample(progress, timing_function).map(Attribute::$variant),)*
+                }
+            }
+
+            // frames中的属性都是ty类型
+            fn from_attrs(ty: StyleType, frames: Vec<KeyFrame<Attribute>>) -> Self {
+                match ty {
+                    $(StyleType::$style_ty => AttributeKeyFrames::$variant(KeyFrames {
+                        frames: frames
+                            .into_iter()
+                            .filter_map(|r| match r.value {
+                                Attribute::$variant(value) => Some(KeyFrame {
+                                    progress: r.progress,
+                                    value,
+                                    timing_function: r.timing_function,
+                                }),
+                                _ => None,
+                            })
+                            .collect(),
+                        color_space: ColorSpace::Srgb,
+                    }),)*
+                }
+            }
+        }
+    };
+}
+
+style_list!(define_key_frames!());
+
+impl KeyFrameList {
+    /// 为每个动画创建每个属性的关键帧曲线，结果按样式类型排序
+    /// 属性缺少0%或100%关键帧时，用base中对应的样式补齐（base返回None时不补齐，曲线在首尾保持不变）
+    /// 相对长度使用默认的LengthContext换算，其中视口尺寸未知，含有vw等的声明被忽略，需要时应使用build_curves_with_context
+    pub fn build_curves<F: Fn(StyleType) -> Option<Attribute>>(&self, base: F) -> XHashMap<Atom, Vec<AttributeKeyFrames>> {
+        let (curves, report) = self.build_curves_with_context(&LengthContext::default(), base);
+        report.log();
+        curves
+    }
+
+    /// 同build_curves，关键帧中的vw、vh、vmin、vmax根据lengths换算为px（视口变化时应重新构建）
+    /// 返回换算时产生的错误（换算失败的声明被忽略）
+    pub fn build_curves_with_context<F: Fn(StyleType) -> Option<Attribute>>(
+        &self,
+        lengths: &LengthContext,
+        base: F,
+    ) -> (XHashMap<Atom, Vec<AttributeKeyFrames>>, ParseReport) {
+        let mut report = ParseReport::default();
+        let curves = self
+            .frames
+            .iter()
+            .map(|(name, frames)| {
+                let curves = match self.resolve_viewport_vars(name, lengths, &mut report) {
+                    Some((frames, timing_functions)) => build_key_frames(&frames, Some(&timing_functions), &base),
+                    None => build_key_frames(frames, self.timing_functions.get(name), &base),
+                };
+                (name.clone(), curves)
+            })
+            .collect();
+        (curves, report)
+    }
+}
+
+/// 将一个动画的关键帧按属性分组，见KeyFrameList::build_curves
+/// timing_functions为关键帧中声明的animation-timing-function
+pub fn build_key_frames<F: Fn(StyleType) -> Option<Attribute>>(
+    frames: &XHashMap<NotNan<f32>, VecDeque<Attribute>>,
+    timing_functions: Option<&XHashMap<NotNan<f32>, AnimationTimingFunction>>,
+    base: &F,
+) -> Vec<AttributeKeyFrames> {
+    let mut progress: Vec<&NotNan<f32>> = frames.keys().collect();
+    progress.sort();
+
+    // 同一关键帧中重复的属性，后面的覆盖前面的
+    let mut groups: BTreeMap<u16, Vec<KeyFrame<Attribute>>> = BTreeMap::new();
+    for p in progress {
+        let timing_function = timing_functions.and_then(|r| r.get(p));
+        for attr in frames[p].iter() {
+            let list = groups.entry(group_key(attr)).or_default();
+            match list.last_mut() {
+                Some(r) if r.progress == **p => r.value = attr.clone(),
+                _ => list.push(KeyFrame {
+                    progress: **p,
+                    value: attr.clone(),
+                    timing_function: timing_function.cloned(),
+                }),
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(ty, mut list)| {
+            let ty = StyleType::from_u16(ty).unwrap();
+            let base_value = |progress: f32| {
+                base(ty).filter(|r| group_key(r) == ty as u16).map(|value| KeyFrame {
+                    progress,
+                    value,
+                    timing_function: None,
+                })
+            };
+            if list[0].progress > 0.0 {
+                if let Some(r) = base_value(0.0) {
+                    list.insert(0, r);
+                }
+            }
+            if list[list.len() - 1].progress < 1.0 {
+                if let Some(r) = base_value(1.0) {
+                    list.push(r);
+                }
+            }
+            AttributeKeyFrames::from_attrs(ty, list)
+        })
+        .collect()
+}
+
+/// 属性所在的关键帧曲线，同一动画中每组属性只有一条曲线
+/// AnimationTimeline::sample也按此合并多个动画的值
+pub(crate) fn group_key(attr: &Attribute) -> u16 { attr.get_type() as u16 }
+
+#[test]
+fn test_build_curves() {
+    use crate::style_parse::parse_class_map_from_string;
+    use crate::style::LayoutDimension;
+
+    let class_map = parse_class_map_from_string(
+        "@keyframes move {
+            50% { opacity: 0.5; width: 10px; opacity: 0.6; }
+            0% { opacity: 0; }
+            100% { width: 20px; }
+        }",
+        0,
+    )
+    .unwrap();
+    let curves = class_map.key_frames.build_curves(|ty| match ty {
+        StyleType::Opacity => Some(Attribute::Opacity(OpacityType(1.0))),
+        StyleType::Width => Some(Attribute::Width(WidthType(LayoutDimension::Points(0.0)))),
+        _ => None,
+    });
+    let curves = &curves[&Atom::from("move")];
+    assert_eq!(curves.iter().map(|r| r.get_type()).collect::<Vec<_>>(), vec![StyleType::Width, StyleType::Opacity]);
+
+    match &curves[0] {
+        AttributeKeyFrames::Width(r) => {
+            let frames: Vec<(f32, f32)> = r
+                .frames
+                .iter()
+                .map(|r| match &r.value.0 {
+                    LayoutDimension::Points(value) => (r.progress, *value),
+                    r => panic!("{:?}", r),
+                })
+                .collect();
+            // 0%由base补齐
+            assert_eq!(frames, vec![(0.0, 0.0), (0.5, 10.0), (1.0, 20.0)]);
+        }
+        r => panic!("{:?}", r),
+    }
+    match &curves[1] {
+        AttributeKeyFrames::Opacity(r) => {
+            // 重复的属性取后面的值，100%由base补齐
+            assert_eq!(r.frames.iter().map(|r| (r.progress, r.value.0)).collect::<Vec<_>>(), vec![(0.0, 0.0), (0.5, 0.6), (1.0, 1.0)]);
+        }
+        r => panic!("{:?}", r),
+    }
+
+    // 没有base时，不补齐
+    let curves = class_map.key_frames.build_curves(|_| None);
+    assert!(matches!(&curves[&Atom::from("move")][0], AttributeKeyFrames::Width(r) if r.frames.len() == 2));
+}
+
+#[test]
+fn test_calc_curves() {
+    use crate::style::LayoutDimension;
+    use crate::style_parse::parse_class_map_from_string;
+    use pi_flex_layout::style::Dimension;
+
+    let class_map = parse_class_map_from_string(
+        "@keyframes grow {
+            from { width: 100px; }
+            to { width: calc(100% - 20px); }
+        }",
+        0,
+    )
+    .unwrap();
+    let curves = class_map.key_frames.build_curves(|_| None);
+    let width = match &curves[&Atom::from("grow")][..] {
+        [AttributeKeyFrames::Width(r)] => r,
+        r => panic!("{:?}", r),
+    };
+    let sample = |progress: f32| width.sample(progress, &AnimationTimingFunction::Linear).unwrap().0;
+    // px与calc()之间按表达式插值，不跳变
+    assert!(matches!(sample(0.0), LayoutDimension::Points(r) if r == 100.0));
+    assert!(matches!(sample(0.5), LayoutDimension::Calc(_)));
+    assert!(matches!(sample(0.5).resolve(200.0), Dimension::Points(r) if r == 140.0));
+    assert!(matches!(sample(0.75).resolve(200.0), Dimension::Points(r) if r == 160.0));
+    assert!(matches!(sample(1.0).resolve(200.0), Dimension::Points(r) if r == 180.0));
+}
+
+#[test]
+fn test_key_frame_timing_function() {
+    use crate::style_parse::parse_class_map_from_string;
+
+    let s = "@keyframes fade {
+	0% {
+		opacity: 0;
+		animation-timing-function: steps(2, jump-end);
+	}
+	50% {
+		opacity: 0.5;
+		width: 10px;
+	}
+	100% {
+		opacity: 1;
+		width: 20px;
+	}
+}
+";
+    let class_map = parse_class_map_from_string(s, 0).unwrap();
+    let name = Atom::from("fade");
+    let timing_functions = &class_map.key_frames.timing_functions[&name];
+    assert_eq!(timing_functions.len(), 1);
+    assert!(matches!(timing_functions[&NotNan::new(0.0).unwrap()], AnimationTimingFunction::Step(2, EStepMode::JumpEnd)));
+    // 缓动函数不作为关键帧的属性
+    assert!(class_map.key_frames.frames[&name].values().all(|r| r.len() <= 2));
+    assert_eq!(class_map.to_css_string(), s);
+
+    let curves = class_map.key_frames.build_curves(|_| None);
+    let opacity = match curves[&name].iter().find(|r| r.get_type() == StyleType::Opacity) {
+        Some(AttributeKeyFrames::Opacity(r)) => r,
+        r => panic!("{:?}", r),
+    };
+    let sample = |progress: f32| opacity.sample(progress, &AnimationTimingFunction::Linear).unwrap().0;
+    // 0%~50%使用关键帧的steps(2, jump-end)，50%~100%使用动画的linear
+    assert_eq!(sample(0.1), 0.0);
+    assert_eq!(sample(0.3), 0.25);
+    assert!((sample(0.75) - 0.75).abs() < 1e-5);
+    assert_eq!(sample(1.5), 1.0);
+    // 关键帧的缓动函数只作用于该关键帧中声明的属性
+    match curves[&name].iter().find(|r| r.get_type() == StyleType::Width) {
+        Some(AttributeKeyFrames::Width(r)) => assert!(r.frames.iter().all(|r| r.timing_function.is_none())),
+        r => panic!("{:?}", r),
+    }
+
+    // @media中重新定义的同名keyframes没有关键帧缓动函数时，不保留原来的
+    let class_map = parse_class_map_from_string(
+        &format!("{}@media all {{ @keyframes fade {{ 0% {{ opacity: 0; }} 100% {{ opacity: 1; }} }} }}", s),
+        0,
+    )
+    .unwrap()
+    .resolve_media(&Default::default());
+    assert_eq!(class_map.key_frames.frames[&name].len(), 2);
+    assert!(!class_map.key_frames.timing_functions.contains_key(&name));
+}
+
+#[test]
+fn test_timing_function() {
+    use crate::style_css::ToCss;
+    use crate::style_parse::{parse_style_list_from_string, parse_style_list_from_string_with_report};
+
+    let parse = |s: &str| -> Vec<AnimationTimingFunction> {
+        match parse_style_list_from_string(&format!("animation-timing-function: {}", s), 0).unwrap().pop_front() {
+            Some(Attribute::AnimationTimingFunction(r)) => r.0.into_vec(),
+            r => panic!("{:?}", r),
+        }
+    };
+    let near = |a: f32, b: f32| (a - b).abs() < 1e-4;
+
+    let r = parse("ease, ease-in-out, cubic-bezier(0, 0, 1, 1)");
+    assert!(near(r[0].evaluate(0.5), 0.8024));
+    assert!(near(r[1].evaluate(0.5), 0.5));
+    assert!(near(r[1].evaluate(0.0), 0.0) && near(r[1].evaluate(1.0), 1.0));
+    for i in 0..=10 {
+        assert!(near(r[2].evaluate(i as f32 / 10.0), i as f32 / 10.0));
+    }
+    // 0~1之外沿切线延长
+    assert!(near(r[2].evaluate(1.5), 1.5));
+
+    let r = parse("steps(4), steps(4, jump-start), steps(4, jump-both), steps(4, jump-none)");
+    assert!(matches!(r[0], AnimationTimingFunction::Step(4, EStepMode::JumpEnd)));
+    assert!(matches!(r[2], AnimationTimingFunction::Step(4, EStepMode::JumpBoth)));
+    let values = |f: &AnimationTimingFunction| [0.0, 0.5, 0.99, 1.0].map(|r| f.evaluate(r));
+    assert_eq!(values(&r[0]), [0.0, 0.5, 0.75, 1.0]);
+    assert_eq!(values(&r[1]), [0.25, 0.75, 1.0, 1.0]);
+    assert_eq!(values(&r[2]), [0.2, 0.6, 0.8, 1.0]);
+    assert!(near(r[3].evaluate(0.5), 2.0 / 3.0) && r[3].evaluate(0.0) == 0.0 && r[3].evaluate(1.0) == 1.0);
+    // 步数必须为整数，jump-none至少为2
+    for s in ["steps(1, jump-none)", "steps(2.5)", "steps(0)"] {
+        let (attrs, report) = parse_style_list_from_string_with_report(&format!("animation-timing-function: {}", s), 0);
+        assert!(attrs.is_empty() && !report.is_empty(), "{}", s);
+    }
+
+    // linear(...)：缺少的输入进度按规范补齐
+    let r = parse("linear(0, 0.25 75%, 1), linear(0, 0.5, 0.6 20% 40%, 1), linear()");
+    assert!(near(r[0].evaluate(0.375), 0.125) && near(r[0].evaluate(0.875), 0.625));
+    assert!(near(r[1].evaluate(0.05), 0.25) && near(r[1].evaluate(0.3), 0.6) && near(r[1].evaluate(0.7), 0.8));
+    assert!(matches!(r[2], AnimationTimingFunction::Linear));
+    assert_eq!(r[1].to_css_string(), "linear(0 0%, 0.5 10%, 0.6 20%, 0.6 40%, 1 100%)");
+}
@@ -0,0 +1,762 @@
+//! ClassSheet的二进制格式
+//!
+//! ClassSheet中的style_buffer保存的是样式值的内存布局，依赖编译器版本、目标平台的指针宽度，
+//! 并且含有Atom、Vec等的堆指针，不能直接持久化或在不同设备间传输。
+//! 此模块定义一种与平台无关、带版本号的编码格式，所有整数均为小端序：
+//!
+//! ```text
+//! Header
+//!     magic:               [u8; 4]    固定为 b"PICS"
+//!     format_version:      u16        格式版本，见FORMAT_VERSION
+//!     style_table_version: u16        StyleType表的版本，见STYLE_TABLE_VERSION
+//!     style_count:         u16        StyleType的数量
+//! StringTable
+//!     count:               u32
+//!     [len: u32, utf8: [u8; len]] * count
+//! Classes
+//!     count:               u32
+//!     [class_name: u64, style_count: u32, Style * style_count] * count
+//! States                                     带有伪类的class
+//!     count:               u32
+//!     [class_name: u64, state: u8, style_count: u32, Style * style_count] * count
+//! Selectors                                  按定义顺序排列
+//!     count:               u32
+//!     [selector: Selector, style_count: u32, Style * style_count] * count
+//! Media                                      @media中的样式表，按定义顺序排列
+//!     count:               u32
+//!     [query: MediaQueryList, len: u32, sheet: [u8; len]] * count    sheet为完整的二进制格式（含Header）
+//! Selector、MediaQueryList                  按serde定义编码，见下文
+//! Style
+//!     index:               u16        StyleType的值；重置样式为 StyleType + STYLE_COUNT_MAX，且没有value
+//!     value:                          样式值，按字段依次编码
+//! ```
+//!
+//! 样式值按照其serde定义逐字段编码：
+//! * bool、u8、i8 占1个字节，u16/i16、u32/i32、u64/i64、f32、f64 按其宽度编码，usize/isize按u64/i64编码
+//! * char 按u32编码
+//! * 字符串编码为字符串表中的索引（u32）
+//! * Option 编码为1个字节的标记（0: None，1: Some），Some时后跟其值
+//! * 序列、map 编码为长度（u32），后跟各元素
+//! * 结构体、元组、数组依次编码各字段，没有长度前缀
+//! * 枚举编码为变体索引（u32），后跟变体内容
+//!
+//! 因此，StyleType或任意样式值类型的定义（字段顺序、枚举变体顺序）发生变化时，必须增加STYLE_TABLE_VERSION。
+
+use bitvec::prelude::BitArray;
+use pi_hash::XHashMap;
+use serde::{
+    de::{self, DeserializeSeed, IntoDeserializer, Visitor},
+    ser, Deserialize, Serialize,
+};
+use thiserror::Error;
+
+use crate::style::{StyleType, GUI_STYLE_COUNT};
+use crate::style_parse::{reset_to_buffer, style_list, style_to_buffer, Attribute, ClassReadError, ClassStyle, ClassStyleReader};
+use crate::style_media::{MediaQueryList, MediaSheet};
+use crate::style_selector::{PseudoState, Selector, SelectorMeta};
+use crate::style_type::*;
+
+/// 文件标识
+pub const MAGIC: [u8; 4] = *b"PICS";
+/// 格式版本
+pub const FORMAT_VERSION: u16 = 4;
+/// StyleType表的版本，StyleType或样式值类型的定义发生变化时，需要增加该版本
+pub const STYLE_TABLE_VERSION: u16 = 10;
+
+#[derive(Debug, Error)]
+pub enum BinaryError {
+    #[error("invalid magic: {0:?}")]
+    InvalidMagic([u8; 4]),
+    #[error("unsupported format version: {0}")]
+    FormatVersion(u16),
+    #[error("style table version mismatch, expect: {expect}, found: {found}")]
+    StyleTableVersion { expect: u16, found: u16 },
+    #[error("style count mismatch, expect: {expect}, found: {found}")]
+    StyleCount { expect: u16, found: u16 },
+    #[error("unexpected end of data, offset: {0}")]
+    UnexpectedEnd(usize),
+    #[error("invalid string index: {0}")]
+    InvalidStringIndex(u32),
+    #[error("invalid style index: {0}")]
+    InvalidStyleIndex(u16),
+    #[error("sequence length is unknown")]
+    UnknownLength,
+    #[error("{0}")]
+    Message(String),
+    #[error("read class sheet fail: {0}")]
+    Read(#[from] ClassReadError),
+}
+
+impl ser::Error for BinaryError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self { BinaryError::Message(msg.to_string()) }
+}
+
+impl de::Error for BinaryError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self { BinaryError::Message(msg.to_string()) }
+}
+
+impl ClassSheet {
+    /// 编码为二进制格式
+    ///
+    /// # Safety
+    /// 需要读取style_buffer中的样式值，安全约定同ClassSheet::iter_class（包括media中的样式表）
+    pub unsafe fn to_binary(&self) -> Result<Vec<u8>, BinaryError> {
+        let mut writer = Writer::default();
+
+        // 按class名排序，保证相同的ClassSheet编码结果相同
+        let mut class_names: Vec<usize> = self.class_map.keys().cloned().collect();
+        class_names.sort_unstable();
+
+        writer.write_u32(class_names.len() as u32);
+        for class_name in class_names {
+            let styles = self.iter_class(class_name)?.collect::<Result<Vec<ClassStyle>, _>>()?;
+            writer.write_u64(class_name as u64);
+            writer.write_u32(styles.len() as u32);
+            for style in styles.iter() {
+                write_style(&mut writer, style)?;
+            }
+        }
+
+        let mut states: Vec<(usize, PseudoState)> = self.state_map.keys().cloned().collect();
+        states.sort_unstable();
+
+        writer.write_u32(states.len() as u32);
+        for (class_name, state) in states {
+            let styles = ClassStyleReader::new(self.style_buffer.as_slice(), &self.state_map[&(class_name, state)])?.collect::<Result<Vec<ClassStyle>, _>>()?;
+            writer.write_u64(class_name as u64);
+            writer.buffer.push(state.bits());
+            writer.write_u32(styles.len() as u32);
+            for style in styles.iter() {
+                write_style(&mut writer, style)?;
+            }
+        }
+
+        writer.write_u32(self.selectors.len() as u32);
+        for selector in self.selectors.iter() {
+            let styles = ClassStyleReader::new(self.style_buffer.as_slice(), &selector.meta)?.collect::<Result<Vec<ClassStyle>, _>>()?;
+            selector.selector.serialize(&mut writer)?;
+            writer.write_u32(styles.len() as u32);
+            for style in styles.iter() {
+                write_style(&mut writer, style)?;
+            }
+        }
+
+        writer.write_u32(self.media.len() as u32);
+        for media in self.media.iter() {
+            let data = media.sheet.to_binary()?;
+            media.query.serialize(&mut writer)?;
+            writer.write_u32(data.len() as u32);
+            writer.buffer.extend_from_slice(&data);
+        }
+
+        let mut buffer = Vec::with_capacity(writer.buffer.len() + 64);
+        buffer.extend_from_slice(&MAGIC);
+        buffer.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        buffer.extend_from_slice(&STYLE_TABLE_VERSION.to_le_bytes());
+        buffer.extend_from_slice(&(GUI_STYLE_COUNT + 1).to_le_bytes());
+        buffer.extend_from_slice(&(writer.strings.len() as u32).to_le_bytes());
+        for s in writer.strings.iter() {
+            buffer.extend_from_slice(&(s.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(s.as_bytes());
+        }
+        buffer.extend_from_slice(&writer.buffer);
+        Ok(buffer)
+    }
+
+    /// 从二进制格式解码，重建style_buffer和ClassMeta
+    pub fn from_binary(data: &[u8]) -> Result<ClassSheet, BinaryError> {
+        let mut reader = Reader { data, offset: 0, strings: Vec::new() };
+
+        let mut magic = [0; 4];
+        magic.copy_from_slice(reader.read_bytes(4)?);
+        if magic != MAGIC {
+            return Err(BinaryError::InvalidMagic(magic));
+        }
+        let format_version = reader.read_u16()?;
+        if format_version != FORMAT_VERSION {
+            return Err(BinaryError::FormatVersion(format_version));
+        }
+        let style_table_version = reader.read_u16()?;
+        let style_count = reader.read_u16()?;
+        if style_table_version != STYLE_TABLE_VERSION {
+            return Err(BinaryError::StyleTableVersion {
+                expect: STYLE_TABLE_VERSION,
+                found: style_table_version,
+            });
+        }
+        if style_count != GUI_STYLE_COUNT + 1 {
+            return Err(BinaryError::StyleCount {
+                expect: GUI_STYLE_COUNT + 1,
+                found: style_count,
+            });
+        }
+
+        let string_count = reader.read_u32()?;
+        for _ in 0..string_count {
+            let len = reader.read_u32()? as usize;
+            let s = std::str::from_utf8(reader.read_bytes(len)?).map_err(|e| BinaryError::Message(e.to_string()))?;
+            reader.strings.push(s.to_string());
+        }
+
+        let mut class_sheet = ClassSheet::default();
+        let class_count = reader.read_u32()?;
+        for _ in 0..class_count {
+            let class_name = reader.read_u64()? as usize;
+            let style_count = reader.read_u32()?;
+
+            let start = class_sheet.style_buffer.len();
+            let mut class_meta = ClassMeta {
+                start,
+                end: start,
+                class_style_mark: BitArray::default(),
+            };
+            read_styles(&mut reader, style_count, &mut class_sheet.style_buffer, &mut class_meta)?;
+            class_sheet.class_map.insert(class_name, class_meta);
+        }
+
+        let state_count = reader.read_u32()?;
+        for _ in 0..state_count {
+            let class_name = reader.read_u64()? as usize;
+            let state = PseudoState::from_bits(reader.read_u8()?);
+            let style_count = reader.read_u32()?;
+
+            let start = class_sheet.style_buffer.len();
+            let mut class_meta = ClassMeta {
+                start,
+                end: start,
+                class_style_mark: BitArray::default(),
+            };
+            read_styles(&mut reader, style_count, &mut class_sheet.style_buffer, &mut class_meta)?;
+            class_sheet.state_map.insert((class_name, state), class_meta);
+        }
+
+        let selector_count = reader.read_u32()?;
+        for _ in 0..selector_count {
+            let selector = Selector::deserialize(&mut reader)?;
+            let style_count = reader.read_u32()?;
+
+            let start = class_sheet.style_buffer.len();
+            let mut meta = ClassMeta {
+                start,
+                end: start,
+                class_style_mark: BitArray::default(),
+            };
+            read_styles(&mut reader, style_count, &mut class_sheet.style_buffer, &mut meta)?;
+            class_sheet.selectors.push(SelectorMeta { selector, meta });
+        }
+
+        let media_count = reader.read_u32()?;
+        for _ in 0..media_count {
+            let query = MediaQueryList::deserialize(&mut reader)?;
+            let len = reader.read_u32()? as usize;
+            let sheet = ClassSheet::from_binary(reader.read_bytes(len)?)?;
+            class_sheet.media.push(MediaSheet { query, sheet });
+        }
+
+        Ok(class_sheet)
+    }
+}
+
+fn read_styles(reader: &mut Reader, style_count: u32, style_buffer: &mut Vec<u8>, class_meta: &mut ClassMeta) -> Result<(), BinaryError> {
+    for _ in 0..style_count {
+        match read_style(reader)? {
+            ClassStyle::Set(attr) => style_to_buffer(style_buffer, attr, class_meta),
+            ClassStyle::Reset(ty) => reset_to_buffer(style_buffer, ty, class_meta),
+        }
+    }
+    class_meta.end = style_buffer.len();
+    Ok(())
+}
+
+fn write_style(writer: &mut Writer, style: &ClassStyle) -> Result<(), BinaryError> {
+    macro_rules! write_attr {
+        ($attr: expr, $writer: expr; $($style_ty: ident => $variant: ident($value_ty: ident)),* $(,)?) => {
+            match $attr {
+                $(Attribute::$variant(r) => {
+                    $writer.write_u16(StyleType::$style_ty as u16);
+                    r.serialize(&mut *$writer)?;
+                })*
+            }
+        };
+    }
+
+    match style {
+        ClassStyle::Set(attr) => style_list!(write_attr!(attr, writer)),
+        ClassStyle::Reset(ty) => writer.write_u16(*ty as u16 + STYLE_COUNT_MAX),
+    }
+    Ok(())
+}
+
+fn read_style(reader: &mut Reader) -> Result<ClassStyle, BinaryError> {
+    let index = reader.read_u16()?;
+    if index >= STYLE_COUNT_MAX {
+        return match StyleType::from_u16(index - STYLE_COUNT_MAX) {
+            Some(ty) => Ok(ClassStyle::Reset(ty)),
+            None => Err(BinaryError::InvalidStyleIndex(index)),
+        };
+    }
+    let ty = StyleType::from_u16(index).ok_or(BinaryError::InvalidStyleIndex(index))?;
+
+    macro_rules! read_attr {
+        ($ty: expr, $reader: expr; $($style_ty: ident => $variant: ident($value_ty: ident)),* $(,)?) => {
+            match $ty {
+                $(StyleType::$style_ty => Attribute::$variant($value_ty::deserialize(&mut *$reader)?),)*
+            }
+        };
+    }
+
+    Ok(ClassStyle::Set(style_list!(read_attr!(ty, reader))))
+}
+
+#[derive(Default)]
+struct Writer {
+    buffer: Vec<u8>,
+    strings: Vec<String>,
+    string_map: XHashMap<String, u32>,
+}
+
+impl Writer {
+    fn write_u16(&mut self, v: u16) { self.buffer.extend_from_slice(&v.to_le_bytes()); }
+    fn write_u32(&mut self, v: u32) { self.buffer.extend_from_slice(&v.to_le_bytes()); }
+    fn write_u64(&mut self, v: u64) { self.buffer.extend_from_slice(&v.to_le_bytes()); }
+    fn write_len(&mut self, len: Option<usize>) -> Result<(), BinaryError> {
+        match len {
+            Some(len) => {
+                self.write_u32(len as u32);
+                Ok(())
+            }
+            None => Err(BinaryError::UnknownLength),
+        }
+    }
+    fn write_str(&mut self, v: &str) {
+        let index = match self.string_map.get(v) {
+            Some(r) => *r,
+            None => {
+                let index = self.strings.len() as u32;
+                self.strings.push(v.to_string());
+                self.string_map.insert(v.to_string(), index);
+                index
+            }
+        };
+        self.write_u32(index);
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Writer {
+    type Ok = ();
+    type Error = BinaryError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn is_human_readable(&self) -> bool { false }
+
+    fn serialize_bool(self, v: bool) -> Result<(), BinaryError> {
+        self.buffer.push(v as u8);
+        Ok(())
+    }
+    fn serialize_i8(self, v: i8) -> Result<(), BinaryError> {
+        self.buffer.push(v as u8);
+        Ok(())
+    }
+    fn serialize_i16(self, v: i16) -> Result<(), BinaryError> {
+        self.buffer.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_i32(self, v: i32) -> Result<(), BinaryError> {
+        self.buffer.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_i64(self, v: i64) -> Result<(), BinaryError> {
+        self.buffer.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_u8(self, v: u8) -> Result<(), BinaryError> {
+        self.buffer.push(v);
+        Ok(())
+    }
+    fn serialize_u16(self, v: u16) -> Result<(), BinaryError> {
+        self.write_u16(v);
+        Ok(())
+    }
+    fn serialize_u32(self, v: u32) -> Result<(), BinaryError> {
+        self.write_u32(v);
+        Ok(())
+    }
+    fn serialize_u64(self, v: u64) -> Result<(), BinaryError> {
+        self.write_u64(v);
+        Ok(())
+    }
+    fn serialize_f32(self, v: f32) -> Result<(), BinaryError> {
+        self.buffer.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_f64(self, v: f64) -> Result<(), BinaryError> {
+        self.buffer.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+    fn serialize_char(self, v: char) -> Result<(), BinaryError> {
+        self.write_u32(v as u32);
+        Ok(())
+    }
+    fn serialize_str(self, v: &str) -> Result<(), BinaryError> {
+        self.write_str(v);
+        Ok(())
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), BinaryError> {
+        self.write_u32(v.len() as u32);
+        self.buffer.extend_from_slice(v);
+        Ok(())
+    }
+    fn serialize_none(self) -> Result<(), BinaryError> {
+        self.buffer.push(0);
+        Ok(())
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), BinaryError> {
+        self.buffer.push(1);
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<(), BinaryError> { Ok(()) }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), BinaryError> { Ok(()) }
+    fn serialize_unit_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str) -> Result<(), BinaryError> {
+        self.write_u32(variant_index);
+        Ok(())
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<(), BinaryError> { value.serialize(self) }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), BinaryError> {
+        self.write_u32(variant_index);
+        value.serialize(self)
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, BinaryError> {
+        self.write_len(len)?;
+        Ok(self)
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self, BinaryError> { Ok(self) }
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, BinaryError> { Ok(self) }
+    fn serialize_tuple_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self, BinaryError> {
+        self.write_u32(variant_index);
+        Ok(self)
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, BinaryError> {
+        self.write_len(len)?;
+        Ok(self)
+    }
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, BinaryError> { Ok(self) }
+    fn serialize_struct_variant(self, _name: &'static str, variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self, BinaryError> {
+        self.write_u32(variant_index);
+        Ok(self)
+    }
+}
+
+macro_rules! impl_serialize_compound {
+    ($trait_name: ident, $method: ident) => {
+        impl<'a> ser::$trait_name for &'a mut Writer {
+            type Ok = ();
+            type Error = BinaryError;
+            fn $method<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), BinaryError> { value.serialize(&mut **self) }
+            fn end(self) -> Result<(), BinaryError> { Ok(()) }
+        }
+    };
+    ($trait_name: ident) => {
+        impl<'a> ser::$trait_name for &'a mut Writer {
+            type Ok = ();
+            type Error = BinaryError;
+            fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, value: &T) -> Result<(), BinaryError> {
+                value.serialize(&mut **self)
+            }
+            fn end(self) -> Result<(), BinaryError> { Ok(()) }
+        }
+    };
+}
+
+impl_serialize_compound!(SerializeSeq, serialize_element);
+impl_serialize_compound!(SerializeTuple, serialize_element);
+impl_serialize_compound!(SerializeTupleStruct, serialize_field);
+impl_serialize_compound!(SerializeTupleVariant, serialize_field);
+impl_serialize_compound!(SerializeStruct);
+impl_serialize_compound!(SerializeStructVariant);
+
+impl<'a> ser::SerializeMap for &'a mut Writer {
+    type Ok = ();
+    type Error = BinaryError;
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), BinaryError> { key.serialize(&mut **self) }
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), BinaryError> { value.serialize(&mut **self) }
+    fn end(self) -> Result<(), BinaryError> { Ok(()) }
+}
+
+struct Reader<'de> {
+    data: &'de [u8],
+    offset: usize,
+    strings: Vec<String>,
+}
+
+impl<'de> Reader<'de> {
+    // 剩余的字节数
+    fn remaining(&self) -> usize { self.data.len() - self.offset }
+    fn read_bytes(&mut self, len: usize) -> Result<&'de [u8], BinaryError> {
+        if self.remaining() < len {
+            return Err(BinaryError::UnexpectedEnd(self.offset));
+        }
+        let r = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(r)
+    }
+    fn read_u8(&mut self) -> Result<u8, BinaryError> { Ok(self.read_bytes(1)?[0]) }
+    fn read_u16(&mut self) -> Result<u16, BinaryError> { Ok(u16::from_le_bytes(self.read_bytes(2)?.try_into().unwrap())) }
+    fn read_u32(&mut self) -> Result<u32, BinaryError> { Ok(u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap())) }
+    fn read_u64(&mut self) -> Result<u64, BinaryError> { Ok(u64::from_le_bytes(self.read_bytes(8)?.try_into().unwrap())) }
+    fn read_str(&mut self) -> Result<&str, BinaryError> {
+        let index = self.read_u32()?;
+        match self.strings.get(index as usize) {
+            Some(r) => Ok(r.as_str()),
+            None => Err(BinaryError::InvalidStringIndex(index)),
+        }
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for &'a mut Reader<'de> {
+    type Error = BinaryError;
+
+    fn is_human_readable(&self) -> bool { false }
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, BinaryError> {
+        Err(BinaryError::Message("deserialize_any is not supported".to_string()))
+    }
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        match self.read_u8()? {
+            0 => visitor.visit_bool(false),
+            1 => visitor.visit_bool(true),
+            r => Err(BinaryError::Message(format!("invalid bool: {}", r))),
+        }
+    }
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_i8(self.read_u8()? as i8) }
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_i16(self.read_u16()? as i16) }
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_i32(self.read_u32()? as i32) }
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_i64(self.read_u64()? as i64) }
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_u8(self.read_u8()?) }
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_u16(self.read_u16()?) }
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_u32(self.read_u32()?) }
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_u64(self.read_u64()?) }
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_f32(f32::from_bits(self.read_u32()?))
+    }
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_f64(f64::from_bits(self.read_u64()?))
+    }
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        let v = self.read_u32()?;
+        match char::from_u32(v) {
+            Some(r) => visitor.visit_char(r),
+            None => Err(BinaryError::Message(format!("invalid char: {}", v))),
+        }
+    }
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_str(self.read_str()?) }
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_string(self.read_str()?.to_string()) }
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        let len = self.read_u32()? as usize;
+        visitor.visit_borrowed_bytes(self.read_bytes(len)?)
+    }
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { self.deserialize_bytes(visitor) }
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        match self.read_u8()? {
+            0 => visitor.visit_none(),
+            1 => visitor.visit_some(self),
+            r => Err(BinaryError::Message(format!("invalid option tag: {}", r))),
+        }
+    }
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_unit() }
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_unit() }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        let len = self.read_u32()? as usize;
+        visitor.visit_seq(Access { reader: self, len })
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_seq(Access { reader: self, len })
+    }
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_seq(Access { reader: self, len })
+    }
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        let len = self.read_u32()? as usize;
+        visitor.visit_map(Access { reader: self, len })
+    }
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_seq(Access { reader: self, len: fields.len() })
+    }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, BinaryError> {
+        visitor.visit_enum(self)
+    }
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> { visitor.visit_u32(self.read_u32()?) }
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, BinaryError> {
+        Err(BinaryError::Message("deserialize_ignored_any is not supported".to_string()))
+    }
+}
+
+struct Access<'a, 'de> {
+    reader: &'a mut Reader<'de>,
+    len: usize,
+}
+
+impl<'a, 'de> de::SeqAccess<'de> for Access<'a, 'de> {
+    type Error = BinaryError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, BinaryError> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(&mut *self.reader).map(Some)
+    }
+
+    // 长度来自输入数据，不可信，每个元素至少占用一个字节，因此不超过剩余的字节数，避免按错误的长度预分配大量内存
+    fn size_hint(&self) -> Option<usize> { Some(self.len.min(self.reader.remaining())) }
+}
+
+impl<'a, 'de> de::MapAccess<'de> for Access<'a, 'de> {
+    type Error = BinaryError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, BinaryError> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        seed.deserialize(&mut *self.reader).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, BinaryError> { seed.deserialize(&mut *self.reader) }
+
+    // 见SeqAccess::size_hint
+    fn size_hint(&self) -> Option<usize> { Some(self.len.min(self.reader.remaining())) }
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for &'a mut Reader<'de> {
+    type Error = BinaryError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), BinaryError> {
+        let index = self.read_u32()?;
+        let index: de::value::U32Deserializer<BinaryError> = index.into_deserializer();
+        let value = seed.deserialize(index)?;
+        Ok((value, self))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for &'a mut Reader<'de> {
+    type Error = BinaryError;
+
+    fn unit_variant(self) -> Result<(), BinaryError> { Ok(()) }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, BinaryError> { seed.deserialize(self) }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, BinaryError> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, BinaryError> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
+
+#[test]
+fn test_binary() {
+    use crate::style_parse::parse_class_map_from_string;
+
+    let s = r#"
+	.c1 {
+		width: 10px;
+		background-image: url(a.png);
+		background-color: #ff0000;
+		transform: scale(0.5, 2) translate(10px, 50%);
+		animation: a1 1s ease-in 2;
+	}
+	.c2 {
+		mask-image: url(a.png);
+		text-shadow: 1px 1px 2px #000, 2px 2px 4px #fff;
+	}
+	.c1 > .c2 {
+		height: 5px;
+	}
+	.c1:hover:active {
+		opacity: 0.5;
+	}"#;
+
+    let mut class_sheet = ClassSheet::default();
+    parse_class_map_from_string(s, 0).unwrap().to_class_sheet(&mut class_sheet);
+
+    let data = unsafe { class_sheet.to_binary() }.unwrap();
+    // 字符串只保存一份
+    assert_eq!(data.windows(5).filter(|r| r == b"a.png").count(), 1);
+
+    let class_sheet1 = ClassSheet::from_binary(&data).unwrap();
+    assert_eq!(class_sheet1.class_map.len(), 2);
+    for class_name in [1, 2] {
+        let list = unsafe { class_sheet.iter_class(class_name) }.unwrap().collect::<Result<Vec<ClassStyle>, _>>().unwrap();
+        let list1 = unsafe { class_sheet1.iter_class(class_name) }.unwrap().collect::<Result<Vec<ClassStyle>, _>>().unwrap();
+        assert_eq!(format!("{:?}", list), format!("{:?}", list1));
+        assert_eq!(
+            class_sheet.class_map.get(&class_name).unwrap().class_style_mark,
+            class_sheet1.class_map.get(&class_name).unwrap().class_style_mark
+        );
+    }
+    assert_eq!(class_sheet1.state_map.len(), 1);
+    assert!(class_sheet1.state_map.contains_key(&(1, PseudoState::HOVER | PseudoState::ACTIVE)));
+    assert_eq!(class_sheet1.selectors.len(), 1);
+    assert_eq!(class_sheet1.selectors[0].selector, class_sheet.selectors[0].selector);
+    let list = unsafe { ClassStyleReader::new(&class_sheet1.style_buffer, &class_sheet1.selectors[0].meta) }.unwrap().collect::<Result<Vec<ClassStyle>, _>>().unwrap();
+    assert_eq!(list.len(), 1);
+    // 重新编码，结果应当一致
+    assert_eq!(unsafe { class_sheet1.to_binary() }.unwrap(), data);
+
+    // 错误的数据
+    assert!(matches!(ClassSheet::from_binary(&data[..3]), Err(BinaryError::UnexpectedEnd(_))));
+    assert!(matches!(ClassSheet::from_binary(b"ABCD\x01\x00"), Err(BinaryError::InvalidMagic(_))));
+    assert!(ClassSheet::from_binary(&data[..data.len() - 1]).is_err());
+    // style_count位于magic、format_version、style_table_version之后
+    let mut data1 = data.clone();
+    data1[8] = data1[8].wrapping_add(1);
+    assert!(matches!(ClassSheet::from_binary(&data1), Err(BinaryError::StyleCount { .. })));
+    // 错误的序列长度不会导致预分配大量内存
+    let mut reader = Reader {
+        data: &[0xff, 0xff, 0xff, 0xff, 1, 0, 0, 0],
+        offset: 0,
+        strings: Vec::new(),
+    };
+    assert!(matches!(Vec::<u32>::deserialize(&mut reader), Err(BinaryError::UnexpectedEnd(_))));
+}
+
+#[test]
+fn test_binary_timing_function() {
+    use crate::style_parse::parse_class_map_from_string;
+
+    let mut class_sheet = ClassSheet::default();
+    parse_class_map_from_string(".c1 { animation-timing-function: linear(0, 0.25 75%, 1), steps(3, jump-both); }", 0)
+        .unwrap()
+        .to_class_sheet(&mut class_sheet);
+    let data = unsafe { class_sheet.to_binary() }.unwrap();
+    let class_sheet1 = ClassSheet::from_binary(&data).unwrap();
+    let list = unsafe { class_sheet1.iter_class(1) }.unwrap().collect::<Result<Vec<ClassStyle>, _>>().unwrap();
+    assert_eq!(
+        format!("{:?}", list),
+        format!("{:?}", unsafe { class_sheet.iter_class(1) }.unwrap().collect::<Result<Vec<ClassStyle>, _>>().unwrap())
+    );
+    assert!(format!("{:?}", list).contains("LinearPoints([(0.0, 0.0), (0.75, 0.25), (1.0, 1.0)])"));
+    assert_eq!(unsafe { class_sheet1.to_binary() }.unwrap(), data);
+}
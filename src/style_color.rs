@@ -0,0 +1,325 @@
+//! 颜色计算工具
+//!
+//! * 渐变取色：ColorAndPosition列表、LinearGradientColor在某个位置的颜色
+//! * 预乘透明度、sRGB与线性空间的转换
+//! * 在不同颜色空间中混合两个颜色
+//! * 相对亮度、对比度（WCAG）
+//! * 将Hsi滤镜转换为4x5的颜色矩阵
+//!
+//! 除特别说明外，颜色的各分量均为0~1，rgb为sRGB空间、非预乘的值
+
+use crate::style::{CgColor, ColorAndPosition, Hsi, LinearGradientColor};
+use crate::style_parse::hsl_to_rgb;
+
+/// 颜色矩阵，4行分别计算r、g、b、a，每行5列分别为r、g、b、a的系数及偏移量（0~1）
+pub type ColorMatrix = [[f32; 5]; 4];
+
+/// 单位颜色矩阵
+pub const IDENTITY_COLOR_MATRIX: ColorMatrix = [
+    [1.0, 0.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0, 0.0],
+];
+
+/// 混合颜色时使用的颜色空间
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// sRGB空间，与css渐变的插值方式相同
+    #[default]
+    Srgb,
+    /// 线性sRGB空间
+    LinearSrgb,
+    /// hsl空间，色相沿较短的方向插值
+    Hsl,
+    /// Oklab空间，感知上较均匀
+    Oklab,
+}
+
+/// sRGB分量转换为线性分量
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// 线性分量转换为sRGB分量
+pub fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// rgb转换为hsl，返回的hue单位为deg，其余为0~1；灰色的hue为0
+pub fn rgb_to_hsl(red: f32, green: f32, blue: f32) -> (f32, f32, f32) {
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let lightness = (max + min) / 2.0;
+    let d = max - min;
+    if d <= 0.0 {
+        return (0.0, 0.0, lightness);
+    }
+    let saturation = if lightness <= 0.5 { d / (max + min) } else { d / (2.0 - max - min) };
+    let hue = if max == red {
+        (green - blue) / d + if green < blue { 6.0 } else { 0.0 }
+    } else if max == green {
+        (blue - red) / d + 2.0
+    } else {
+        (red - green) / d + 4.0
+    };
+    (hue * 60.0, saturation, lightness)
+}
+
+/// 线性sRGB转换为Oklab
+/// https://bottosson.github.io/posts/oklab/
+#[allow(clippy::excessive_precision)]
+fn linear_to_oklab(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    (
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    )
+}
+
+/// Oklab转换为线性sRGB
+#[allow(clippy::excessive_precision)]
+fn oklab_to_linear(l: f32, a: f32, b: f32) -> (f32, f32, f32) {
+    let l1 = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m1 = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s1 = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    (
+        4.0767416621 * l1 - 3.3077115913 * m1 + 0.2309699292 * s1,
+        -1.2684380046 * l1 + 2.6097574011 * m1 - 0.3413193965 * s1,
+        -0.0041960863 * l1 - 0.7034186147 * m1 + 1.7076147010 * s1,
+    )
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 { a + (b - a) * t }
+
+impl CgColor {
+    /// rgb乘以透明度
+    pub fn premultiply(&self) -> CgColor { CgColor::new(self.x * self.w, self.y * self.w, self.z * self.w, self.w) }
+
+    /// rgb除以透明度（premultiply的逆运算），完全透明时rgb为0
+    pub fn unpremultiply(&self) -> CgColor {
+        if self.w <= 0.0 {
+            CgColor::new(0.0, 0.0, 0.0, 0.0)
+        } else {
+            CgColor::new(self.x / self.w, self.y / self.w, self.z / self.w, self.w)
+        }
+    }
+
+    /// sRGB转换为线性sRGB，透明度不变
+    pub fn to_linear(&self) -> CgColor { CgColor::new(srgb_to_linear(self.x), srgb_to_linear(self.y), srgb_to_linear(self.z), self.w) }
+
+    /// 线性sRGB转换为sRGB，透明度不变
+    pub fn to_srgb(&self) -> CgColor { CgColor::new(linear_to_srgb(self.x), linear_to_srgb(self.y), linear_to_srgb(self.z), self.w) }
+
+    /// 相对亮度（不考虑透明度）
+    /// https://www.w3.org/TR/WCAG21/#dfn-relative-luminance
+    pub fn luminance(&self) -> f32 {
+        let c = self.to_linear();
+        0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+    }
+
+    /// 与另一个颜色的对比度，1~21
+    /// https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio
+    pub fn contrast_ratio(&self, other: &CgColor) -> f32 {
+        let (l1, l2) = (self.luminance(), other.luminance());
+        (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05)
+    }
+
+    /// 在space空间中混合两个颜色，t为0时为self，为1时为other
+    /// 除hsl外，均以预乘透明度的方式插值，避免与透明色混合时颜色变暗
+    pub fn mix(&self, other: &CgColor, t: f32, space: ColorSpace) -> CgColor {
+        let alpha = lerp(self.w, other.w, t);
+        match space {
+            ColorSpace::Srgb => mix_premultiplied(self, other, t, alpha),
+            ColorSpace::LinearSrgb => mix_premultiplied(&self.to_linear(), &other.to_linear(), t, alpha).to_srgb(),
+            ColorSpace::Oklab => {
+                let (a, b) = (self.to_linear(), other.to_linear());
+                let (a, b) = (linear_to_oklab(a.x, a.y, a.z), linear_to_oklab(b.x, b.y, b.z));
+                let r = mix_premultiplied(&CgColor::new(a.0, a.1, a.2, self.w), &CgColor::new(b.0, b.1, b.2, other.w), t, alpha);
+                let (red, green, blue) = oklab_to_linear(r.x, r.y, r.z);
+                CgColor::new(red.clamp(0.0, 1.0), green.clamp(0.0, 1.0), blue.clamp(0.0, 1.0), alpha).to_srgb()
+            }
+            ColorSpace::Hsl => {
+                let (mut h1, s1, l1) = rgb_to_hsl(self.x, self.y, self.z);
+                let (mut h2, s2, l2) = rgb_to_hsl(other.x, other.y, other.z);
+                // 灰色没有色相，取另一个颜色的色相
+                if s1 == 0.0 {
+                    h1 = h2;
+                } else if s2 == 0.0 {
+                    h2 = h1;
+                }
+                let mut d = h2 - h1;
+                if d > 180.0 {
+                    d -= 360.0;
+                } else if d < -180.0 {
+                    d += 360.0;
+                }
+                let (red, green, blue) = hsl_to_rgb(h1 + d * t, lerp(s1, s2, t), lerp(l1, l2, t));
+                CgColor::new(red, green, blue, alpha)
+            }
+        }
+    }
+}
+
+// 预乘后插值，alpha为插值后的透明度
+fn mix_premultiplied(a: &CgColor, b: &CgColor, t: f32, alpha: f32) -> CgColor {
+    let (a, b) = (a.premultiply(), b.premultiply());
+    CgColor::new(lerp(a.x, b.x, t), lerp(a.y, b.y, t), lerp(a.z, b.z, t), alpha).unpremultiply()
+}
+
+/// 取到色标列表在position（0~1）处的颜色，超出首尾色标时取首尾的颜色，列表为空时为透明色
+pub fn sample_color_stops(list: &[ColorAndPosition], position: f32) -> CgColor {
+    let (first, last) = match (list.first(), list.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return CgColor::default(),
+    };
+    if position <= first.position {
+        return first.rgba.clone();
+    }
+    if position >= last.position {
+        return last.rgba.clone();
+    }
+    // 位置相同的色标，取后面的颜色
+    let i = list.partition_point(|r| r.position <= position);
+    let (a, b) = (&list[i - 1], &list[i]);
+    let t = (position - a.position) / (b.position - a.position);
+    a.rgba.mix(&b.rgba, t, ColorSpace::Srgb)
+}
+
+impl LinearGradientColor {
+    /// 取到渐变线上position（0~1）处的颜色，repeat时在首尾色标之间重复
+    /// 色标位置含有长度时，需要用resolve得到的列表调用sample_color_stops
+    pub fn sample(&self, position: f32) -> CgColor {
+        let mut position = position;
+        if self.repeat {
+            if let (Some(first), Some(last)) = (self.list.first(), self.list.last()) {
+                let range = last.position - first.position;
+                if range > 0.0 {
+                    position = first.position + (position - first.position).rem_euclid(range);
+                }
+            }
+        }
+        sample_color_stops(&self.list, position)
+    }
+}
+
+impl Hsi {
+    /// 转换为颜色矩阵，依次进行色相旋转、饱和度、亮度调整
+    /// * 色相：绕灰轴旋转hue_rotate
+    /// * 饱和度：与灰度（亮度加权）插值，-1为完全灰度，1为两倍饱和度
+    /// * 亮度：大于0时向白色插值，小于0时向黑色插值
+    pub fn to_color_matrix(&self) -> ColorMatrix {
+        // 亮度权重，与css滤镜相同
+        const LR: f32 = 0.213;
+        const LG: f32 = 0.715;
+        const LB: f32 = 0.072;
+
+        let (sin, cos) = self.hue_rotate.to_radians().sin_cos();
+        let hue = [
+            [LR + cos * (1.0 - LR) - sin * LR, LG - cos * LG - sin * LG, LB - cos * LB + sin * (1.0 - LB)],
+            [LR - cos * LR + sin * 0.143, LG + cos * (1.0 - LG) + sin * 0.140, LB - cos * LB - sin * 0.283],
+            [LR - cos * LR - sin * (1.0 - LR), LG - cos * LG + sin * LG, LB + cos * (1.0 - LB) + sin * LB],
+        ];
+        let s = 1.0 + self.saturate;
+        let saturate = [
+            [LR + (1.0 - LR) * s, LG - LG * s, LB - LB * s],
+            [LR - LR * s, LG + (1.0 - LG) * s, LB - LB * s],
+            [LR - LR * s, LG - LG * s, LB + (1.0 - LB) * s],
+        ];
+        let (scale, offset) = if self.bright_ness >= 0.0 {
+            (1.0 - self.bright_ness, self.bright_ness)
+        } else {
+            (1.0 + self.bright_ness, 0.0)
+        };
+
+        let mut ret = IDENTITY_COLOR_MATRIX;
+        for (row, ret_row) in ret.iter_mut().take(3).enumerate() {
+            for (col, r) in ret_row.iter_mut().take(3).enumerate() {
+                let v: f32 = (0..3).map(|k| saturate[row][k] * hue[k][col]).sum();
+                *r = v * scale;
+            }
+            ret_row[4] = offset;
+        }
+        ret
+    }
+}
+
+/// 用颜色矩阵变换颜色，结果限制在0~1
+pub fn apply_color_matrix(matrix: &ColorMatrix, color: &CgColor) -> CgColor {
+    let input = [color.x, color.y, color.z, color.w];
+    let mut out = [0.0; 4];
+    for (row, r) in matrix.iter().zip(out.iter_mut()) {
+        *r = (row[0] * input[0] + row[1] * input[1] + row[2] * input[2] + row[3] * input[3] + row[4]).clamp(0.0, 1.0);
+    }
+    CgColor::new(out[0], out[1], out[2], out[3])
+}
+
+#[test]
+fn test_color_util() {
+    use crate::style::Turn;
+
+    let near = |a: &CgColor, b: [f32; 4]| (0..4).all(|i| (a[i] - b[i]).abs() < 1e-3);
+    let red = CgColor::new(1.0, 0.0, 0.0, 1.0);
+    let blue = CgColor::new(0.0, 0.0, 1.0, 1.0);
+    let white = CgColor::new(1.0, 1.0, 1.0, 1.0);
+    let black = CgColor::new(0.0, 0.0, 0.0, 1.0);
+
+    // 预乘、sRGB与线性空间
+    let c = CgColor::new(0.5, 0.25, 1.0, 0.5);
+    assert!(near(&c.premultiply(), [0.25, 0.125, 0.5, 0.5]));
+    assert!(near(&c.premultiply().unpremultiply(), [0.5, 0.25, 1.0, 0.5]));
+    assert!(near(&CgColor::new(0.5, 0.5, 0.5, 1.0).to_linear(), [0.214, 0.214, 0.214, 1.0]));
+    assert!(near(&c.to_linear().to_srgb(), [0.5, 0.25, 1.0, 0.5]));
+
+    // 亮度与对比度
+    assert!((white.luminance() - 1.0).abs() < 1e-4);
+    assert!((white.contrast_ratio(&black) - 21.0).abs() < 1e-3);
+    assert!((red.contrast_ratio(&white) - 4.0).abs() < 1e-2);
+
+    // 混合
+    assert!(near(&red.mix(&blue, 0.5, ColorSpace::Srgb), [0.5, 0.0, 0.5, 1.0]));
+    assert!(near(&red.mix(&blue, 0.5, ColorSpace::LinearSrgb), [0.735, 0.0, 0.735, 1.0]));
+    assert!(near(&red.mix(&blue, 0.5, ColorSpace::Hsl), [1.0, 0.0, 1.0, 1.0]));
+    assert!(near(&black.mix(&white, 0.0, ColorSpace::Oklab), [0.0, 0.0, 0.0, 1.0]));
+    assert!(near(&black.mix(&white, 1.0, ColorSpace::Oklab), [1.0, 1.0, 1.0, 1.0]));
+    // 与透明色混合时不会变暗
+    assert!(near(&red.mix(&CgColor::new(0.0, 0.0, 0.0, 0.0), 0.5, ColorSpace::Srgb), [1.0, 0.0, 0.0, 0.5]));
+
+    // 渐变取色
+    let gradient = LinearGradientColor {
+        direction: 0.0,
+        list: vec![
+            ColorAndPosition { position: 0.2, rgba: red.clone() },
+            ColorAndPosition { position: 0.6, rgba: blue.clone() },
+        ],
+        repeat: false,
+        stops: Vec::new(),
+        corner: None,
+    };
+    assert!(near(&gradient.sample(0.0), [1.0, 0.0, 0.0, 1.0]));
+    assert!(near(&gradient.sample(0.4), [0.5, 0.0, 0.5, 1.0]));
+    assert!(near(&gradient.sample(1.0), [0.0, 0.0, 1.0, 1.0]));
+    let gradient = LinearGradientColor { repeat: true, ..gradient };
+    assert!(near(&gradient.sample(0.8), [0.5, 0.0, 0.5, 1.0]));
+    assert!(near(&gradient.sample(0.1), [0.25, 0.0, 0.75, 1.0]));
+
+    // Hsi转颜色矩阵
+    assert!(near(&apply_color_matrix(&Hsi::default().to_color_matrix(), &c), [0.5, 0.25, 1.0, 0.5]));
+    let gray = apply_color_matrix(&Hsi { saturate: -1.0, ..Default::default() }.to_color_matrix(), &red);
+    assert!(near(&gray, [0.213, 0.213, 0.213, 1.0]));
+    let r = apply_color_matrix(&Hsi { hue_rotate: Turn(1.0), ..Default::default() }.to_color_matrix(), &c);
+    assert!(near(&r, [0.5, 0.25, 1.0, 0.5]));
+    assert!(near(&apply_color_matrix(&Hsi { bright_ness: 1.0, ..Default::default() }.to_color_matrix(), &red), [1.0, 1.0, 1.0, 1.0]));
+    assert!(near(&apply_color_matrix(&Hsi { bright_ness: -0.5, ..Default::default() }.to_color_matrix(), &red), [0.5, 0.0, 0.0, 1.0]));
+}
@@ -0,0 +1,1111 @@
+//! 将样式序列化为css字符串
+//!
+//! 输出的css可以被style_parse再次解析。
+//! 部分样式在css中没有对应的属性（如NodeState），序列化时被忽略；
+//! 部分样式的解析是有损的（如transition-property中的margin、padding等组合属性），序列化时无法还原。
+
+use std::fmt::{self, Debug, Write};
+
+use cssparser::{serialize_identifier, serialize_string};
+use pi_atom::Atom;
+use pi_flex_layout::{
+    prelude::Number,
+    style::{Dimension, Display, FlexWrap},
+};
+
+use crate::style::{
+    AnimationDirection, AnimationFillMode, AnimationPlayState, AnimationTimingFunction, AsImage, BaseShape, BlendMode, BorderRadius, CalcExpr, Center, CgColor, Color, ColorAndPosition,
+    ConicGradientColor,     Enable, FitType, FontSize, FontStyle, GradientStop, ImageRepeat, ImageRepeatOption, IterationCount, LayoutDimension, LengthUnit, LineHeight, LinearGradientColor, MaskImage,
+    NotNanRect, RadialGradientColor, RadialGradientShape, RadialGradientSize, StyleType, TextAlign, TextOverflow, Time, TransformFunc, TransformOrigin, TransformStyle, VerticalAlign, WhiteSpace, GUI_STYLE_COUNT,
+};
+use crate::style_parse::{Attribute, ClassMap, KeyFrameList};
+use crate::style_media::{MediaFeature, MediaQuery, MediaQueryList, MediaRange, MediaType};
+use crate::style_selector::{Combinator, CompoundSelector, Selector};
+use crate::style_var::VarDeclarations;
+
+/// 序列化为css字符串
+pub trait ToCss {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result;
+
+    fn to_css_string(&self) -> String {
+        let mut s = String::new();
+        self.to_css(&mut s).unwrap();
+        s
+    }
+}
+
+impl ToCss for f32 {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result { write!(dest, "{}", self) }
+}
+
+impl ToCss for CgColor {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        let to_u8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        // 不透明的颜色输出为16进制，可以被无损的解析回来
+        if self.w >= 1.0 {
+            write!(dest, "#{:02x}{:02x}{:02x}", to_u8(self.x), to_u8(self.y), to_u8(self.z))
+        } else {
+            write!(dest, "rgba({}, {}, {}, {})", to_u8(self.x), to_u8(self.y), to_u8(self.z), self.w)
+        }
+    }
+}
+
+impl ToCss for LinearGradientColor {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        write_repeating(self.repeat, dest)?;
+        // 解析时，direction为css角度减去90度
+        match self.corner {
+            Some((x, y)) => write!(dest, "linear-gradient(to {} {}", if x < 0 { "left" } else { "right" }, if y < 0 { "top" } else { "bottom" })?,
+            None => write!(dest, "linear-gradient({}deg", self.direction + 90.0)?,
+        }
+        if self.stops.is_empty() {
+            write_stops(&self.list, dest, |r, dest| write_percent(r, dest))?;
+        } else {
+            for stop in self.stops.iter() {
+                dest.write_str(", ")?;
+                match stop {
+                    GradientStop::Color(rgba, position) => {
+                        rgba.to_css(dest)?;
+                        if let Some(r) = position {
+                            dest.write_char(' ')?;
+                            r.to_css(dest)?;
+                        }
+                    }
+                    GradientStop::Hint(r) => r.to_css(dest)?,
+                }
+            }
+        }
+        dest.write_char(')')
+    }
+}
+
+impl ToCss for RadialGradientColor {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        write_repeating(self.repeat, dest)?;
+        dest.write_str("radial-gradient(")?;
+        dest.write_str(match self.shape {
+            RadialGradientShape::Circle => "circle",
+            RadialGradientShape::Ellipse => "ellipse",
+        })?;
+        dest.write_str(match self.size {
+            RadialGradientSize::ClosestSide => " closest-side",
+            RadialGradientSize::FarthesSide => " farthest-side",
+            RadialGradientSize::ClosestCorner => " closest-corner",
+            RadialGradientSize::Farthescorner => " farthest-corner",
+        })?;
+        write_gradient_center(&self.center, dest)?;
+        write_stops(&self.list, dest, |r, dest| write_percent(r, dest))?;
+        dest.write_char(')')
+    }
+}
+
+impl ToCss for ConicGradientColor {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        write_repeating(self.repeat, dest)?;
+        write!(dest, "conic-gradient(from {}deg", self.from)?;
+        write_gradient_center(&self.center, dest)?;
+        // 位置0~1对应0~360度
+        write_stops(&self.list, dest, |r, dest| write!(dest, "{}deg", r * 360.0))?;
+        dest.write_char(')')
+    }
+}
+
+fn write_repeating<W: Write>(repeat: bool, dest: &mut W) -> fmt::Result {
+    if repeat {
+        dest.write_str("repeating-")?;
+    }
+    Ok(())
+}
+
+fn write_gradient_center<W: Write>(center: &Center, dest: &mut W) -> fmt::Result {
+    dest.write_str(" at ")?;
+    center.x.to_css(dest)?;
+    dest.write_char(' ')?;
+    center.y.to_css(dest)
+}
+
+fn write_stops<W: Write>(list: &[ColorAndPosition], dest: &mut W, position: impl Fn(f32, &mut W) -> fmt::Result) -> fmt::Result {
+    for stop in list.iter() {
+        dest.write_str(", ")?;
+        stop.rgba.to_css(dest)?;
+        dest.write_char(' ')?;
+        position(stop.position, dest)?;
+    }
+    Ok(())
+}
+
+impl ToCss for Color {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            Color::RGBA(r) => r.to_css(dest),
+            Color::LinearGradient(r) => r.to_css(dest),
+            Color::RadialGradient(r) => r.to_css(dest),
+            Color::ConicGradient(r) => r.to_css(dest),
+        }
+    }
+}
+
+impl ToCss for MaskImage {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            MaskImage::Path(r) => write_url(r, dest),
+            MaskImage::LinearGradient(r) => r.to_css(dest),
+            MaskImage::RadialGradient(r) => r.to_css(dest),
+            MaskImage::ConicGradient(r) => r.to_css(dest),
+        }
+    }
+}
+
+impl ToCss for LengthUnit {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            LengthUnit::Pixel(r) => write!(dest, "{}px", r),
+            LengthUnit::Percent(r) => write_percent(*r, dest),
+            LengthUnit::Calc(r) => r.to_css(dest),
+        }
+    }
+}
+
+impl ToCss for CalcExpr {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            CalcExpr::Value { pixel, percent } if *percent == 0.0 => write!(dest, "{}px", pixel),
+            CalcExpr::Value { pixel, percent } if *pixel == 0.0 => write_percent(*percent, dest),
+            CalcExpr::Min(list) => write_function("min", &list.iter().collect::<Vec<_>>(), dest),
+            CalcExpr::Max(list) => write_function("max", &list.iter().collect::<Vec<_>>(), dest),
+            CalcExpr::Clamp(min, value, max) => write_function("clamp", &[&**min, &**value, &**max], dest),
+            _ => {
+                dest.write_str("calc(")?;
+                write_calc_sum(self, dest)?;
+                dest.write_char(')')
+            }
+        }
+    }
+}
+
+impl ToCss for Dimension {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            // Undefined在css中没有对应的写法，按auto输出
+            Dimension::Undefined | Dimension::Auto => dest.write_str("auto"),
+            Dimension::Points(r) => write!(dest, "{}px", r),
+            Dimension::Percent(r) => write_percent(*r, dest),
+        }
+    }
+}
+
+impl ToCss for LayoutDimension {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            LayoutDimension::Undefined | LayoutDimension::Auto => dest.write_str("auto"),
+            LayoutDimension::Points(r) => write!(dest, "{}px", r),
+            LayoutDimension::Percent(r) => write_percent(*r, dest),
+            LayoutDimension::Calc(r) => r.to_css(dest),
+        }
+    }
+}
+
+impl ToCss for TransformFunc {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            TransformFunc::TranslateX(x) => write_function("translateX", &[x], dest),
+            TransformFunc::TranslateY(y) => write_function("translateY", &[y], dest),
+            TransformFunc::Translate(x, y) => write_function("translate", &[x, y], dest),
+            TransformFunc::ScaleX(x) => write_function("scaleX", &[x], dest),
+            TransformFunc::ScaleY(y) => write_function("scaleY", &[y], dest),
+            TransformFunc::Scale(x, y) => write_function("scale", &[x, y], dest),
+            TransformFunc::RotateX(r) => write!(dest, "rotateX({}deg)", r),
+            TransformFunc::RotateY(r) => write!(dest, "rotateY({}deg)", r),
+            TransformFunc::RotateZ(r) => write!(dest, "rotateZ({}deg)", r),
+            TransformFunc::SkewX(r) => write!(dest, "skewX({}deg)", r),
+            TransformFunc::SkewY(r) => write!(dest, "skewY({}deg)", r),
+            TransformFunc::TranslateZ(z) => write!(dest, "translateZ({}px)", z),
+            TransformFunc::Translate3D(x, y, z) => {
+                dest.write_str("translate3d(")?;
+                x.to_css(dest)?;
+                dest.write_str(", ")?;
+                y.to_css(dest)?;
+                write!(dest, ", {}px)", z)
+            }
+            TransformFunc::ScaleZ(z) => write_function("scaleZ", &[z], dest),
+            TransformFunc::Scale3D(x, y, z) => write_function("scale3d", &[x, y, z], dest),
+            TransformFunc::Rotate3D(x, y, z, r) => write!(dest, "rotate3d({}, {}, {}, {}deg)", x, y, z, r),
+            TransformFunc::Skew(x, y) => write!(dest, "skew({}deg, {}deg)", x, y),
+            TransformFunc::Perspective(r) => write_perspective("perspective(", *r, ")", dest),
+            TransformFunc::Matrix(a, b, c, d, e, f) => write_function("matrix", &[a, b, c, d, e, f], dest),
+            TransformFunc::Matrix3D(m) => write_function("matrix3d", &m.iter().collect::<Vec<_>>(), dest),
+        }
+    }
+}
+
+impl ToCss for TransformOrigin {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            TransformOrigin::Center => dest.write_str("center"),
+            TransformOrigin::XY(x, y) => {
+                x.to_css(dest)?;
+                dest.write_char(' ')?;
+                y.to_css(dest)
+            }
+        }
+    }
+}
+
+impl ToCss for BorderRadius {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        write_separated(&self.x, " ", dest)?;
+        dest.write_str(" / ")?;
+        write_separated(&self.y, " ", dest)
+    }
+}
+
+impl ToCss for BaseShape {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            BaseShape::Circle { radius, center } => {
+                dest.write_str("circle(")?;
+                radius.to_css(dest)?;
+                write_center(&center.x, &center.y, dest)?;
+            }
+            BaseShape::Ellipse { rx, ry, center } => {
+                dest.write_str("ellipse(")?;
+                rx.to_css(dest)?;
+                dest.write_char(' ')?;
+                ry.to_css(dest)?;
+                write_center(&center.x, &center.y, dest)?;
+            }
+            BaseShape::Inset { rect_box, border_radius } => {
+                dest.write_str("inset(")?;
+                write_separated(rect_box, " ", dest)?;
+                dest.write_str(" round ")?;
+                border_radius.to_css(dest)?;
+            }
+            BaseShape::Sector { rotate, angle, radius, center } => {
+                write!(dest, "sector({}deg {}deg ", rotate.0, angle.0)?;
+                radius.to_css(dest)?;
+                write_center(&center.x, &center.y, dest)?;
+            }
+        }
+        dest.write_char(')')
+    }
+}
+
+impl ToCss for Time {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result { write!(dest, "{}ms", self.0) }
+}
+
+impl ToCss for IterationCount {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        if self.0.is_infinite() {
+            dest.write_str("infinite")
+        } else {
+            write!(dest, "{}", self.0)
+        }
+    }
+}
+
+impl ToCss for AnimationTimingFunction {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            AnimationTimingFunction::Linear => dest.write_str("linear"),
+            AnimationTimingFunction::Ease(r) => write_keyword(r, dest),
+            AnimationTimingFunction::Step(count, mode) => {
+                write!(dest, "steps({}, ", count)?;
+                write_keyword(mode, dest)?;
+                dest.write_char(')')
+            }
+            AnimationTimingFunction::CubicBezier(x1, y1, x2, y2) => write!(dest, "cubic-bezier({}, {}, {}, {})", x1, y1, x2, y2),
+            AnimationTimingFunction::LinearPoints(points) => {
+                dest.write_str("linear(")?;
+                for (i, (input, output)) in points.iter().enumerate() {
+                    if i > 0 {
+                        dest.write_str(", ")?;
+                    }
+                    write!(dest, "{} ", output)?;
+                    write_percent(*input, dest)?;
+                }
+                dest.write_char(')')
+            }
+        }
+    }
+}
+
+impl ToCss for AnimationDirection {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        dest.write_str(match self {
+            AnimationDirection::Normal => "normal",
+            AnimationDirection::Reverse => "reverse",
+            AnimationDirection::Alternate => "alternate",
+            AnimationDirection::AlternateReverse => "alternate-reverse",
+        })
+    }
+}
+
+impl ToCss for AnimationFillMode {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        dest.write_str(match self {
+            AnimationFillMode::None => "none",
+            AnimationFillMode::Forwards => "forwards",
+            AnimationFillMode::Backwards => "backwards",
+            AnimationFillMode::Both => "both",
+        })
+    }
+}
+
+impl ToCss for AnimationPlayState {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        dest.write_str(match self {
+            AnimationPlayState::Running => "running",
+            AnimationPlayState::Paused => "paused",
+        })
+    }
+}
+
+impl ToCss for Atom {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        let value: &str = self.as_ref();
+        serialize_identifier(value, dest)
+    }
+}
+
+impl ToCss for Attribute {
+    /// 输出 `属性名: 属性值`，不含分号；没有对应css属性的样式不输出任何内容
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        match self {
+            Attribute::BackgroundRepeat(r) => {
+                dest.write_str("background-repeat: ")?;
+                write_image_repeat(&r.0, dest)
+            }
+            Attribute::FontStyle(r) => write!(
+                dest,
+                "font-style: {}",
+                match r.0 {
+                    FontStyle::Normal => "normal",
+                    FontStyle::Ttalic => "italic",
+                    FontStyle::Oblique => "oblique",
+                }
+            ),
+            Attribute::FontWeight(r) => write!(dest, "font-weight: {}", r.0),
+            Attribute::FontSize(r) => match r.0 {
+                FontSize::None => dest.write_str("font-size: medium"),
+                FontSize::Length(r) => write!(dest, "font-size: {}px", r),
+                FontSize::Percent(r) => write_decl("font-size", &Percent(r), dest),
+            },
+            Attribute::FontFamily(r) => write_decl("font-family", &r.0, dest),
+            Attribute::LetterSpacing(r) => write!(dest, "letter-spacing: {}px", r.0),
+            Attribute::WordSpacing(r) => write!(dest, "word-spacing: {}px", r.0),
+            Attribute::LineHeight(r) => match r.0 {
+                LineHeight::Normal => dest.write_str("line-height: normal"),
+                LineHeight::Length(r) => write!(dest, "line-height: {}px", r),
+                LineHeight::Number(r) => write!(dest, "line-height: {}", r),
+                LineHeight::Percent(r) => write_decl("line-height", &Percent(r), dest),
+            },
+            Attribute::TextIndent(r) => write!(dest, "text-indent: {}px", r.0),
+            Attribute::WhiteSpace(r) => write!(
+                dest,
+                "white-space: {}",
+                match r.0 {
+                    WhiteSpace::Normal => "normal",
+                    WhiteSpace::Nowrap => "nowrap",
+                    WhiteSpace::PreWrap => "pre-wrap",
+                    WhiteSpace::Pre => "pre",
+                    WhiteSpace::PreLine => "pre-line",
+                }
+            ),
+            Attribute::TextAlign(r) => write!(
+                dest,
+                "text-align: {}",
+                match r.0 {
+                    TextAlign::Left => "left",
+                    TextAlign::Right => "right",
+                    TextAlign::Center => "center",
+                    TextAlign::Justify => "justify",
+                }
+            ),
+            Attribute::VerticalAlign(r) => write!(
+                dest,
+                "vertical-align: {}",
+                match r.0 {
+                    VerticalAlign::Top => "top",
+                    VerticalAlign::Middle => "middle",
+                    VerticalAlign::Bottom => "bottom",
+                }
+            ),
+            Attribute::Color(r) => match &r.0 {
+                Color::RGBA(r) => write_decl("color", r, dest),
+                r => write_decl("text-gradient", r, dest),
+            },
+            Attribute::TextStroke(r) => {
+                write!(dest, "text-stroke: {}px ", *r.width)?;
+                r.color.to_css(dest)
+            }
+            Attribute::TextShadow(r) => {
+                dest.write_str("text-shadow: ")?;
+                for (i, shadow) in r.0.iter().enumerate() {
+                    if i > 0 {
+                        dest.write_str(", ")?;
+                    }
+                    write!(dest, "{}px {}px {}px ", shadow.h, shadow.v, shadow.blur)?;
+                    shadow.color.to_css(dest)?;
+                }
+                Ok(())
+            }
+            Attribute::BackgroundImage(r) => {
+                dest.write_str("background-image: ")?;
+                write_url(&r.0, dest)
+            }
+            Attribute::BackgroundImageClip(r) => {
+                dest.write_str("background-image-clip: ")?;
+                write_rect_percent(&r.0, dest)
+            }
+            Attribute::ObjectFit(r) => write!(
+                dest,
+                "object-fit: {}",
+                match r.0 {
+                    FitType::None => "none",
+                    FitType::Fill => "fill",
+                    FitType::Contain => "contain",
+                    FitType::Cover => "cover",
+                    FitType::ScaleDown => "scale-down",
+                }
+            ),
+            Attribute::BackgroundColor(r) => match &r.0 {
+                Color::RGBA(r) => write_decl("background-color", r, dest),
+                r => write_decl("background", r, dest),
+            },
+            Attribute::BoxShadow(r) => {
+                write!(dest, "box-shadow: {}px {}px {}px {}px ", r.h, r.v, r.blur, r.spread)?;
+                r.color.to_css(dest)
+            }
+            Attribute::BorderImage(r) => {
+                dest.write_str("border-image: ")?;
+                write_url(&r.0, dest)
+            }
+            Attribute::BorderImageClip(r) => {
+                dest.write_str("border-image-clip: ")?;
+                write_rect_percent(&r.0, dest)
+            }
+            Attribute::BorderImageSlice(r) => {
+                dest.write_str("border-image-slice: ")?;
+                write_separated(&[Percent(*r.top), Percent(*r.right), Percent(*r.bottom), Percent(*r.left)], " ", dest)?;
+                if r.fill {
+                    dest.write_str(" fill")?;
+                }
+                Ok(())
+            }
+            Attribute::BorderImageRepeat(r) => {
+                dest.write_str("border-image-repeat: ")?;
+                write_image_repeat(&r.0, dest)
+            }
+            Attribute::BorderColor(r) => write_decl("border-color", &r.0, dest),
+            // 与解析一致，hsi中色相单位为度，饱和度、亮度为-100~100
+            Attribute::Hsi(r) => write!(
+                dest,
+                "filter: hsi({}, {}, {})",
+                r.hue_rotate.to_deg().0,
+                r.saturate * 100.0,
+                r.bright_ness * 100.0
+            ),
+            Attribute::Blur(r) => write!(dest, "filter: blur({}px)", r.0),
+            Attribute::MaskImage(r) => write_decl("mask-image", &r.0, dest),
+            Attribute::MaskImageClip(r) => {
+                dest.write_str("mask-image-clip: ")?;
+                write_rect_percent(&r.0, dest)
+            }
+            Attribute::Transform(r) => {
+                dest.write_str("transform: ")?;
+                write_separated(&r.0, " ", dest)
+            }
+            Attribute::TransformOrigin(r) => write_decl("transform-origin", &r.0, dest),
+            Attribute::TransformWillChange(r) => {
+                if r.0 {
+                    dest.write_str("will-change-transform")?;
+                }
+                Ok(())
+            }
+            Attribute::BorderRadius(r) => write_decl("border-radius", &r.0, dest),
+            Attribute::ZIndex(r) => write!(dest, "z-index: {}", r.0),
+            Attribute::Overflow(r) => write!(dest, "overflow: {}", if r.0 { "hidden" } else { "visible" }),
+            Attribute::BlendMode(r) => write!(
+                dest,
+                "blend-mode: {}",
+                match r.0 {
+                    BlendMode::Normal => "normal",
+                    BlendMode::AlphaAdd => "alpha-add",
+                    BlendMode::Subtract => "subtract",
+                    BlendMode::Multiply => "multiply",
+                    BlendMode::OneOne => "one-one",
+                }
+            ),
+            Attribute::Display(r) => write!(
+                dest,
+                "display: {}",
+                match r.0 {
+                    Display::Flex => "flex",
+                    Display::Grid => "grid",
+                    Display::None => "none",
+                }
+            ),
+            Attribute::Visibility(r) => write!(dest, "visibility: {}", if r.0 { "visible" } else { "hidden" }),
+            Attribute::Enable(r) => write!(
+                dest,
+                "pointer-events: {}",
+                match r.0 {
+                    Enable::Auto => "auto",
+                    Enable::None => "none",
+                    Enable::Visible => "visible",
+                }
+            ),
+            Attribute::Width(r) => write_decl("width", &r.0, dest),
+            Attribute::Height(r) => write_decl("height", &r.0, dest),
+            Attribute::MarginTop(r) => write_decl("margin-top", &r.0, dest),
+            Attribute::MarginRight(r) => write_decl("margin-right", &r.0, dest),
+            Attribute::MarginBottom(r) => write_decl("margin-bottom", &r.0, dest),
+            Attribute::MarginLeft(r) => write_decl("margin-left", &r.0, dest),
+            Attribute::PaddingTop(r) => write_decl("padding-top", &r.0, dest),
+            Attribute::PaddingRight(r) => write_decl("padding-right", &r.0, dest),
+            Attribute::PaddingBottom(r) => write_decl("padding-bottom", &r.0, dest),
+            Attribute::PaddingLeft(r) => write_decl("padding-left", &r.0, dest),
+            Attribute::BorderTop(r) => write_decl("border-top", &r.0, dest),
+            Attribute::BorderRight(r) => write_decl("border-right", &r.0, dest),
+            Attribute::BorderBottom(r) => write_decl("border-bottom", &r.0, dest),
+            Attribute::BorderLeft(r) => write_decl("border-left", &r.0, dest),
+            Attribute::PositionTop(r) => write_decl("top", &r.0, dest),
+            Attribute::PositionRight(r) => write_decl("right", &r.0, dest),
+            Attribute::PositionBottom(r) => write_decl("bottom", &r.0, dest),
+            Attribute::PositionLeft(r) => write_decl("left", &r.0, dest),
+            Attribute::MinWidth(r) => write_decl("min-width", &r.0, dest),
+            Attribute::MinHeight(r) => write_decl("min-height", &r.0, dest),
+            Attribute::MaxHeight(r) => write_decl("max-height", &r.0, dest),
+            Attribute::MaxWidth(r) => write_decl("max-width", &r.0, dest),
+            Attribute::Direction(r) => write_keyword_decl("direction", &r.0, dest),
+            Attribute::FlexDirection(r) => write_keyword_decl("flex-direction", &r.0, dest),
+            Attribute::FlexWrap(r) => match r.0 {
+                // Debug名为NoWrap，css中为nowrap
+                FlexWrap::NoWrap => dest.write_str("flex-wrap: nowrap"),
+                _ => write_keyword_decl("flex-wrap", &r.0, dest),
+            },
+            Attribute::JustifyContent(r) => write_keyword_decl("justify-content", &r.0, dest),
+            Attribute::AlignContent(r) => write_keyword_decl("align-content", &r.0, dest),
+            Attribute::AlignItems(r) => write_keyword_decl("align-items", &r.0, dest),
+            Attribute::PositionType(r) => write_keyword_decl("position", &r.0, dest),
+            Attribute::AlignSelf(r) => write_keyword_decl("align-self", &r.0, dest),
+            Attribute::FlexShrink(r) => write!(dest, "flex-shrink: {}", r.0),
+            Attribute::FlexGrow(r) => write!(dest, "flex-grow: {}", r.0),
+            Attribute::AspectRatio(r) => match r.0 {
+                Number::Defined(r) => write!(dest, "aspect-ratio: {}", r),
+                Number::Undefined => dest.write_str("aspect-ratio: auto"),
+            },
+            Attribute::Order(r) => write!(dest, "order: {}", r.0),
+            Attribute::FlexBasis(r) => write_decl("flex-basis", &r.0, dest),
+            Attribute::Opacity(r) => write!(dest, "opacity: {}", r.0),
+            Attribute::TextContent(r) => {
+                dest.write_str("content: ")?;
+                serialize_string(&r.0 .0, dest)
+            }
+            // 节点状态不是css属性
+            Attribute::VNode(_) => Ok(()),
+            Attribute::AnimationName(r) => {
+                dest.write_str("animation-name: ")?;
+                write_separated(&r.value, ", ", dest)
+            }
+            Attribute::AnimationDuration(r) => write_list_decl("animation-duration", &r.0, dest),
+            Attribute::AnimationTimingFunction(r) => write_list_decl("animation-timing-function", &r.0, dest),
+            Attribute::AnimationDelay(r) => write_list_decl("animation-delay", &r.0, dest),
+            Attribute::AnimationIterationCount(r) => write_list_decl("animation-iteration-count", &r.0, dest),
+            Attribute::AnimationDirection(r) => write_list_decl("animation-direction", &r.0, dest),
+            Attribute::AnimationFillMode(r) => write_list_decl("animation-fill-mode", &r.0, dest),
+            Attribute::AnimationPlayState(r) => write_list_decl("animation-play-state", &r.0, dest),
+            Attribute::ClipPath(r) => write_decl("clip-path", &r.0, dest),
+            Attribute::Translate(r) => {
+                dest.write_str("translate: ")?;
+                write_separated(&r.0, " ", dest)
+            }
+            Attribute::Scale(r) => {
+                dest.write_str("scale: ")?;
+                write_separated(&r.0, " ", dest)
+            }
+            Attribute::Rotate(r) => write!(dest, "rotate: {}deg", r.0),
+            Attribute::AsImage(r) => write!(
+                dest,
+                "as-image: {}",
+                match r.0 {
+                    AsImage::None => "none",
+                    AsImage::Advise => "advise",
+                    AsImage::Force => "force",
+                }
+            ),
+            Attribute::TextOverflow(r) => match &r.0 {
+                TextOverflow::None => dest.write_str("text-overflow: none"),
+                TextOverflow::Clip => dest.write_str("text-overflow: clip"),
+                TextOverflow::Ellipsis => dest.write_str("text-overflow: ellipsis"),
+                TextOverflow::Custom(r) => {
+                    dest.write_str("text-overflow: ")?;
+                    serialize_string(r, dest)
+                }
+            },
+            Attribute::OverflowWrap(r) => write_keyword_decl("overflow-wrap", &r.0, dest),
+            Attribute::TransitionProperty(r) => {
+                dest.write_str("transition-property: ")?;
+                for (i, property) in r.0.iter().enumerate() {
+                    if i > 0 {
+                        dest.write_str(", ")?;
+                    }
+                    dest.write_str(transition_property_name(*property))?;
+                }
+                Ok(())
+            }
+            Attribute::TransitionDuration(r) => write_list_decl("transition-duration", &r.0, dest),
+            Attribute::TransitionTimingFunction(r) => write_list_decl("transition-timing-function", &r.0, dest),
+            Attribute::TransitionDelay(r) => write_list_decl("transition-delay", &r.0, dest),
+            Attribute::TextOuterGlow(r) => {
+                dest.write_str("text-outer-grow: ")?;
+                r.color.to_css(dest)?;
+                write!(dest, " {}px {}", r.distance, r.intensity)
+            }
+            Attribute::RowGap(r) => write!(dest, "row-gap: {}px", r.0),
+            Attribute::ColumnGap(r) => write!(dest, "column-gap: {}px", r.0),
+            Attribute::AutoReduce(r) => write!(dest, "auto-reduce: {}", r.0),
+            Attribute::Perspective(r) => write_perspective("perspective: ", r.0, "", dest),
+            Attribute::TransformStyle(r) => write!(
+                dest,
+                "transform-style: {}",
+                match r.0 {
+                    TransformStyle::Flat => "flat",
+                    TransformStyle::Preserve3D => "preserve-3d",
+                }
+            ),
+        }
+    }
+}
+
+impl ToCss for KeyFrameList {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        // 按名称、进度排序，保证输出稳定
+        let mut names: Vec<&Atom> = self.frames.keys().collect();
+        names.sort_by(|a, b| {
+            let (a, b): (&str, &str) = (a.as_ref(), b.as_ref());
+            a.cmp(b)
+        });
+        for name in names {
+            let mut frames: Vec<_> = self.frames[name].iter().collect();
+            frames.sort_by_key(|(progress, _)| **progress);
+
+            let timing_functions = self.timing_functions.get(name);
+            let viewport_vars = self.viewport_vars.get(name);
+
+            dest.write_str("@keyframes ")?;
+            name.to_css(dest)?;
+            dest.write_str(" {\n")?;
+            for (progress, attrs) in frames {
+                dest.write_char('\t')?;
+                write_percent(**progress, dest)?;
+                dest.write_str(" {\n")?;
+                match viewport_vars.and_then(|r| r.get(progress)) {
+                    Some(vars) => write_var_declarations(attrs.iter(), vars, "\t\t", dest)?,
+                    None => write_declarations(attrs.iter(), "\t\t", dest)?,
+                }
+                if let Some(r) = timing_functions.and_then(|r| r.get(progress)) {
+                    dest.write_str("\t\tanimation-timing-function: ")?;
+                    r.to_css(dest)?;
+                    dest.write_str(";\n")?;
+                }
+                dest.write_str("\t}\n")?;
+            }
+            dest.write_str("}\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl ToCss for ClassMap {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        if !self.root_vars.is_empty() {
+            let mut vars: Vec<(&String, &String)> = self.root_vars.vars.iter().collect();
+            vars.sort();
+            dest.write_str(":root {\n")?;
+            for (name, value) in vars {
+                writeln!(dest, "\t{}: {};", name, value)?;
+            }
+            dest.write_str("}\n")?;
+        }
+        let mut start = 0;
+        for class in self.classes.iter() {
+            let end = (start + class.count).min(self.attrs.len());
+            write_class_name(self, class.class_name, dest)?;
+            for name in class.state.names() {
+                dest.write_char(':')?;
+                dest.write_str(name)?;
+            }
+            dest.write_str(" {\n")?;
+            write_var_declarations(self.attrs.range(start..end), &class.vars, "\t", dest)?;
+            dest.write_str("}\n")?;
+            start = end;
+        }
+        let mut start = 0;
+        for item in self.selectors.iter() {
+            let end = (start + item.count).min(self.selector_attrs.len());
+            write_selector(self, &item.selector, dest)?;
+            dest.write_str(" {\n")?;
+            write_var_declarations(self.selector_attrs.range(start..end), &item.vars, "\t", dest)?;
+            dest.write_str("}\n")?;
+            start = end;
+        }
+        for group in self.media.iter() {
+            dest.write_str("@media ")?;
+            group.query.to_css(dest)?;
+            dest.write_str(" {\n")?;
+            group.class_map.to_css(dest)?;
+            dest.write_str("}\n")?;
+        }
+        self.key_frames.to_css(dest)
+    }
+}
+
+impl ToCss for MediaQueryList {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        if self.0.is_empty() {
+            return dest.write_str("all");
+        }
+        write_separated(&self.0, ", ", dest)
+    }
+}
+
+impl ToCss for MediaQuery {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        let mut first = true;
+        if self.not {
+            dest.write_str("not ")?;
+        }
+        if self.not || self.media_type != MediaType::All || self.features.is_empty() {
+            write_keyword(&self.media_type, dest)?;
+            first = false;
+        }
+        for feature in self.features.iter() {
+            if !first {
+                dest.write_str(" and ")?;
+            }
+            feature.to_css(dest)?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+impl ToCss for MediaFeature {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result {
+        let write_range = |name: &str, range: &MediaRange, dest: &mut W| match range {
+            MediaRange::Min => write!(dest, "(min-{}: ", name),
+            MediaRange::Max => write!(dest, "(max-{}: ", name),
+            MediaRange::Exact => write!(dest, "({}: ", name),
+        };
+        match self {
+            MediaFeature::Width(range, r) => {
+                write_range("width", range, dest)?;
+                write!(dest, "{}px)", r)
+            }
+            MediaFeature::Height(range, r) => {
+                write_range("height", range, dest)?;
+                write!(dest, "{}px)", r)
+            }
+            MediaFeature::Resolution(range, r) => {
+                write_range("resolution", range, dest)?;
+                write!(dest, "{}dppx)", r)
+            }
+            MediaFeature::Orientation(r) => {
+                dest.write_str("(orientation: ")?;
+                write_keyword(r, dest)?;
+                dest.write_char(')')
+            }
+            MediaFeature::Platform(r) => {
+                dest.write_str("(platform: ")?;
+                serialize_identifier(r, dest)?;
+                dest.write_char(')')
+            }
+            MediaFeature::Lang(r) => {
+                dest.write_str("(lang: ")?;
+                serialize_identifier(r, dest)?;
+                dest.write_char(')')
+            }
+        }
+    }
+}
+
+/// 输出`.name`，没有记录class名时输出`.cNNN`
+fn write_class_name<W: Write>(class_map: &ClassMap, class_name: usize, dest: &mut W) -> fmt::Result {
+    match class_map.class_name(class_name) {
+        Some(name) => {
+            dest.write_char('.')?;
+            serialize_identifier(name, dest)
+        }
+        None => write!(dest, ".c{}", class_name),
+    }
+}
+
+fn write_compound_selector<W: Write>(class_map: &ClassMap, compound: &CompoundSelector, dest: &mut W) -> fmt::Result {
+    for class_name in compound.classes.iter() {
+        write_class_name(class_map, *class_name, dest)?;
+    }
+    Ok(())
+}
+
+fn write_selector<W: Write>(class_map: &ClassMap, selector: &Selector, dest: &mut W) -> fmt::Result {
+    for (combinator, compound) in selector.ancestors.iter().rev() {
+        write_compound_selector(class_map, compound, dest)?;
+        match combinator {
+            Combinator::Descendant => dest.write_char(' ')?,
+            Combinator::Child => dest.write_str(" > ")?,
+        }
+    }
+    write_compound_selector(class_map, &selector.compound, dest)
+}
+
+impl ClassMap {
+    /// 序列化为css字符串，每个class输出为`.name { ... }`（没有记录class名时为`.cNNN`），其后依次输出选择器，关键帧输出为`@keyframes`
+    pub fn to_css_string(&self) -> String { <Self as ToCss>::to_css_string(self) }
+}
+
+/// 百分比（0~1）
+struct Percent(f32);
+
+impl ToCss for Percent {
+    fn to_css<W: Write>(&self, dest: &mut W) -> fmt::Result { write_percent(self.0, dest) }
+}
+
+fn write_percent<W: Write>(value: f32, dest: &mut W) -> fmt::Result { write!(dest, "{}%", value * 100.0) }
+
+fn write_decl<T: ToCss + ?Sized, W: Write>(name: &str, value: &T, dest: &mut W) -> fmt::Result {
+    dest.write_str(name)?;
+    dest.write_str(": ")?;
+    value.to_css(dest)
+}
+
+fn write_list_decl<T: ToCss, W: Write>(name: &str, list: &[T], dest: &mut W) -> fmt::Result {
+    dest.write_str(name)?;
+    dest.write_str(": ")?;
+    write_separated(list, ", ", dest)
+}
+
+fn write_keyword_decl<T: Debug, W: Write>(name: &str, value: &T, dest: &mut W) -> fmt::Result {
+    dest.write_str(name)?;
+    dest.write_str(": ")?;
+    write_keyword(value, dest)
+}
+
+fn write_separated<T: ToCss, W: Write>(list: &[T], separator: &str, dest: &mut W) -> fmt::Result {
+    for (i, item) in list.iter().enumerate() {
+        if i > 0 {
+            dest.write_str(separator)?;
+        }
+        item.to_css(dest)?;
+    }
+    Ok(())
+}
+
+/// 输出calc()中的表达式，不含外层的calc()
+fn write_calc_sum<W: Write>(expr: &CalcExpr, dest: &mut W) -> fmt::Result {
+    match expr {
+        CalcExpr::Value { pixel, percent } if *pixel != 0.0 && *percent != 0.0 => {
+            write_percent(*percent, dest)?;
+            write!(dest, " {} {}px", if *pixel < 0.0 { '-' } else { '+' }, pixel.abs())
+        }
+        CalcExpr::Add(r1, r2) => {
+            write_calc_sum(r1, dest)?;
+            dest.write_str(" + ")?;
+            write_calc_sum(r2, dest)
+        }
+        CalcExpr::Mul(r, factor) => {
+            r.to_css(dest)?;
+            write!(dest, " * {}", factor)
+        }
+        _ => expr.to_css(dest),
+    }
+}
+
+// 透视距离，小于等于0时为none
+fn write_perspective<W: Write>(prefix: &str, value: f32, suffix: &str, dest: &mut W) -> fmt::Result {
+    if value > 0.0 {
+        write!(dest, "{}{}px{}", prefix, value, suffix)
+    } else {
+        write!(dest, "{}none{}", prefix, suffix)
+    }
+}
+
+fn write_function<T: ToCss, W: Write>(name: &str, args: &[&T], dest: &mut W) -> fmt::Result {
+    dest.write_str(name)?;
+    dest.write_char('(')?;
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            dest.write_str(", ")?;
+        }
+        arg.to_css(dest)?;
+    }
+    dest.write_char(')')
+}
+
+/// 将枚举的Debug名转换为css关键字，如：SpaceBetween => space-between
+/// 用于布局、曲线等外部库中定义的枚举
+fn write_keyword<T: Debug, W: Write>(value: &T, dest: &mut W) -> fmt::Result {
+    let name = format!("{:?}", value);
+    let mut pre_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if pre_lower {
+                dest.write_char('-')?;
+            }
+            dest.write_char(c.to_ascii_lowercase())?;
+            pre_lower = false;
+        } else {
+            dest.write_char(c)?;
+            pre_lower = true;
+        }
+    }
+    Ok(())
+}
+
+fn write_url<W: Write>(path: &Atom, dest: &mut W) -> fmt::Result {
+    dest.write_str("url(")?;
+    serialize_string(path.as_ref(), dest)?;
+    dest.write_char(')')
+}
+
+fn write_rect_percent<W: Write>(rect: &NotNanRect, dest: &mut W) -> fmt::Result {
+    write_separated(&[Percent(*rect.top), Percent(*rect.right), Percent(*rect.bottom), Percent(*rect.left)], " ", dest)
+}
+
+fn write_center<W: Write>(x: &LengthUnit, y: &LengthUnit, dest: &mut W) -> fmt::Result {
+    dest.write_str(" at ")?;
+    x.to_css(dest)?;
+    dest.write_char(' ')?;
+    y.to_css(dest)
+}
+
+fn write_image_repeat<W: Write>(repeat: &ImageRepeat, dest: &mut W) -> fmt::Result {
+    let name = |option: ImageRepeatOption| match option {
+        ImageRepeatOption::Stretch => "no-repeat",
+        ImageRepeatOption::Repeat => "repeat",
+        ImageRepeatOption::Round => "round",
+        ImageRepeatOption::Space => "space",
+    };
+    write!(dest, "{} {}", name(repeat.x), name(repeat.y))
+}
+
+fn write_declarations<'a, W: Write>(attrs: impl Iterator<Item = &'a Attribute>, indent: &str, dest: &mut W) -> fmt::Result {
+    let mut decl = String::new();
+    for attr in attrs {
+        decl.clear();
+        attr.to_css(&mut decl)?;
+        if !decl.is_empty() {
+            writeln!(dest, "{}{};", indent, decl)?;
+        }
+    }
+    Ok(())
+}
+
+/// 输出样式列表，自定义属性在最前面，含有var()的声明按其位置插入
+fn write_var_declarations<'a, W: Write>(attrs: impl Iterator<Item = &'a Attribute>, vars: &VarDeclarations, indent: &str, dest: &mut W) -> fmt::Result {
+    for (name, value) in vars.vars.iter() {
+        writeln!(dest, "{}{}: {};", indent, name, value)?;
+    }
+    let mut decls = vars.decls.iter().peekable();
+    for (i, attr) in attrs.enumerate() {
+        while let Some(decl) = decls.next_if(|r| r.index <= i) {
+            writeln!(dest, "{}{}: {};", indent, decl.name, decl.value)?;
+        }
+        write_declarations(std::iter::once(attr), indent, dest)?;
+    }
+    for decl in decls {
+        writeln!(dest, "{}{}: {};", indent, decl.name, decl.value)?;
+    }
+    Ok(())
+}
+
+/// transition-property中的属性名，与parse_transation_property1对应
+/// 无法还原的属性（如margin等组合值）输出为all
+fn transition_property_name(property: usize) -> &'static str {
+    if property > GUI_STYLE_COUNT as usize {
+        return "all";
+    }
+    match StyleType::from_u16(property as u16) {
+        Some(StyleType::BackgroundRepeat) => "background-repeat",
+        Some(StyleType::Color) => "color",
+        Some(StyleType::BackgroundImageClip) => "background-image-clip",
+        Some(StyleType::BackgroundColor) => "background-color",
+        Some(StyleType::BorderColor) => "border-color",
+        Some(StyleType::Hsi) => "hsi",
+        Some(StyleType::Blur) => "blur",
+        Some(StyleType::Transform) => "transform",
+        Some(StyleType::BorderRadius) => "border-radius",
+        Some(StyleType::Width) => "width",
+        Some(StyleType::Height) => "height",
+        Some(StyleType::MarginTop) => "margin-top",
+        Some(StyleType::MarginRight) => "margin-right",
+        Some(StyleType::MarginBottom) => "margin-bottom",
+        Some(StyleType::MarginLeft) => "margin-left",
+        Some(StyleType::PaddingTop) => "padding-top",
+        Some(StyleType::PaddingRight) => "padding-right",
+        Some(StyleType::PaddingBottom) => "padding-bottom",
+        Some(StyleType::PaddingLeft) => "padding-left",
+        Some(StyleType::BorderTop) => "border-top",
+        Some(StyleType::BorderRight) => "border-right",
+        Some(StyleType::BorderBottom) => "border-bottom",
+        Some(StyleType::BorderLeft) => "border-left",
+        Some(StyleType::PositionTop) => "top",
+        Some(StyleType::PositionRight) => "right",
+        Some(StyleType::PositionBottom) => "bottom",
+        Some(StyleType::PositionLeft) => "left",
+        Some(StyleType::MinWidth) => "min-width",
+        Some(StyleType::MinHeight) => "min-height",
+        Some(StyleType::MaxWidth) => "max-width",
+        Some(StyleType::MaxHeight) => "max-height",
+        Some(StyleType::Opacity) => "opacity",
+        Some(StyleType::Translate) => "translate",
+        Some(StyleType::Scale) => "scale",
+        Some(StyleType::Rotate) => "rotate",
+        Some(StyleType::Perspective) => "perspective",
+        _ => "all",
+    }
+}
+
+#[test]
+fn test_to_css() {
+    let s = r#".c1 {
+        width: 10px;
+        height: 50%;
+        margin-left: auto;
+        color: #ff0000;
+        background: linear-gradient(20deg, #555555 10%, #ffffff 100%);
+        border-radius: 10px 20px / 5px;
+        transform: translate(10px, 50%) rotate(30deg) scale(2, 1);
+        filter: hsi(90, 20, -10);
+        content: "a\"b";
+        flex-wrap: nowrap;
+        justify-content: space-between;
+        clip-path: circle(10px at 50% 20px);
+        transition: opacity 200ms linear;
+        animation: move 1s infinite;
+    }
+    .c2 {
+        opacity: 0.5;
+    }
+    @keyframes move {
+        from { opacity: 0; }
+        to { opacity: 1; }
+    }"#;
+
+    let class_map = crate::style_parse::parse_class_map_from_string(s, 0).unwrap();
+    let css = class_map.to_css_string();
+    assert!(css.contains(".c1 {"));
+    assert!(css.contains("background: linear-gradient(20deg, #555555 10%, #ffffff 100%)"));
+    assert!(css.contains("border-radius: 10px 20px 10px 20px / 5px 5px 5px 5px"));
+    assert!(css.contains("flex-wrap: nowrap"));
+    assert!(css.contains("justify-content: space-between"));
+    assert!(css.contains("transition-property: opacity"));
+    assert!(css.contains("animation-iteration-count: infinite"));
+    assert!(css.contains(".c2 {\n\topacity: 0.5;\n}"));
+    assert!(css.contains("@keyframes move {\n\t0% {\n\t\topacity: 0;\n\t}\n\t100% {\n\t\topacity: 1;\n\t}\n}"));
+
+    // 序列化的结果再次解析、序列化，结果应保持不变
+    let class_map1 = crate::style_parse::parse_class_map_from_string(&css, 0).unwrap();
+    assert_eq!(css, class_map1.to_css_string());
+}
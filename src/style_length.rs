@@ -0,0 +1,243 @@
+//! 相对长度单位
+//!
+//! 支持的单位：
+//! * `em`：相对于当前字体大小
+//! * `rem`：相对于根字体大小
+//! * `vw`、`vh`：相对于视口宽度、高度的1%
+//! * `vmin`、`vmax`：相对于视口宽度、高度中较小、较大者的1%
+//!
+//! 含有相对长度的声明与含有var()的声明一样，在解析时只记录其原始文本（见VarDeclarations），
+//! 编译为ClassSheet时根据LengthContext换算为px后再解析，因此同一份样式表可以适配不同的屏幕；
+//! 环境变化时，可通过ClassMap::apply_context重新计算受影响的class。
+//!
+//! 关键帧中含有vw、vh、vmin、vmax的声明在构建关键帧曲线时才换算（见KeyFrameList::build_curves_with_context）；
+//! 单独的样式列表可通过parse_style_list_from_string_with_context指定LengthContext。
+//! 视口尺寸未知时（未指定视口的LengthContext），含有vw、vh、vmin、vmax的声明无法换算，
+//! 该声明被忽略并记录在ParseReport中，而不是换算为0px。
+//! em相对于同一样式列表中在其之前设置的font-size（px），没有时相对于LengthContext::font_size；
+//! 不考虑从父节点继承的字体大小。
+
+use cssparser::Token;
+
+use crate::style_var::rewrite_tokens;
+
+/// 换算相对长度所需的环境
+#[derive(Debug, Clone, PartialEq)]
+pub struct LengthContext {
+    /// 当前字体大小（px），用于em（样式列表中没有设置font-size时）
+    pub font_size: f32,
+    /// 根字体大小（px），用于rem
+    pub root_font_size: f32,
+    /// 视口宽度（px），None表示未知
+    pub viewport_width: Option<f32>,
+    /// 视口高度（px），None表示未知
+    pub viewport_height: Option<f32>,
+}
+
+impl Default for LengthContext {
+    fn default() -> Self {
+        Self {
+            font_size: 16.0,
+            root_font_size: 16.0,
+            viewport_width: None,
+            viewport_height: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelativeUnit {
+    Em,
+    Rem,
+    Vw,
+    Vh,
+    Vmin,
+    Vmax,
+}
+
+impl RelativeUnit {
+    /// 根据单位名取到相对单位，不区分大小写
+    pub fn from_name(name: &str) -> Option<Self> {
+        match &*name.to_ascii_lowercase() {
+            "em" => Some(RelativeUnit::Em),
+            "rem" => Some(RelativeUnit::Rem),
+            "vw" => Some(RelativeUnit::Vw),
+            "vh" => Some(RelativeUnit::Vh),
+            "vmin" => Some(RelativeUnit::Vmin),
+            "vmax" => Some(RelativeUnit::Vmax),
+            _ => None,
+        }
+    }
+
+    /// 换算为px，需要的视口尺寸未知时返回None
+    pub fn to_px(self, value: f32, context: &LengthContext) -> Option<f32> {
+        let viewport = || Some((context.viewport_width?, context.viewport_height?));
+        Some(match self {
+            RelativeUnit::Em => value * context.font_size,
+            RelativeUnit::Rem => value * context.root_font_size,
+            RelativeUnit::Vw => value * context.viewport_width? / 100.0,
+            RelativeUnit::Vh => value * context.viewport_height? / 100.0,
+            RelativeUnit::Vmin => value * viewport().map(|(w, h)| w.min(h))? / 100.0,
+            RelativeUnit::Vmax => value * viewport().map(|(w, h)| w.max(h))? / 100.0,
+        })
+    }
+}
+
+/// value中是否含有与视口尺寸相关的长度（vw、vh、vmin、vmax），字符串中的内容除外
+pub fn has_viewport_units(value: &str) -> bool {
+    let mut found = false;
+    rewrite_tokens(value, &mut |token| {
+        if let Token::Dimension { unit, .. } = token {
+            found |= matches!(RelativeUnit::from_name(unit), Some(RelativeUnit::Vw | RelativeUnit::Vh | RelativeUnit::Vmin | RelativeUnit::Vmax));
+        }
+        None
+    });
+    found
+}
+
+/// 将value中的相对长度替换为px，其余文本保持不变（字符串中的内容不会被替换）
+/// 有无法换算的相对长度时（视口尺寸未知），返回错误
+pub fn resolve_relative_lengths(value: &str, context: &LengthContext) -> Result<String, String> {
+    let mut unresolved = None;
+    let r = rewrite_tokens(value, &mut |token| match token {
+        Token::Dimension { value, unit, .. } => {
+            let r = RelativeUnit::from_name(unit)?.to_px(*value, context);
+            if r.is_none() && unresolved.is_none() {
+                unresolved = Some(unit.to_string());
+            }
+            r.map(|r| format!("{}px", r))
+        }
+        _ => None,
+    });
+    match unresolved {
+        Some(unit) => Err(format!("{} can not be resolved, the viewport size is unknown", unit)),
+        None => Ok(r),
+    }
+}
+
+#[test]
+fn test_length() {
+    use crate::style_parse::{
+        parse_class_map_from_string_with_report, parse_style_list_from_string, parse_style_list_from_string_with_context, parse_style_list_from_string_with_report, Attribute,
+        ClassStyle,
+    };
+    use crate::style::AnimationTimingFunction;
+    use crate::style_animation::AttributeKeyFrames;
+    use ordered_float::NotNan;
+    use pi_atom::Atom;
+    use pi_hash::XHashMap;
+    use crate::style_type::ClassSheet;
+
+    let context = LengthContext {
+        font_size: 20.0,
+        root_font_size: 10.0,
+        viewport_width: Some(400.0),
+        viewport_height: Some(800.0),
+    };
+    assert_eq!(
+        resolve_relative_lengths("calc(100% - 2em) 1.5rem 10vw 10vh \"3em\" 5vmin 5VMAX", &context).unwrap(),
+        "calc(100% - 40px) 15px 40px 80px \"3em\" 20px 40px"
+    );
+    // 视口尺寸未知时不换算为0px，而是返回错误
+    assert_eq!(resolve_relative_lengths("2em 1rem", &LengthContext::default()).unwrap(), "32px 16px");
+    assert!(resolve_relative_lengths("10vw", &LengthContext::default()).is_err());
+    assert!(resolve_relative_lengths("5vmax", &LengthContext { viewport_width: Some(100.0), ..Default::default() }).is_err());
+
+    let s = ".c1 {
+	width: 50vw;
+	height: 10px;
+	font-size: 1.5rem;
+}
+.c2 {
+	width: 10px;
+}
+";
+    let (class_map, report) = parse_class_map_from_string_with_report(s, 0);
+    assert!(report.is_empty());
+    assert_eq!(class_map.classes[0].vars.decls.len(), 2);
+    assert!(class_map.classes[1].vars.is_empty());
+    // 保留原始单位
+    assert_eq!(class_map.to_css_string(), s);
+
+    let styles = |class_sheet: &ClassSheet, class_name: usize| -> String {
+        let list: Vec<Attribute> = unsafe { class_sheet.iter_class(class_name) }
+            .unwrap()
+            .map(|r| match r.unwrap() {
+                ClassStyle::Set(r) => r,
+                ClassStyle::Reset(_) => unreachable!(),
+            })
+            .collect();
+        format!("{:?}", list)
+    };
+    let expect = |s: &str| -> String { format!("{:?}", parse_style_list_from_string(s, 0).unwrap().into_iter().collect::<Vec<Attribute>>()) };
+
+    let mut class_sheet = ClassSheet::default();
+    class_map.clone().to_class_sheet_with_context(&mut class_sheet, &Default::default(), &context);
+    assert_eq!(styles(&class_sheet, 1), expect("width: 200px; height: 10px; font-size: 15px"));
+
+    // 视口变化
+    let context = LengthContext {
+        viewport_width: Some(1000.0),
+        ..context
+    };
+    class_map.apply_context(&Default::default(), &context, &mut class_sheet);
+    assert_eq!(styles(&class_sheet, 1), expect("width: 500px; height: 10px; font-size: 15px"));
+    assert_eq!(styles(&class_sheet, 2), expect("width: 10px"));
+
+    // 未指定视口时，含有vw的声明被忽略并报告
+    let mut class_sheet = ClassSheet::default();
+    let report = class_map.clone().to_class_sheet(&mut class_sheet);
+    assert_eq!(styles(&class_sheet, 1), expect("height: 10px; font-size: 24px"));
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].class_name.as_deref(), Some("c1"));
+    let (list, report) = parse_style_list_from_string_with_report("width: 10vw; height: 2em", 0);
+    assert_eq!(format!("{:?}", list), format!("{:?}", parse_style_list_from_string("height: 32px", 0).unwrap()));
+    assert_eq!(report.diagnostics.len(), 1);
+    let (list, report) = parse_style_list_from_string_with_context("width: 10vw; height: 2em", 0, &context);
+    assert!(report.is_empty());
+    assert_eq!(format!("{:?}", list), expect("width: 100px; height: 32px"));
+
+    // 关键帧中含有vw等的声明推迟到构建曲线时换算
+    let (class_map, report) = parse_class_map_from_string_with_report(
+        "@keyframes k { from { width: 10vh; height: 1em; } }
+@keyframes slide { from { left: -100vw; } to { left: 0px; } }",
+        0,
+    );
+    assert!(report.is_empty());
+    assert!(class_map.key_frames.frames[&Atom::from("k")][&NotNan::new(0.0).unwrap()].is_empty());
+    let samples = |curves: &XHashMap<Atom, Vec<AttributeKeyFrames>>, name: &str, progress: f32| -> String {
+        let mut list: Vec<Attribute> = curves[&Atom::from(name)]
+            .iter()
+            .map(|r| r.sample(progress, &AnimationTimingFunction::Linear).unwrap())
+            .collect();
+        list.sort_by_key(|r| r.get_type() as u16);
+        format!("{:?}", list)
+    };
+    let sorted = |s: &str| -> String {
+        let mut list: Vec<Attribute> = parse_style_list_from_string(s, 0).unwrap().into_iter().collect();
+        list.sort_by_key(|r| r.get_type() as u16);
+        format!("{:?}", list)
+    };
+    let (curves, report) = class_map.key_frames.build_curves_with_context(&context, |_| None);
+    assert!(report.is_empty());
+    assert_eq!(samples(&curves, "k", 0.0), sorted("width: 80px; height: 16px"));
+    assert_eq!(samples(&curves, "slide", 0.0), sorted("left: -1000px"));
+    assert_eq!(samples(&curves, "slide", 0.5), sorted("left: -500px"));
+    // 视口未知时，含有vw的声明被忽略并报告
+    let (curves, report) = class_map.key_frames.build_curves_with_context(&LengthContext::default(), |_| None);
+    assert_eq!(samples(&curves, "k", 0.0), sorted("height: 16px"));
+    assert_eq!(samples(&curves, "slide", 0.0), sorted("left: 0px"));
+    assert_eq!(report.diagnostics.len(), 2);
+    assert!(report.diagnostics.iter().all(|r| r.key_frames.is_some()));
+    // 输出时保留原始单位
+    let css = class_map.to_css_string();
+    assert!(css.contains("width: 10vh") && css.contains("left: -100vw"));
+
+    // em相对于同一样式列表中在其之前设置的font-size，font-size中的em相对于LengthContext::font_size
+    let (list, report) = parse_style_list_from_string_with_report("width: 1em; font-size: 30px; height: 2em; font-size: 2em; left: 2em", 0);
+    assert!(report.is_empty());
+    assert_eq!(
+        format!("{:?}", list),
+        format!("{:?}", parse_style_list_from_string("width: 16px; font-size: 30px; height: 60px; font-size: 32px; left: 64px", 0).unwrap())
+    );
+}
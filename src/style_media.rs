@@ -0,0 +1,263 @@
+//! @media查询
+//!
+//! 支持的查询：
+//! * 媒体类型：`all`、`screen`、`print`（print永远不匹配），可带`not`、`only`前缀
+//! * `width`、`height`及其`min-`、`max-`形式，单位为px
+//! * `resolution`及其`min-`、`max-`形式，单位为dppx、x、dpi、dpcm
+//! * `orientation`：portrait、landscape
+//! * `platform`：平台名称，如android、ios、web
+//! * `lang`：语言，`(lang: zh)`可匹配zh、zh-CN等
+//!
+//! 多个条件用`and`连接，多个查询用`,`分隔（任意一个匹配即可）
+
+use crate::style_type::ClassSheet;
+
+/// 运行环境，用于计算@media查询
+#[derive(Debug, Clone)]
+pub struct MediaContext {
+    /// 视口宽度（px）
+    pub width: f32,
+    /// 视口高度（px）
+    pub height: f32,
+    /// 设备像素比（dppx）
+    pub resolution: f32,
+    /// 平台名称
+    pub platform: String,
+    /// 语言
+    pub lang: String,
+}
+
+impl Default for MediaContext {
+    fn default() -> Self {
+        Self {
+            width: 0.0,
+            height: 0.0,
+            resolution: 1.0,
+            platform: String::new(),
+            lang: String::new(),
+        }
+    }
+}
+
+impl MediaContext {
+    pub fn orientation(&self) -> Orientation {
+        if self.height >= self.width {
+            Orientation::Portrait
+        } else {
+            Orientation::Landscape
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MediaType {
+    All,
+    Screen,
+    Print,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+/// 范围条件的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MediaRange {
+    /// `min-`，环境值大于等于条件值
+    Min,
+    /// `max-`，环境值小于等于条件值
+    Max,
+    /// 环境值等于条件值
+    Exact,
+}
+
+impl MediaRange {
+    pub fn matches(self, value: f32, target: f32) -> bool {
+        match self {
+            MediaRange::Min => value >= target,
+            MediaRange::Max => value <= target,
+            MediaRange::Exact => value == target,
+        }
+    }
+}
+
+/// 查询条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MediaFeature {
+    Width(MediaRange, f32),
+    Height(MediaRange, f32),
+    /// 单位为dppx
+    Resolution(MediaRange, f32),
+    Orientation(Orientation),
+    Platform(String),
+    Lang(String),
+}
+
+impl MediaFeature {
+    pub fn matches(&self, context: &MediaContext) -> bool {
+        match self {
+            MediaFeature::Width(range, r) => range.matches(context.width, *r),
+            MediaFeature::Height(range, r) => range.matches(context.height, *r),
+            MediaFeature::Resolution(range, r) => range.matches(context.resolution, *r),
+            MediaFeature::Orientation(r) => context.orientation() == *r,
+            MediaFeature::Platform(r) => r.eq_ignore_ascii_case(&context.platform),
+            MediaFeature::Lang(r) => lang_matches(r, &context.lang),
+        }
+    }
+}
+
+/// 语言是否匹配，`zh`可匹配`zh`、`zh-CN`，不区分大小写
+fn lang_matches(range: &str, lang: &str) -> bool {
+    match lang.get(..range.len()) {
+        Some(r) if r.eq_ignore_ascii_case(range) => lang.len() == range.len() || lang.as_bytes()[range.len()] == b'-',
+        _ => false,
+    }
+}
+
+/// 单个查询，如`not screen and (min-width: 100px)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaQuery {
+    pub not: bool,
+    pub media_type: MediaType,
+    /// 所有条件都满足时匹配
+    pub features: Vec<MediaFeature>,
+}
+
+impl Default for MediaQuery {
+    fn default() -> Self {
+        Self {
+            not: false,
+            media_type: MediaType::All,
+            features: Vec::new(),
+        }
+    }
+}
+
+impl MediaQuery {
+    pub fn matches(&self, context: &MediaContext) -> bool {
+        let r = self.media_type != MediaType::Print && self.features.iter().all(|r| r.matches(context));
+        r != self.not
+    }
+}
+
+/// 查询列表，任意一个查询匹配即匹配；列表为空时总是匹配
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MediaQueryList(pub Vec<MediaQuery>);
+
+impl MediaQueryList {
+    pub fn matches(&self, context: &MediaContext) -> bool { self.0.is_empty() || self.0.iter().any(|r| r.matches(context)) }
+}
+
+/// @media中的样式编译后的结果
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MediaSheet {
+    pub query: MediaQueryList,
+    pub sheet: ClassSheet,
+}
+
+impl ClassSheet {
+    /// 取到在给定环境下生效的@media样式表（含嵌套的@media），按定义顺序排列
+    /// 这些样式表中的样式应在本样式表的样式之后应用；环境变化（如视口尺寸变化）时，重新调用即可
+    pub fn media_sheets(&self, context: &MediaContext) -> Vec<&ClassSheet> {
+        let mut ret = Vec::new();
+        collect_media_sheets(self, context, &mut ret);
+        ret
+    }
+}
+
+fn collect_media_sheets<'a>(sheet: &'a ClassSheet, context: &MediaContext, ret: &mut Vec<&'a ClassSheet>) {
+    for media in sheet.media.iter() {
+        if media.query.matches(context) {
+            ret.push(&media.sheet);
+            collect_media_sheets(&media.sheet, context, ret);
+        }
+    }
+}
+
+#[test]
+fn test_media() {
+    use crate::style_parse::{parse_class_map_from_string_with_report, ClassMap};
+
+    let s = ".c1 {
+	width: 10px;
+	height: 10px;
+}
+@media screen and (min-width: 600px) and (orientation: landscape) {
+.c1 {
+	width: 20px;
+}
+.c2 {
+	width: 30px;
+}
+}
+@media (platform: android), (lang: zh) {
+.c1 {
+	height: 40px;
+}
+}
+@media not screen and (min-resolution: 1.5dppx) {
+.c3 {
+	width: 50px;
+}
+}
+";
+    let (class_map, report) = parse_class_map_from_string_with_report(s, 0);
+    assert!(report.is_empty());
+    assert_eq!(class_map.classes.len(), 1);
+    assert_eq!(class_map.media.len(), 3);
+    assert_eq!(class_map.to_css_string(), s);
+
+    let web = MediaContext {
+        width: 800.0,
+        height: 600.0,
+        resolution: 2.0,
+        platform: "web".to_string(),
+        lang: "zh-CN".to_string(),
+    };
+    let android = MediaContext {
+        width: 360.0,
+        height: 640.0,
+        resolution: 1.0,
+        platform: "android".to_string(),
+        lang: "en".to_string(),
+    };
+    let matches = |context: &MediaContext| -> Vec<bool> { class_map.media.iter().map(|r| r.query.matches(context)).collect() };
+    assert_eq!(matches(&web), vec![true, true, false]);
+    assert_eq!(matches(&android), vec![false, true, true]);
+    assert!(!lang_matches("zh", "zhx"));
+
+    // 针对某个环境编译，@media中的样式追加到同名class之后
+    let resolved: ClassMap = class_map.clone().resolve_media(&web);
+    assert!(resolved.media.is_empty());
+    assert_eq!(resolved.classes.iter().map(|r| (r.class_name, r.count)).collect::<Vec<_>>(), vec![(1, 4), (2, 1)]);
+    assert_eq!(resolved.to_css_string(), ".c1 {
+	width: 10px;
+	height: 10px;
+	width: 20px;
+	height: 40px;
+}
+.c2 {
+	width: 30px;
+}
+");
+
+    // 保留@media，运行时根据环境选择
+    let mut class_sheet = ClassSheet::default();
+    class_map.to_class_sheet(&mut class_sheet);
+    assert_eq!(class_sheet.class_map.len(), 1);
+    assert_eq!(class_sheet.media.len(), 3);
+    let sheets = class_sheet.media_sheets(&android);
+    assert_eq!(sheets.len(), 2);
+    assert!(sheets[1].class_map.contains_key(&3));
+
+    let data = unsafe { class_sheet.to_binary() }.unwrap();
+    let class_sheet1 = ClassSheet::from_binary(&data).unwrap();
+    assert_eq!(class_sheet1.media.len(), 3);
+    assert_eq!(class_sheet1.media[0].query, class_sheet.media[0].query);
+    assert_eq!(unsafe { class_sheet1.to_binary() }.unwrap(), data);
+
+    let (_, report) = parse_class_map_from_string_with_report("@media (min-color: 1) { .c1 { width: 1px; } }", 0);
+    assert!(report.has_errors());
+}
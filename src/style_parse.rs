@@ -5,7 +5,7 @@ use std::str::FromStr;
 use std::{collections::VecDeque, intrinsics::transmute};
 
 use bitvec::prelude::BitArray;
-use cssparser::{CowRcStr, Delimiter, ParseError, Parser, ParserInput, Token, BasicParseError, SourceLocation, ParseErrorKind, BasicParseErrorKind};
+use cssparser::{CowRcStr, Delimiter, ParseError, Parser, ParserInput, Token, BasicParseError, SourceLocation, ParseErrorKind, BasicParseErrorKind, ToCss};
 use ordered_float::NotNan;
 use pi_atom::Atom;
 use pi_curves::steps::EStepMode;
@@ -18,9 +18,13 @@ use smallvec::SmallVec;
 use thiserror::Error;
 
 use crate::style::{
-    Animation, AnimationDirection, AnimationFillMode, AnimationName, AnimationPlayState, AnimationTimingFunction, AsImage, BaseShape, BlendMode, BorderImageSlice, BorderRadius, BoxShadow, Center, CgColor, Color, ColorAndPosition, Enable, FitType, FontSize, Hsi, ImageRepeat, ImageRepeatOption, IterationCount, LengthUnit, LineHeight, LinearGradientColor, MaskImage, NotNanRect, OuterGlow, Stroke, TextAlign, TextContent, TextOverflow, TextShadow, Time, TransformFunc, TransformOrigin, Transition, WhiteSpace
+    AngleUnit, Animation, AnimationDirection, AnimationFillMode, AnimationName, AnimationPlayState, AnimationTimingFunction, AsImage, BaseShape, BlendMode, BorderImageSlice, BorderRadius, BoxShadow, CalcExpr, Center, CgColor, Color, ColorAndPosition, ConicGradientColor, Deg, Enable, FitType, FontSize, GradientStop, Hsi, ImageRepeat, ImageRepeatOption, IterationCount, LayoutDimension, LengthUnit, LineHeight, LinearGradientColor, MaskImage, NotNanRect, OuterGlow, RadialGradientColor, RadialGradientShape, RadialGradientSize, resolve_color_stops, Stroke, TextAlign, TextContent, TextOverflow, TextShadow, Time, TransformFunc, TransformOrigin, TransformStyle, Transition, Turn, WhiteSpace
 };
 use crate::style::StyleType;
+use crate::style_length::{LengthContext, RelativeUnit};
+use crate::style_media::{MediaContext, MediaFeature, MediaQuery, MediaQueryList, MediaRange, MediaSheet, MediaType, Orientation};
+use crate::style_selector::{Combinator, CompoundSelector, PseudoState, Selector, SelectorMeta};
+use crate::style_var::{Theme, VarDeclaration, VarDeclarations};
 
 use super::style_type::*;
 
@@ -146,12 +150,181 @@ pub enum Attribute {
     RowGap(RowGapType),     // 97
     ColumnGap(ColumnGapType),     // 98
     AutoReduce(AutoReduceType), // 99
+
+    Perspective(PerspectiveType),         // 100
+    TransformStyle(TransformStyleType),   // 101
+}
+
+/// 按 `StyleType => Attribute变体(样式类型)` 列出所有样式，用于生成按样式类型分派的代码
+/// 用法：style_list!(m!(args))，展开为 m!(args; 样式列表)
+macro_rules! style_list {
+	($m: ident!($($args: tt)*)) => {
+		$m!($($args)*;
+			BackgroundRepeat => BackgroundRepeat(BackgroundRepeatType),
+			FontStyle => FontStyle(FontStyleType),
+			FontWeight => FontWeight(FontWeightType),
+			FontSize => FontSize(FontSizeType),
+			FontFamily => FontFamily(FontFamilyType),
+			LetterSpacing => LetterSpacing(LetterSpacingType),
+			WordSpacing => WordSpacing(WordSpacingType),
+			LineHeight => LineHeight(LineHeightType),
+			TextIndent => TextIndent(TextIndentType),
+			WhiteSpace => WhiteSpace(WhiteSpaceType),
+			TextAlign => TextAlign(TextAlignType),
+			VerticalAlign => VerticalAlign(VerticalAlignType),
+			Color => Color(ColorType),
+			TextStroke => TextStroke(TextStrokeType),
+			TextShadow => TextShadow(TextShadowType),
+			BackgroundImage => BackgroundImage(BackgroundImageType),
+			BackgroundImageClip => BackgroundImageClip(BackgroundImageClipType),
+			ObjectFit => ObjectFit(ObjectFitType),
+			BackgroundColor => BackgroundColor(BackgroundColorType),
+			BoxShadow => BoxShadow(BoxShadowType),
+			BorderImage => BorderImage(BorderImageType),
+			BorderImageClip => BorderImageClip(BorderImageClipType),
+			BorderImageSlice => BorderImageSlice(BorderImageSliceType),
+			BorderImageRepeat => BorderImageRepeat(BorderImageRepeatType),
+			BorderColor => BorderColor(BorderColorType),
+			Hsi => Hsi(HsiType),
+			Blur => Blur(BlurType),
+			MaskImage => MaskImage(MaskImageType),
+			MaskImageClip => MaskImageClip(MaskImageClipType),
+			Transform => Transform(TransformType),
+			TransformOrigin => TransformOrigin(TransformOriginType),
+			TransformWillChange => TransformWillChange(TransformWillChangeType),
+			BorderRadius => BorderRadius(BorderRadiusType),
+			ZIndex => ZIndex(ZIndexType),
+			Overflow => Overflow(OverflowType),
+			BlendMode => BlendMode(BlendModeType),
+			Display => Display(DisplayType),
+			Visibility => Visibility(VisibilityType),
+			Enable => Enable(EnableType),
+			Width => Width(WidthType),
+			Height => Height(HeightType),
+			MarginTop => MarginTop(MarginTopType),
+			MarginRight => MarginRight(MarginRightType),
+			MarginBottom => MarginBottom(MarginBottomType),
+			MarginLeft => MarginLeft(MarginLeftType),
+			PaddingTop => PaddingTop(PaddingTopType),
+			PaddingRight => PaddingRight(PaddingRightType),
+			PaddingBottom => PaddingBottom(PaddingBottomType),
+			PaddingLeft => PaddingLeft(PaddingLeftType),
+			BorderTop => BorderTop(BorderTopType),
+			BorderRight => BorderRight(BorderRightType),
+			BorderBottom => BorderBottom(BorderBottomType),
+			BorderLeft => BorderLeft(BorderLeftType),
+			PositionTop => PositionTop(PositionTopType),
+			PositionRight => PositionRight(PositionRightType),
+			PositionBottom => PositionBottom(PositionBottomType),
+			PositionLeft => PositionLeft(PositionLeftType),
+			MinWidth => MinWidth(MinWidthType),
+			MinHeight => MinHeight(MinHeightType),
+			MaxHeight => MaxHeight(MaxHeightType),
+			MaxWidth => MaxWidth(MaxWidthType),
+			Direction => Direction(DirectionType),
+			FlexDirection => FlexDirection(FlexDirectionType),
+			FlexWrap => FlexWrap(FlexWrapType),
+			JustifyContent => JustifyContent(JustifyContentType),
+			AlignContent => AlignContent(AlignContentType),
+			AlignItems => AlignItems(AlignItemsType),
+			PositionType => PositionType(PositionTypeType),
+			AlignSelf => AlignSelf(AlignSelfType),
+			FlexShrink => FlexShrink(FlexShrinkType),
+			FlexGrow => FlexGrow(FlexGrowType),
+			AspectRatio => AspectRatio(AspectRatioType),
+			Order => Order(OrderType),
+			FlexBasis => FlexBasis(FlexBasisType),
+			Opacity => Opacity(OpacityType),
+			TextContent => TextContent(TextContentType),
+			NodeState => VNode(VNodeType),
+			AnimationName => AnimationName(AnimationNameType),
+			AnimationDuration => AnimationDuration(AnimationDurationType),
+			AnimationTimingFunction => AnimationTimingFunction(AnimationTimingFunctionType),
+			AnimationDelay => AnimationDelay(AnimationDelayType),
+			AnimationIterationCount => AnimationIterationCount(AnimationIterationCountType),
+			AnimationDirection => AnimationDirection(AnimationDirectionType),
+			AnimationFillMode => AnimationFillMode(AnimationFillModeType),
+			AnimationPlayState => AnimationPlayState(AnimationPlayStateType),
+			ClipPath => ClipPath(ClipPathType),
+			Translate => Translate(TranslateType),
+			Scale => Scale(ScaleType),
+			Rotate => Rotate(RotateType),
+			AsImage => AsImage(AsImageType),
+			TextOverflow => TextOverflow(TextOverflowType),
+			OverflowWrap => OverflowWrap(OverflowWrapType),
+			TransitionProperty => TransitionProperty(TransitionPropertyType),
+			TransitionDuration => TransitionDuration(TransitionDurationType),
+			TransitionTimingFunction => TransitionTimingFunction(TransitionTimingFunctionType),
+			TransitionDelay => TransitionDelay(TransitionDelayType),
+			TextOuterGlow => TextOuterGlow(TextOuterGlowType),
+			RowGap => RowGap(RowGapType),
+			ColumnGap => ColumnGap(ColumnGapType),
+			AutoReduce => AutoReduce(AutoReduceType),
+			Perspective => Perspective(PerspectiveType),
+			TransformStyle => TransformStyle(TransformStyleType),
+		)
+	};
+}
+pub(crate) use style_list;
+
+impl Attribute {
+	/// 样式类型
+	pub fn get_type(&self) -> StyleType {
+		macro_rules! attr_type {
+			($attr: expr; $($style_ty: ident => $variant: ident($value_ty: ident)),* $(,)?) => {
+				match $attr {
+					$(Attribute::$variant(_) => StyleType::$style_ty,)*
+				}
+			};
+		}
+		style_list!(attr_type!(self))
+	}
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct KeyFrameList {
     pub frames: XHashMap<Atom, XHashMap<NotNan<f32>, VecDeque<Attribute>>>,
+	/// 关键帧中声明的animation-timing-function，作用于该关键帧到下一个关键帧之间，不在frames中保存
+	#[serde(default)]
+	pub timing_functions: XHashMap<Atom, XHashMap<NotNan<f32>, AnimationTimingFunction>>,
 	pub scope_hash: usize,
+	/// 关键帧中含有var()、相对长度或currentColor的声明，整个样式表解析完成（`:root`已知）后计算
+	#[serde(skip)]
+	pub(crate) pending_vars: XHashMap<Atom, XHashMap<NotNan<f32>, VarDeclarations>>,
+	/// 含有vw、vh、vmin、vmax的关键帧中需要计算的声明（var()已替换），解析时视口尺寸未知，
+	/// 构建关键帧曲线时根据LengthContext计算并插入到frames对应的关键帧中（见KeyFrameList::build_curves_with_context）
+	#[serde(default)]
+	pub viewport_vars: XHashMap<Atom, XHashMap<NotNan<f32>, VarDeclarations>>,
+}
+
+impl KeyFrameList {
+	/// 根据lengths计算name的关键帧中含有vw等的声明（见viewport_vars），返回计算后的关键帧及关键帧的缓动函数
+	/// 没有这样的声明时返回None，直接使用frames、timing_functions即可
+	pub(crate) fn resolve_viewport_vars(
+		&self,
+		name: &Atom,
+		lengths: &LengthContext,
+		report: &mut ParseReport,
+	) -> Option<(XHashMap<NotNan<f32>, VecDeque<Attribute>>, XHashMap<NotNan<f32>, AnimationTimingFunction>)> {
+		let pending = self.viewport_vars.get(name)?;
+		let mut frames = self.frames.get(name).cloned().unwrap_or_default();
+		let mut timing_functions = self.timing_functions.get(name).cloned().unwrap_or_default();
+		for (progress, vars) in pending.iter() {
+			let attrs = frames.entry(*progress).or_default();
+			for (location, message) in vars.resolve(attrs, &[], lengths, self.scope_hash) {
+				let mut diagnostic = ParseDiagnostic::new(Severity::Error, location, message);
+				diagnostic.key_frames = Some(AsRef::<str>::as_ref(name).to_string());
+				report.push(diagnostic);
+			}
+			// 通过var()声明的animation-timing-function
+			if let Some(r) = take_timing_function(attrs, &mut []) {
+				timing_functions.insert(*progress, r);
+			}
+		}
+		frames.retain(|_, r| !r.is_empty());
+		timing_functions.retain(|progress, _| frames.contains_key(progress));
+		Some((frames, timing_functions))
+	}
 }
 
 // #[derive(Debug, Serialize, Deserialize, Default)]
@@ -165,15 +338,313 @@ pub struct ClassMap {
     pub attrs: VecDeque<Attribute>,
     pub classes: Vec<ClassItem>,
     pub key_frames: KeyFrameList,
+	/// class id到class名的反向表，用于调试
+	pub class_names: XHashMap<usize, String>,
+	/// 不只含有单个class的选择器，按定义顺序排列
+	pub selectors: Vec<SelectorItem>,
+	/// 选择器的样式，按selectors的顺序依次排列
+	pub selector_attrs: VecDeque<Attribute>,
+	/// @media中的样式，按定义顺序排列
+	pub media: Vec<MediaGroup>,
+	/// `:root`中定义的自定义属性
+	pub root_vars: Theme,
 }
 
 impl ClassMap {
-    pub fn to_class_sheet(mut self, class_sheet: &mut ClassSheet) {
+    /// 编译为ClassSheet，@media中的样式分别编译为ClassSheet::media中的样式表，运行时根据环境选择生效的样式表
+    /// var()使用`:root`中定义的自定义属性计算，相对长度使用默认的LengthContext换算
+    /// 返回计算var()及相对长度时产生的错误（计算失败的声明被忽略）
+    pub fn to_class_sheet(self, class_sheet: &mut ClassSheet) -> ParseReport { self.to_class_sheet_with_theme(class_sheet, &Theme::default()) }
+
+    /// 编译为ClassSheet，var()优先使用theme中定义的自定义属性计算
+    pub fn to_class_sheet_with_theme(self, class_sheet: &mut ClassSheet, theme: &Theme) -> ParseReport {
+        self.to_class_sheet_with_context(class_sheet, theme, &LengthContext::default())
+    }
+
+    /// 编译为ClassSheet，var()优先使用theme中定义的自定义属性计算，相对长度（em、rem、vw等）根据lengths换算为px
+    pub fn to_class_sheet_with_context(self, class_sheet: &mut ClassSheet, theme: &Theme, lengths: &LengthContext) -> ParseReport {
+        let mut report = ParseReport::default();
+        self.compile(class_sheet, theme, lengths, &[], &mut report);
+        report
+    }
+
+    /// parents为外层ClassMap（@media所在的ClassMap）的`:root`，从内到外排列
+    fn compile(mut self, class_sheet: &mut ClassSheet, theme: &Theme, lengths: &LengthContext, parents: &[&Theme], report: &mut ParseReport) {
+        let root_vars = std::mem::take(&mut self.root_vars);
+        let mut roots = vec![&root_vars];
+        roots.extend_from_slice(parents);
+        for group in std::mem::take(&mut self.media) {
+            let mut sheet = ClassSheet::default();
+            group.class_map.compile(&mut sheet, theme, lengths, &roots, report);
+            class_sheet.media.push(MediaSheet { query: group.query, sheet });
+        }
+
+        let mut themes = vec![theme];
+        themes.extend_from_slice(&roots);
+        let context = VarContext {
+            themes,
+            lengths,
+            scope_hash: self.key_frames.scope_hash,
+        };
         for class in self.classes.iter() {
-            let class_meta = style_list_to_buffer(&mut class_sheet.style_buffer, &mut self.attrs, class.count);
-            class_sheet.class_map.insert(class.class_name, class_meta);
+            let class_name = self.class_names.get(&class.class_name).map(|r| r.as_str());
+            let class_meta = var_list_to_buffer(&mut class_sheet.style_buffer, &mut self.attrs, class.count, &class.vars, &context, report, class_name);
+            if class.state.is_empty() {
+                class_sheet.class_map.insert(class.class_name, class_meta);
+            } else {
+                class_sheet.state_map.insert((class.class_name, class.state), class_meta);
+            }
         }
+        for item in self.selectors.into_iter() {
+            let meta = var_list_to_buffer(&mut class_sheet.style_buffer, &mut self.selector_attrs, item.count, &item.vars, &context, report, None);
+            class_sheet.selectors.push(SelectorMeta { selector: item.selector, meta });
+        }
+        class_sheet.var_context = Some((theme.clone(), lengths.clone()));
     }
+
+    /// 切换主题，重新计算引用了变化的变量的class及选择器，更新class_sheet中对应的ClassMeta
+    /// class_sheet须由该ClassMap编译得到（to_class_sheet会消耗ClassMap，需要切换主题时应保留一份clone）；
+    /// 新的样式优先放在旧样式的位置，放不下时整理style_buffer（其它class的ClassMeta也可能改变）
+    /// 相对长度使用默认的LengthContext换算，使用了相对长度时，应调用apply_context
+    pub fn apply_theme(&self, theme: &Theme, class_sheet: &mut ClassSheet) -> ParseReport { self.apply_context(theme, &LengthContext::default(), class_sheet) }
+
+    /// 切换主题或换算相对长度的环境（如视口尺寸变化），重新计算受影响的class及选择器
+    /// 环境变化时，所有含有var()或相对长度的class都会重新计算
+    pub fn apply_context(&self, theme: &Theme, lengths: &LengthContext, class_sheet: &mut ClassSheet) -> ParseReport {
+        let mut report = ParseReport::default();
+        self.apply_inner(theme, lengths, &[], class_sheet, &mut report);
+        report
+    }
+
+    fn apply_inner(&self, theme: &Theme, lengths: &LengthContext, parents: &[&Theme], class_sheet: &mut ClassSheet, report: &mut ParseReport) {
+        let mut roots = vec![&self.root_vars];
+        roots.extend_from_slice(parents);
+        for (group, media) in self.media.iter().zip(class_sheet.media.iter_mut()) {
+            group.class_map.apply_inner(theme, lengths, &roots, &mut media.sheet, report);
+        }
+
+        // 没有上次计算所用的环境，或相对长度的环境变化时，重新计算所有含有var()或相对长度的class
+        let previous = class_sheet.var_context.replace((theme.clone(), lengths.clone()));
+        let rebuild_all = !matches!(&previous, Some((_, r)) if r == lengths);
+        let changed = |name: &str| match &previous {
+            Some((r, _)) => r.get(name) != theme.get(name),
+            None => true,
+        };
+
+        let mut themes = vec![theme];
+        themes.extend_from_slice(&roots);
+        let context = VarContext {
+            themes,
+            lengths,
+            scope_hash: self.key_frames.scope_hash,
+        };
+        let mut garbage = false;
+        let mut start = 0;
+        for (i, class) in self.classes.iter().enumerate() {
+            // 同名class（伪类相同）以最后定义的为准
+            let overridden = self.classes[i + 1..].iter().any(|r| r.class_name == class.class_name && r.state == class.state);
+            if !overridden && !class.vars.decls.is_empty() && (rebuild_all || class.vars.references(&context.themes, &changed)) {
+                let mut list: VecDeque<Attribute> = self.attrs.range(start..start + class.count).cloned().collect();
+                let class_name = self.class_names.get(&class.class_name).map(|r| r.as_str());
+                let class_meta = var_list_to_buffer(&mut class_sheet.style_buffer, &mut list, class.count, &class.vars, &context, report, class_name);
+                let old = if class.state.is_empty() {
+                    class_sheet.class_map.get(&class.class_name)
+                } else {
+                    class_sheet.state_map.get(&(class.class_name, class.state))
+                };
+                let (class_meta, r) = reuse_buffer(&mut class_sheet.style_buffer, old, class_meta);
+                garbage |= r;
+                if class.state.is_empty() {
+                    class_sheet.class_map.insert(class.class_name, class_meta);
+                } else {
+                    class_sheet.state_map.insert((class.class_name, class.state), class_meta);
+                }
+            }
+            start += class.count;
+        }
+
+        let mut start = 0;
+        for (i, item) in self.selectors.iter().enumerate() {
+            if !item.vars.decls.is_empty() && (rebuild_all || item.vars.references(&context.themes, &changed)) {
+                let mut list: VecDeque<Attribute> = self.selector_attrs.range(start..start + item.count).cloned().collect();
+                let meta = var_list_to_buffer(&mut class_sheet.style_buffer, &mut list, item.count, &item.vars, &context, report, None);
+                let old = class_sheet.selectors.get(i).map(|r| &r.meta);
+                let (meta, r) = reuse_buffer(&mut class_sheet.style_buffer, old, meta);
+                garbage |= r;
+                if let Some(r) = class_sheet.selectors.get_mut(i) {
+                    r.meta = meta;
+                }
+            }
+            start += item.count;
+        }
+
+        if garbage {
+            class_sheet.compact();
+        }
+    }
+
+	/// 计算关键帧中的var()及相对长度，parents为外层ClassMap的`:root`，从内到外排列
+	/// 关键帧不随主题变化，var()只使用`:root`中的自定义属性，em、rem使用默认的LengthContext换算；
+	/// 含有vw等的关键帧，只替换var()，记录在viewport_vars中，构建关键帧曲线时再计算（为保持声明的顺序，该关键帧的其它声明也一起延迟计算）
+	fn resolve_key_frames(&mut self, parents: &[&Theme], report: &mut ParseReport) {
+		let mut roots = vec![&self.root_vars];
+		roots.extend_from_slice(parents);
+		for group in self.media.iter_mut() {
+			group.class_map.resolve_key_frames(&roots, report);
+		}
+
+		let lengths = LengthContext::default();
+		let key_frames = &mut self.key_frames;
+		for (name, pending) in std::mem::take(&mut key_frames.pending_vars) {
+			let frames = match key_frames.frames.get_mut(&name) {
+				Some(r) => r,
+				None => continue,
+			};
+			for (progress, mut vars) in pending {
+				let attrs = match frames.get_mut(&progress) {
+					Some(r) => r,
+					None => continue,
+				};
+				if vars.uses_viewport(&roots) {
+					for (location, message) in vars.substitute(&roots) {
+						let mut diagnostic = ParseDiagnostic::new(Severity::Error, location, message);
+						diagnostic.key_frames = Some(AsRef::<str>::as_ref(&name).to_string());
+						report.push(diagnostic);
+					}
+					key_frames.viewport_vars.entry(name.clone()).or_default().insert(progress, vars);
+					continue;
+				}
+				for (location, message) in vars.resolve(attrs, &roots, &lengths, key_frames.scope_hash) {
+					let mut diagnostic = ParseDiagnostic::new(Severity::Error, location, message);
+					diagnostic.key_frames = Some(AsRef::<str>::as_ref(&name).to_string());
+					report.push(diagnostic);
+				}
+				// 通过var()声明的animation-timing-function
+				if let Some(r) = take_timing_function(attrs, &mut []) {
+					key_frames.timing_functions.entry(name.clone()).or_default().insert(progress, r);
+				}
+			}
+
+			// 没有属性的关键帧不会保存，其缓动函数也没有意义
+			let viewport_vars = key_frames.viewport_vars.get(&name);
+			frames.retain(|progress, r| !r.is_empty() || viewport_vars.is_some_and(|r| r.contains_key(progress)));
+			if let Some(r) = key_frames.timing_functions.get_mut(&name) {
+				r.retain(|progress, _| frames.contains_key(progress));
+			}
+			if frames.is_empty() {
+				key_frames.frames.remove(&name);
+			}
+			if key_frames.timing_functions.get(&name).is_some_and(|r| r.is_empty()) {
+				key_frames.timing_functions.remove(&name);
+			}
+		}
+	}
+
+	/// 针对某个运行环境编译为ClassSheet，@media中匹配的样式合并到对应的class中，不匹配的样式被丢弃
+	pub fn to_class_sheet_for(self, class_sheet: &mut ClassSheet, context: &MediaContext) -> ParseReport {
+		self.resolve_media(context).to_class_sheet(class_sheet)
+	}
+
+	/// 计算@media，返回不含@media的ClassMap
+	/// 匹配的@media中的class样式追加到同名class（伪类相同）的样式之后，选择器和关键帧按定义顺序追加
+	pub fn resolve_media(mut self, context: &MediaContext) -> ClassMap {
+		for group in std::mem::take(&mut self.media) {
+			if group.query.matches(context) {
+				self.merge(group.class_map.resolve_media(context));
+			}
+		}
+		self
+	}
+
+	fn merge(&mut self, other: ClassMap) {
+		let mut attrs = other.attrs;
+		for class in other.classes {
+			let list: Vec<Attribute> = attrs.drain(..class.count).collect();
+			match self.classes.iter().rposition(|r| r.class_name == class.class_name && r.state == class.state) {
+				Some(index) => {
+					let end = self.classes[..=index].iter().map(|r| r.count).sum::<usize>();
+					for (i, attr) in list.into_iter().enumerate() {
+						self.attrs.insert(end + i, attr);
+					}
+					let item = &mut self.classes[index];
+					item.vars.extend(class.vars, item.count);
+					item.count += class.count;
+				}
+				None => {
+					self.attrs.extend(list);
+					self.classes.push(class);
+				}
+			}
+		}
+		self.selectors.extend(other.selectors);
+		self.selector_attrs.extend(other.selector_attrs);
+		// 同名的keyframes整体替换，不能保留旧的关键帧缓动函数
+		for name in other.key_frames.frames.keys() {
+			self.key_frames.timing_functions.remove(name);
+			self.key_frames.viewport_vars.remove(name);
+		}
+		self.key_frames.frames.extend(other.key_frames.frames);
+		self.key_frames.timing_functions.extend(other.key_frames.timing_functions);
+		self.key_frames.viewport_vars.extend(other.key_frames.viewport_vars);
+		self.root_vars.vars.extend(other.root_vars.vars);
+		for (class_name, name) in other.class_names {
+			self.class_names.entry(class_name).or_insert(name);
+		}
+	}
+
+	/// 根据class id取到class名
+	pub fn class_name(&self, class_name: usize) -> Option<&str> {
+		self.class_names.get(&class_name).map(|r| r.as_str())
+	}
+
+	/// 记录class名，如果该id已被其他名称占用（hash冲突），返回已存在的名称
+	pub fn insert_class_name(&mut self, class_name: usize, name: &str) -> Option<&str> {
+		match self.class_names.entry(class_name) {
+			Entry::Occupied(r) => {
+				if r.get() == name {
+					None
+				} else {
+					Some(r.into_mut().as_str())
+				}
+			}
+			Entry::Vacant(r) => {
+				r.insert(name.to_string());
+				None
+			}
+		}
+	}
+}
+
+/// class名的hash，用于将任意class名映射为class id
+///
+/// 采用32位FNV-1a算法，对class名（不含`.`）的utf8字节计算：
+/// ```text
+/// hash = 0x811c9dc5
+/// for byte in name:
+///     hash = (hash ^ byte) * 0x01000193  (mod 2^32)
+/// ```
+/// 该算法是稳定的，前端工具链可使用相同算法预先计算class id
+pub fn class_name_hash(name: &str) -> usize {
+	let mut hash: u32 = 0x811c9dc5;
+	for byte in name.as_bytes() {
+		hash ^= *byte as u32;
+		hash = hash.wrapping_mul(0x01000193);
+	}
+	hash as usize
+}
+
+/// 将class名（不含`.`）解析为class id
+/// * `cNNNN`（c之后全部为数字）：id为NNNN，即前端工具链已生成的class id
+/// * 其他名称：id为class_name_hash(name)
+pub fn resolve_class_name(name: &str) -> usize {
+	if let Some(num) = name.strip_prefix('c') {
+		if !num.is_empty() && num.bytes().all(|r| r.is_ascii_digit()) {
+			if let Ok(r) = usize::from_str(num) {
+				return r;
+			}
+		}
+	}
+	class_name_hash(name)
 }
 
 pub fn style_list_to_buffer(style_buffer: &mut Vec<u8>, style_list: &mut VecDeque<Attribute>, mut count: usize) -> ClassMeta {
@@ -197,6 +668,57 @@ pub fn style_list_to_buffer(style_buffer: &mut Vec<u8>, style_list: &mut VecDequ
 	class_meta
 }
 
+// 计算var()及相对长度所需的环境
+struct VarContext<'a> {
+	// 查找变量的顺序：Theme > `:root` > 外层的`:root`
+	themes: Vec<&'a Theme>,
+	lengths: &'a LengthContext,
+	scope_hash: usize,
+}
+
+/// 从style_list头部取出count个样式，计算其中的var()及相对长度后写入style_buffer
+/// 计算失败的声明被忽略，错误记录在report中
+fn var_list_to_buffer(
+	style_buffer: &mut Vec<u8>,
+	style_list: &mut VecDeque<Attribute>,
+	count: usize,
+	vars: &VarDeclarations,
+	context: &VarContext,
+	report: &mut ParseReport,
+	class_name: Option<&str>,
+) -> ClassMeta {
+	if vars.decls.is_empty() {
+		return style_list_to_buffer(style_buffer, style_list, count);
+	}
+	let mut list: VecDeque<Attribute> = style_list.drain(..count).collect();
+	for (location, message) in vars.resolve(&mut list, &context.themes, context.lengths, context.scope_hash) {
+		let diagnostic = ParseDiagnostic::new(Severity::Error, location, message);
+		report.push(match class_name {
+			Some(r) => diagnostic.with_class_name(r),
+			None => diagnostic,
+		});
+	}
+	let len = list.len();
+	style_list_to_buffer(style_buffer, &mut list, len)
+}
+
+// meta为刚写入style_buffer末尾的样式，能放入旧样式old的位置时移动过去，并回收末尾的空间
+// 返回最终的ClassMeta，以及旧样式的空间是否成为了无用的空间
+fn reuse_buffer(style_buffer: &mut Vec<u8>, old: Option<&ClassMeta>, mut meta: ClassMeta) -> (ClassMeta, bool) {
+	match old {
+		Some(old) if meta.end - meta.start <= old.end - old.start => {
+			let len = meta.end - meta.start;
+			style_buffer.copy_within(meta.start..meta.end, old.start);
+			style_buffer.truncate(meta.start);
+			meta.start = old.start;
+			meta.end = old.start + len;
+			(meta, false)
+		}
+		Some(_) => (meta, true),
+		None => (meta, false),
+	}
+}
+
 pub fn style_to_buffer(style_buffer: &mut Vec<u8>, mut style: Attribute,  class_meta: &mut ClassMeta) {
 
 	match &mut style {
@@ -611,82 +1133,505 @@ pub fn style_to_buffer(style_buffer: &mut Vec<u8>, mut style: Attribute,  class_
             class_meta.class_style_mark.set(AutoReduceType::get_type() as usize, true);
 			r.write(style_buffer);
         },
+        Attribute::Perspective(r) => unsafe {
+            class_meta.class_style_mark.set(PerspectiveType::get_type() as usize, true);
+			r.write(style_buffer);
+        },
+        Attribute::TransformStyle(r) => unsafe {
+            class_meta.class_style_mark.set(TransformStyleType::get_type() as usize, true);
+			r.write(style_buffer);
+        },
 	}
 	std::mem::forget(style);
 }
 
+/// 写入重置样式（只有样式索引，没有值）
+pub fn reset_to_buffer(style_buffer: &mut Vec<u8>, ty: StyleType, class_meta: &mut ClassMeta) {
+	class_meta.class_style_mark.set(ty as usize, true);
+	style_buffer.extend_from_slice(&(ty as u16 + STYLE_COUNT_MAX).to_ne_bytes());
+}
+
+/// ClassSheet中的一个样式项
+#[derive(Debug, Clone)]
+pub enum ClassStyle {
+	/// 设置样式属性
+	Set(Attribute),
+	/// 重置样式属性（buffer中的索引为 StyleType + STYLE_COUNT_MAX）
+	Reset(StyleType),
+}
+
+/// 读取ClassSheet时的错误
+/// 只能发现buffer结构上的错误（索引、长度），不能检查样式值本身是否有效，见style_from_buffer
+#[derive(Debug, Error, Clone)]
+pub enum ClassReadError {
+	#[error("class is not exist: {0}")]
+	ClassNotFound(usize),
+	#[error("class meta is out of range, start: {start}, end: {end}, buffer len: {len}")]
+	MetaOutOfRange { start: usize, end: usize, len: usize },
+	#[error("invalid style index: {index}, offset: {offset}")]
+	InvalidStyleIndex { index: u16, offset: usize },
+	#[error("unexpected end of style buffer, offset: {offset}, need: {need}, end: {end}")]
+	UnexpectedEnd { offset: usize, need: usize, end: usize },
+}
+
+/// class样式读取器，按写入顺序读出某个class的样式属性
+/// 遇到错误时，返回该错误，并结束迭代
+/// 只能由unsafe的ClassStyleReader::new创建，迭代时依赖其安全约定
+pub struct ClassStyleReader<'a> {
+	buffer: &'a [u8],
+	offset: usize,
+}
+
+impl<'a> ClassStyleReader<'a> {
+	/// # Safety
+	/// 同style_from_buffer：style_buffer中class_meta.start..class_meta.end的样式项必须是当前进程中由style_to_buffer（或reset_to_buffer）写入的
+	pub unsafe fn new(style_buffer: &'a [u8], class_meta: &ClassMeta) -> Result<Self, ClassReadError> {
+		if class_meta.start > class_meta.end || class_meta.end > style_buffer.len() {
+			return Err(ClassReadError::MetaOutOfRange {
+				start: class_meta.start,
+				end: class_meta.end,
+				len: style_buffer.len(),
+			});
+		}
+		Ok(Self {
+			buffer: &style_buffer[..class_meta.end],
+			offset: class_meta.start,
+		})
+	}
+}
+
+impl<'a> Iterator for ClassStyleReader<'a> {
+	type Item = Result<ClassStyle, ClassReadError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.offset >= self.buffer.len() {
+			return None;
+		}
+		// new的安全约定保证了buffer中的样式项都由style_to_buffer写入
+		let r = unsafe { style_from_buffer(self.buffer, &mut self.offset) };
+		if r.is_err() {
+			self.offset = self.buffer.len();
+		}
+		Some(r)
+	}
+}
+
+impl ClassSheet {
+	/// 读取某个class的样式属性
+	///
+	/// # Safety
+	/// style_buffer必须是当前进程中由style_to_buffer写入的（如to_class_sheet、from_binary的结果），
+	/// 不能来自持久化的数据或手动构造，见style_from_buffer
+	pub unsafe fn iter_class(&self, class_name: usize) -> Result<ClassStyleReader, ClassReadError> {
+		match self.class_map.get(&class_name) {
+			Some(class_meta) => ClassStyleReader::new(self.style_buffer.as_slice(), class_meta),
+			None => Err(ClassReadError::ClassNotFound(class_name)),
+		}
+	}
+}
+
+// 安全约定同style_from_buffer
+unsafe fn read_style<T: Attr>(buffer: &[u8], offset: &mut usize) -> Result<T, ClassReadError> {
+	let size = T::size();
+	if buffer.len() - *offset < size {
+		return Err(ClassReadError::UnexpectedEnd { offset: *offset, need: size, end: buffer.len() });
+	}
+	let r = T::read(&buffer[*offset..*offset + size]);
+	*offset += size;
+	Ok(r)
+}
+
+/// 从buffer的offset处读取一个样式项（style_to_buffer的逆操作），并将offset移动到下一个样式项
+/// 索引无效或数据不完整时返回错误
+///
+/// # Safety
+/// 样式值按内存布局保存，含有Atom、Vec等的堆指针，读取时会克隆这些值，因此无法检查值本身是否有效。
+/// buffer中offset处的样式项必须是当前进程中由style_to_buffer（或reset_to_buffer）写入、且其中的值仍然存活的；
+/// 否则，即使索引、长度都正确，也是未定义行为
+pub unsafe fn style_from_buffer(buffer: &[u8], offset: &mut usize) -> Result<ClassStyle, ClassReadError> {
+	let start = *offset;
+	let ty_size = std::mem::size_of::<StyleType>();
+	if start > buffer.len() || buffer.len() - start < ty_size {
+		return Err(ClassReadError::UnexpectedEnd { offset: start, need: ty_size, end: buffer.len() });
+	}
+	let index = std::ptr::read_unaligned(buffer.as_ptr().add(start) as *const u16);
+	if index >= STYLE_COUNT_MAX {
+		return match StyleType::from_u16(index - STYLE_COUNT_MAX) {
+			Some(ty) => {
+				*offset += ty_size;
+				Ok(ClassStyle::Reset(ty))
+			}
+			None => Err(ClassReadError::InvalidStyleIndex { index, offset: start }),
+		};
+	}
+	let ty = match StyleType::from_u16(index) {
+		Some(r) => r,
+		None => return Err(ClassReadError::InvalidStyleIndex { index, offset: start }),
+	};
+	*offset += ty_size;
+
+	macro_rules! read_attr {
+		($ty: expr, $buffer: expr, $offset: expr; $($style_ty: ident => $attr: ident($value_ty: ident)),* $(,)?) => {
+			match $ty {
+				$(StyleType::$style_ty => Attribute::$attr(read_style::<$value_ty>($buffer, $offset)?),)*
+			}
+		};
+	}
+
+	let attr = style_list!(read_attr!(ty, buffer, offset));
+	Ok(ClassStyle::Set(attr))
+}
+
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ClassItem {
     pub count: usize,
     pub class_name: usize,
+    /// 伪类状态，为空时表示class本身的样式
+    #[serde(default)]
+    pub state: PseudoState,
+    /// 自定义属性及含有var()的声明
+    #[serde(default)]
+    pub vars: VarDeclarations,
+}
+
+/// @media中的样式
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MediaGroup {
+    pub query: MediaQueryList,
+    pub class_map: ClassMap,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SelectorItem {
+    pub count: usize,
+    pub selector: Selector,
+    /// 自定义属性及含有var()的声明
+    #[serde(default)]
+    pub vars: VarDeclarations,
+}
+
+/// 诊断级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Severity {
+    /// 不影响其他样式的解析，如：不支持的属性名
+    Warning,
+    /// 样式值错误，该样式被丢弃
+    Error,
+}
+
+/// 一条解析诊断信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseDiagnostic {
+    pub severity: Severity,
+    /// 行号，从1开始
+    pub line: u32,
+    /// 列号，从1开始
+    pub column: u32,
+    /// 所属的class名（不含`.`）
+    pub class_name: Option<String>,
+    /// 所属的keyframes名
+    pub key_frames: Option<String>,
+    /// 属性名
+    pub property: Option<String>,
+    /// 出错的token
+    pub token: Option<String>,
+    pub message: String,
+}
+
+impl ParseDiagnostic {
+    pub fn new(severity: Severity, location: SourceLocation, message: String) -> Self {
+        Self {
+            severity,
+            // SourceLocation的行号从0开始，列号从1开始
+            line: location.line + 1,
+            column: location.column,
+            class_name: None,
+            key_frames: None,
+            property: None,
+            token: None,
+            message,
+        }
+    }
+
+    pub fn with_class_name(mut self, class_name: &str) -> Self {
+        self.class_name = Some(class_name.to_string());
+        self
+    }
+
+    pub fn with_property(mut self, property: &str) -> Self {
+        self.property = Some(property.to_string());
+        self
+    }
+
+    pub fn with_token(mut self, token: &Token) -> Self {
+        self.token = Some(token.to_css_string());
+        self
+    }
+}
+
+impl std::fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {:?}: {}", self.line, self.column, self.severity, self.message)?;
+        if let Some(r) = &self.class_name {
+            write!(f, ", class: {}", r)?;
+        }
+        if let Some(r) = &self.key_frames {
+            write!(f, ", keyframes: {}", r)?;
+        }
+        if let Some(r) = &self.property {
+            write!(f, ", property: {}", r)?;
+        }
+        if let Some(r) = &self.token {
+            write!(f, ", token: {}", r)?;
+        }
+        Ok(())
+    }
+}
+
+/// 解析报告，收集解析过程中的所有诊断信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParseReport {
+    pub diagnostics: Vec<ParseDiagnostic>,
+}
+
+impl ParseReport {
+    pub fn is_empty(&self) -> bool { self.diagnostics.is_empty() }
+
+    /// 是否存在错误级别的诊断
+    pub fn has_errors(&self) -> bool { self.errors().next().is_some() }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ParseDiagnostic> { self.diagnostics.iter().filter(|r| r.severity == Severity::Error) }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ParseDiagnostic> { self.diagnostics.iter().filter(|r| r.severity == Severity::Warning) }
+
+    pub fn push(&mut self, diagnostic: ParseDiagnostic) { self.diagnostics.push(diagnostic); }
+
+    /// 将诊断信息输出到日志
+    pub fn log(&self) {
+        for r in self.diagnostics.iter() {
+            match r.severity {
+                Severity::Warning => log::warn!("{}", r),
+                Severity::Error => log::error!("{}", r),
+            }
+        }
+    }
+
+    pub fn push_token_error(&mut self, severity: Severity, error: &TokenParseError, property: Option<&str>) {
+        let mut diagnostic = ParseDiagnostic::new(severity, error.location, error.error.to_string());
+        diagnostic.property = property.map(|r| r.to_string());
+        diagnostic.token = error.error.token();
+        self.push(diagnostic);
+    }
+
+    pub fn push_item_error(&mut self, error: &ItemParseErrors) {
+        match error {
+            // 空的样式块
+            ItemParseErrors::KeyError { kind: BasicParseErrorKind::EndOfInput, .. } => (),
+            ItemParseErrors::KeyError { location, kind: BasicParseErrorKind::UnexpectedToken(token) } => {
+                let diagnostic = match token {
+                    Token::Ident(name) => ParseDiagnostic::new(Severity::Warning, *location, format!("unsupported property: {}", &**name)).with_property(name),
+                    _ => ParseDiagnostic::new(Severity::Error, *location, "expect: <attribute>".to_string()),
+                };
+                self.push(diagnostic.with_token(token));
+            }
+            ItemParseErrors::KeyError { location, kind } => self.push(ParseDiagnostic::new(Severity::Error, *location, format!("{:?}", kind))),
+            ItemParseErrors::ValueError { attribute, error } => self.push_token_error(Severity::Error, error, Some(&**attribute)),
+        }
+    }
+
+    /// 为start之后新增的诊断信息补充所属的class
+    pub fn fill_class_name(&mut self, start: usize, class_name: &str) {
+        for r in self.diagnostics[start..].iter_mut() {
+            if r.class_name.is_none() {
+                r.class_name = Some(class_name.to_string());
+            }
+        }
+    }
+
+    /// 为start之后新增的诊断信息补充所属的keyframes
+    pub fn fill_key_frames(&mut self, start: usize, name: &str) {
+        for r in self.diagnostics[start..].iter_mut() {
+            if r.key_frames.is_none() {
+                r.key_frames = Some(name.to_string());
+            }
+        }
+    }
 }
 
 pub fn parse_class_map_from_string(value: &str, scope_hash: usize) -> Result<ClassMap, String> {
+    let (classes, report) = parse_class_map_from_string_with_report(value, scope_hash);
+    report.log();
+    Ok(classes)
+}
+
+/// 解析css字符串，同时返回解析过程中产生的所有诊断信息
+pub fn parse_class_map_from_string_with_report(value: &str, scope_hash: usize) -> (ClassMap, ParseReport) {
     let mut classes: ClassMap = ClassMap::default();
+    let mut report = ParseReport::default();
     let mut input = ParserInput::new(value);
     let mut parse = Parser::new(&mut input);
 
 	classes.key_frames.scope_hash = scope_hash;
-    loop {
-        if parse.is_exhausted() {
-            return Ok(classes);
-        }
-
-        if let Err(e) = parse_css_item(&mut classes, &mut parse, scope_hash) {
-            log::error!("parse class err: {:?}", e);
+    while !parse.is_exhausted() {
+        if let Err(e) = parse_css_item(&mut classes, &mut parse, scope_hash, &mut report) {
+            report.push_token_error(Severity::Error, &e, None);
         }
     }
+    classes.resolve_key_frames(&[], &mut report);
+    (classes, report)
 }
 
 pub fn parse_style_list_from_string(value: &str, scope_hash: usize) -> Result<VecDeque<Attribute>, String> {
+    let (list, report) = parse_style_list_from_string_with_report(value, scope_hash);
+    report.log();
+    Ok(list)
+}
+
+/// 解析样式列表字符串，同时返回解析过程中产生的所有诊断信息
+/// 相对长度使用默认的LengthContext换算，其中视口尺寸未知，含有vw等的声明被忽略并报告，需要时应使用parse_style_list_from_string_with_context
+pub fn parse_style_list_from_string_with_report(value: &str, scope_hash: usize) -> (VecDeque<Attribute>, ParseReport) {
+    parse_style_list_from_string_with_context(value, scope_hash, &LengthContext::default())
+}
+
+/// 解析样式列表字符串，相对长度（em、rem、vw等）根据lengths换算为px，同时返回解析过程中产生的所有诊断信息
+pub fn parse_style_list_from_string_with_context(value: &str, scope_hash: usize, lengths: &LengthContext) -> (VecDeque<Attribute>, ParseReport) {
     let mut list = VecDeque::default();
+    let mut report = ParseReport::default();
     let mut input = ParserInput::new(value);
     let mut parse = Parser::new(&mut input);
 
-    let _ = parser_style_items(&mut parse, &mut list, scope_hash);
-    Ok(list)
+    parser_style_items_with_context(&mut parse, &mut list, scope_hash, lengths, &mut report);
+    (list, report)
 }
 
 // 解析css文件中的每一项
-pub fn parse_css_item<'i, 't>(context: &mut ClassMap, input: &mut Parser<'i, 't>, scope_hash: usize) -> Result<(), TokenParseError<'i>> {
+pub fn parse_css_item<'i, 't>(context: &mut ClassMap, input: &mut Parser<'i, 't>, scope_hash: usize, report: &mut ParseReport) -> Result<(), TokenParseError<'i>> {
+    input.skip_whitespace();
+    let location = input.current_source_location();
+    let position = input.position();
     let next = input.next()?;
     match next {
         Token::Delim(r) if r == &'.' => {
-            // 解析class
-            let class_name = input.expect_ident()?.as_ref();
-            log::trace!("class: {}", class_name);
-
-            let class_name = match usize::from_str(&class_name[1..class_name.len()]) {
-                Ok(r) => r,
-                Err(_) => usize::MAX,
-            };
+            // 解析选择器
+            let mut names = Vec::new();
+            let (selector, state) = parse_selector(input, &mut names)?;
+            let selector_str = input.slice_from(position).trim().to_string();
+            log::trace!("selector: {}", selector_str);
+
+            // class名hash冲突时，整条规则被忽略，保留先定义的class
+            let mut collision = false;
+            for (class_name, name) in names.iter() {
+                if let Some(r) = context.insert_class_name(*class_name, name) {
+                    let message = format!("class name collision: {} and {} both resolve to {}", r, name, class_name);
+                    report.push(ParseDiagnostic::new(Severity::Error, location, message).with_class_name(name));
+                    collision = true;
+                }
+            }
 
-            let start = context.attrs.len();
+            let report_start = report.diagnostics.len();
             input.expect_curly_bracket_block()?;
+            let mut attrs = VecDeque::new();
+            let mut vars = VarDeclarations::default();
             let _ = input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|i| {
-                Ok(parser_style_items(i, &mut context.attrs, scope_hash))
+                parser_style_items_with_vars(i, &mut attrs, &mut vars, scope_hash, report);
+                Ok(())
             });
-
-            if class_name != usize::MAX {
+            if collision {
+                report.fill_class_name(report_start, &selector_str);
+            } else if selector.is_single_class() {
+                report.fill_class_name(report_start, &names[0].1);
                 context.classes.push(ClassItem {
-                    count: context.attrs.len() - start,
-                    class_name: class_name,
+                    count: attrs.len(),
+                    class_name: selector.compound.classes[0],
+                    state,
+                    vars,
                 });
+                context.attrs.extend(attrs);
+            } else {
+                report.fill_class_name(report_start, &selector_str);
+                context.selectors.push(SelectorItem {
+                    count: attrs.len(),
+                    selector,
+                    vars,
+                });
+                context.selector_attrs.extend(attrs);
+            }
+        }
+        Token::Colon => {
+            // 解析:root
+            let name_location = input.current_source_location();
+            let name = input.expect_ident_cloned()?;
+            if !name.eq_ignore_ascii_case("root") {
+                return Err(TokenParseError::from_expect(name_location, "root", Token::Ident(name)));
+            }
+            input.expect_curly_bracket_block()?;
+
+            let mut attrs = VecDeque::new();
+            let mut vars = VarDeclarations::default();
+            let report_start = report.diagnostics.len();
+            let _ = input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|i| {
+                parser_style_items_with_vars(i, &mut attrs, &mut vars, scope_hash, report);
+                Ok(())
+            });
+            if !attrs.is_empty() || !vars.decls.is_empty() {
+                report.push(ParseDiagnostic::new(Severity::Warning, location, "only custom properties are supported in :root".to_string()));
+            }
+            report.fill_class_name(report_start, ":root");
+            context.root_vars.vars.extend(vars.vars);
+        }
+        Token::AtKeyword(name) if &**name == "media" => {
+            // 解析@media
+            let query = parse_media_query_list(input)?;
+            log::trace!("media: {:?}", query);
+            input.expect_curly_bracket_block()?;
+
+            let mut class_map = ClassMap::default();
+            class_map.key_frames.scope_hash = scope_hash;
+            let _ = input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|i| {
+                while !i.is_exhausted() {
+                    if let Err(e) = parse_css_item(&mut class_map, i, scope_hash, report) {
+                        report.push_token_error(Severity::Error, &e, None);
+                    }
+                }
+                Ok(())
+            });
+
+            for (class_name, name) in class_map.class_names.iter() {
+                if let Some(r) = context.insert_class_name(*class_name, name) {
+                    let message = format!("class name collision: {} and {} both resolve to {}", r, name, class_name);
+                    report.push(ParseDiagnostic::new(Severity::Error, location, message).with_class_name(name));
+                }
             }
+            context.media.push(MediaGroup { query, class_map });
         }
         Token::AtKeyword(name) if &**name == "keyframes" => {
             // 解析keyframes
-            let name = input.expect_ident()?;
+            let name = input.expect_ident()?.clone();
             log::trace!("parse keyframes start: {:?}", name);
-            let name = Atom::from(&**name);
-            let key_frames = parse_key_frames(input, scope_hash)?;
+            let report_start = report.diagnostics.len();
+            let mut vars = XHashMap::default();
+            let key_frames = parse_key_frames(input, scope_hash, &mut vars, report);
+            report.fill_key_frames(report_start, &name);
+            let (key_frames, timing_functions) = key_frames?;
             if key_frames.len() > 0 {
+                let name = Atom::from(&*name);
+                // 同名的keyframes，后面的覆盖前面的
+                if timing_functions.len() > 0 {
+                    context.key_frames.timing_functions.insert(name.clone(), timing_functions);
+                } else {
+                    context.key_frames.timing_functions.remove(&name);
+                }
+                if vars.len() > 0 {
+                    context.key_frames.pending_vars.insert(name.clone(), vars);
+                } else {
+                    context.key_frames.pending_vars.remove(&name);
+                }
                 context.key_frames.frames.insert(name, key_frames);
             }
         }
         ref i => {
-            log::warn!("Unexpected css: {:?}", i);
+            report.push(ParseDiagnostic::new(Severity::Warning, location, "unexpected css".to_string()).with_token(i));
             loop {
                 if input.is_exhausted() {
                     return Ok(());
@@ -701,12 +1646,251 @@ pub fn parse_css_item<'i, 't>(context: &mut ClassMap, input: &mut Parser<'i, 't>
     Ok(())
 }
 
-pub fn parser_style_items<'i, 't>(input: &mut Parser<'i, 't>, arr: &mut VecDeque<Attribute>, scope_hash: usize) {
+/// 解析选择器（第一个`.`已被解析），解析到`{`之前停止，返回选择器及其伪类状态
+/// names中依次记录选择器中出现的class id和class名
+/// 伪类只能跟在单个class之后（`.btn:hover`）
+pub fn parse_selector<'i, 't>(input: &mut Parser<'i, 't>, names: &mut Vec<(usize, String)>) -> Result<(Selector, PseudoState), TokenParseError<'i>> {
+    let mut compounds: Vec<CompoundSelector> = Vec::new();
+    let mut combinators: Vec<Combinator> = Vec::new();
+    let mut compound = CompoundSelector::default();
+    let mut combinator: Option<Combinator> = None;
+    let mut state = PseudoState::NONE;
+    let mut expect_class = true;
     loop {
-        if let Err(e) = parse_style_item(arr, scope_hash, input) {
-			if let ItemParseErrors::ValueError { .. } = e {
-				log::warn!("{}", e);
-			}
+        if expect_class {
+            // `.`之后必须紧跟class名
+            let location = input.current_source_location();
+            let name = match input.next_including_whitespace()?.clone() {
+                Token::Ident(r) => r,
+                t => return Err(TokenParseError::from_expect(location, "<class-name>", t)),
+            };
+            let class_name = resolve_class_name(&name);
+            names.push((class_name, name.to_string()));
+            compound.classes.push(class_name);
+            expect_class = false;
+            continue;
+        }
+
+        let state = input.state();
+        let location = input.current_source_location();
+        match input.next_including_whitespace()?.clone() {
+            Token::WhiteSpace(_) => {
+                if combinator.is_none() {
+                    combinator = Some(Combinator::Descendant);
+                }
+            }
+            Token::Delim('>') => combinator = Some(Combinator::Child),
+            Token::Colon if combinator.is_none() && compounds.is_empty() && compound.classes.len() == 1 => {
+                let location = input.current_source_location();
+                match input.next_including_whitespace()?.clone() {
+                    Token::Ident(name) if let Some(r) = PseudoState::from_name(&name) => state |= r,
+                    t => return Err(TokenParseError::from_expect(location, "hover | active | focus | disabled", t)),
+                }
+            }
+            Token::Delim('.') if state.is_empty() => {
+                if let Some(r) = combinator.take() {
+                    compounds.push(std::mem::take(&mut compound));
+                    combinators.push(r);
+                }
+                expect_class = true;
+            }
+            Token::CurlyBracketBlock if combinator != Some(Combinator::Child) => {
+                input.reset(&state);
+                break;
+            }
+            t => return Err(TokenParseError::from_expect(location, "<selector>", t)),
+        }
+    }
+
+    // 祖先选择器从近到远排列
+    let ancestors = compounds.into_iter().zip(combinators).rev().map(|(compound, combinator)| (combinator, compound)).collect();
+    Ok((Selector { compound, ancestors }, state))
+}
+
+/// 解析自定义属性（`--name: value`）或含有var()、相对长度、currentColor的声明，记录在vars中
+/// 不是此类声明时返回false，且不消耗输入
+fn parse_var_item<'i, 't>(input: &mut Parser<'i, 't>, index: usize, vars: &mut VarDeclarations) -> bool {
+    let state = input.state();
+    input.skip_whitespace();
+    let location = input.current_source_location();
+    let name = match input.next() {
+        Ok(Token::Ident(r)) => Some(r.clone()),
+        _ => None,
+    };
+    let name = match name {
+        Some(r) if input.expect_colon().is_ok() => r,
+        _ => {
+            input.reset(&state);
+            return false;
+        }
+    };
+
+    input.skip_whitespace();
+    let start = input.position();
+    let has_var = input.parse_until_before::<_, _, ()>(Delimiter::Semicolon, |i| Ok(is_deferred_value(i))).unwrap_or(false);
+    let value = input.slice_from(start).trim().to_string();
+    if name.starts_with("--") {
+        vars.vars.push((name.to_string(), value));
+    } else if has_var {
+        vars.decls.push(VarDeclaration {
+            index,
+            name: name.to_string(),
+            value,
+            line: location.line,
+            column: location.column,
+        });
+    } else {
+        input.reset(&state);
+        return false;
+    }
+    true
+}
+
+/// 值中是否含有var()、相对长度或currentColor，此类值需要在编译时计算，消耗所有输入
+fn is_deferred_value<'i, 't>(input: &mut Parser<'i, 't>) -> bool {
+    let mut ret = false;
+    loop {
+        let nested = match input.next() {
+            Ok(Token::Function(name)) if name.eq_ignore_ascii_case("var") => {
+                ret = true;
+                false
+            }
+            Ok(Token::Dimension { unit, .. }) if RelativeUnit::from_name(unit).is_some() => {
+                ret = true;
+                false
+            }
+            Ok(Token::Ident(name)) if name.eq_ignore_ascii_case("currentcolor") => {
+                ret = true;
+                false
+            }
+            Ok(Token::Function(_)) | Ok(Token::ParenthesisBlock) | Ok(Token::SquareBracketBlock) | Ok(Token::CurlyBracketBlock) => true,
+            Ok(_) => false,
+            Err(_) => break,
+        };
+        if nested && input.parse_nested_block::<_, _, ()>(|i| Ok(is_deferred_value(i))).unwrap_or(false) {
+            ret = true;
+        }
+    }
+    ret
+}
+
+/// 解析@media的查询列表，解析到`{`之前停止
+pub fn parse_media_query_list<'i, 't>(input: &mut Parser<'i, 't>) -> Result<MediaQueryList, TokenParseError<'i>> {
+    let list = input.parse_until_before::<_, _, TokenErrorsInfo<'i>>(Delimiter::CurlyBracketBlock, |i| {
+        if i.is_exhausted() {
+            return Ok(Vec::new());
+        }
+        i.parse_comma_separated(|i| Ok(parse_media_query(i)?))
+    })?;
+    Ok(MediaQueryList(list))
+}
+
+fn parse_media_query<'i, 't>(input: &mut Parser<'i, 't>) -> Result<MediaQuery, TokenParseError<'i>> {
+    let mut query = MediaQuery::default();
+    let location = input.current_source_location();
+    if let Ok(mut ident) = input.try_parse(|i| i.expect_ident_cloned()) {
+        if ident.eq_ignore_ascii_case("not") || ident.eq_ignore_ascii_case("only") {
+            query.not = ident.eq_ignore_ascii_case("not");
+            ident = input.expect_ident_cloned()?;
+        }
+        query.media_type = match &*ident.to_ascii_lowercase() {
+            "all" => MediaType::All,
+            "screen" => MediaType::Screen,
+            "print" => MediaType::Print,
+            _ => return Err(TokenParseError::from_expect(location, "all | screen | print", Token::Ident(ident))),
+        };
+    } else {
+        query.features.push(parse_media_feature(input)?);
+    }
+
+    while input.try_parse(|i| i.expect_ident_matching("and")).is_ok() {
+        query.features.push(parse_media_feature(input)?);
+    }
+    Ok(query)
+}
+
+fn parse_media_feature<'i, 't>(input: &mut Parser<'i, 't>) -> Result<MediaFeature, TokenParseError<'i>> {
+    input.expect_parenthesis_block()?;
+    Ok(input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|i| {
+        let location = i.current_source_location();
+        let ident = i.expect_ident_cloned()?;
+        i.expect_colon()?;
+
+        let name = ident.to_ascii_lowercase();
+        let (range, name) = if let Some(r) = name.strip_prefix("min-") {
+            (MediaRange::Min, r)
+        } else if let Some(r) = name.strip_prefix("max-") {
+            (MediaRange::Max, r)
+        } else {
+            (MediaRange::Exact, name.as_str())
+        };
+        let feature = match name {
+            "width" => MediaFeature::Width(range, parse_len(i)?),
+            "height" => MediaFeature::Height(range, parse_len(i)?),
+            "resolution" => MediaFeature::Resolution(range, parse_resolution(i)?),
+            "orientation" if range == MediaRange::Exact => {
+                let location = i.current_source_location();
+                let r = i.expect_ident_cloned()?;
+                match &*r.to_ascii_lowercase() {
+                    "portrait" => MediaFeature::Orientation(Orientation::Portrait),
+                    "landscape" => MediaFeature::Orientation(Orientation::Landscape),
+                    _ => return Err(TokenParseError::from_expect(location, "portrait | landscape", Token::Ident(r)))?,
+                }
+            }
+            "platform" if range == MediaRange::Exact => MediaFeature::Platform(parse_media_name(i)?),
+            "lang" if range == MediaRange::Exact => MediaFeature::Lang(parse_media_name(i)?),
+            _ => return Err(TokenParseError::from_expect(location, "width | height | resolution | orientation | platform | lang", Token::Ident(ident)))?,
+        };
+        Ok(feature)
+    })?)
+}
+
+/// 解析分辨率，返回dppx
+fn parse_resolution<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, TokenParseError<'i>> {
+    let location = input.current_source_location();
+    let token = input.next()?;
+    if let Token::Dimension { value, ref unit, .. } = *token {
+        match &*unit.to_ascii_lowercase() {
+            "dppx" | "x" => return Ok(value),
+            "dpi" => return Ok(value / 96.0),
+            "dpcm" => return Ok(value * 2.54 / 96.0),
+            _ => (),
+        }
+    }
+    Err(TokenParseError::from_expect(location, "<resolution>", token.clone()))
+}
+
+/// 解析平台、语言等名称（标识符或字符串）
+fn parse_media_name<'i, 't>(input: &mut Parser<'i, 't>) -> Result<String, TokenParseError<'i>> {
+    let location = input.current_source_location();
+    match input.next()? {
+        Token::Ident(r) | Token::QuotedString(r) => Ok(r.to_string()),
+        t => Err(TokenParseError::from_expect(location, "<ident> | <string>", t.clone())),
+    }
+}
+
+/// 解析样式列表，其中的var()只能使用同一样式列表中的自定义属性或默认值，在解析时立即计算
+/// 相对长度使用默认的LengthContext换算，其中视口尺寸未知，含有vw等的声明被忽略并报告
+pub fn parser_style_items<'i, 't>(input: &mut Parser<'i, 't>, arr: &mut VecDeque<Attribute>, scope_hash: usize, report: &mut ParseReport) {
+    parser_style_items_with_context(input, arr, scope_hash, &LengthContext::default(), report);
+}
+
+/// 解析样式列表，相对长度根据lengths换算为px
+pub fn parser_style_items_with_context<'i, 't>(input: &mut Parser<'i, 't>, arr: &mut VecDeque<Attribute>, scope_hash: usize, lengths: &LengthContext, report: &mut ParseReport) {
+    let mut vars = VarDeclarations::default();
+    parser_style_items_with_vars(input, arr, &mut vars, scope_hash, report);
+    for (location, message) in vars.resolve(arr, &[], lengths, scope_hash) {
+        report.push(ParseDiagnostic::new(Severity::Error, location, message));
+    }
+}
+
+/// 解析样式列表，自定义属性和含有var()、相对长度、currentColor的声明不解析为样式，而是记录在vars中（位置为在arr中的位置）
+pub fn parser_style_items_with_vars<'i, 't>(input: &mut Parser<'i, 't>, arr: &mut VecDeque<Attribute>, vars: &mut VarDeclarations, scope_hash: usize, report: &mut ParseReport) {
+    loop {
+        if parse_var_item(input, arr.len(), vars) {
+            let _r = input.try_parse(|i| i.expect_semicolon());
+        } else if let Err(e) = parse_style_item(arr, scope_hash, input) {
+			report.push_item_error(&e);
             end_cur_attr(input);
         } else {
             // 成功后，尝试解析一个或多个分号
@@ -718,65 +1902,92 @@ pub fn parser_style_items<'i, 't>(input: &mut Parser<'i, 't>, arr: &mut VecDeque
     }
 }
 
+/// 解析关键帧列表，返回每个进度的属性，以及关键帧中声明的animation-timing-function
+/// 含有var()、相对长度或currentColor的声明记录在vars中，由调用者计算
 pub fn parse_key_frames<'i, 't>(
     input: &mut Parser<'i, 't>,
 	scope_hash: usize,
-) -> Result<XHashMap<NotNan<f32>, VecDeque<Attribute>>, TokenParseError<'i>> {
+	key_frame_vars: &mut XHashMap<NotNan<f32>, VarDeclarations>,
+	report: &mut ParseReport,
+) -> Result<(XHashMap<NotNan<f32>, VecDeque<Attribute>>, XHashMap<NotNan<f32>, AnimationTimingFunction>), TokenParseError<'i>> {
     let mut key_frames: XHashMap<NotNan<f32>, VecDeque<Attribute>> = XHashMap::default();
+    let mut timing_functions: XHashMap<NotNan<f32>, AnimationTimingFunction> = XHashMap::default();
     input.expect_curly_bracket_block()?;
     Ok(input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|i| {
         loop {
-            match parse_key_frame(i, scope_hash) {
-                Ok((progress, attrs)) => {
-                    if attrs.len() > 0 {
-                        match key_frames.entry(progress) {
-                            Entry::Occupied(mut r) => r.get_mut().extend(attrs),
-                            Entry::Vacant(r) => {
-                                r.insert(attrs);
-                            }
-                        };
+            let mut vars = VarDeclarations::default();
+            match parse_key_frame(i, scope_hash, &mut vars, report) {
+                Ok((progress, mut attrs)) => {
+                    // animation-timing-function不是关键帧的属性，而是该关键帧到下一个关键帧的缓动函数，单独保存（重复声明时，后面的覆盖前面的）
+                    if let Some(r) = take_timing_function(&mut attrs, &mut vars.decls) {
+                        timing_functions.insert(progress, r);
+                    }
+                    if attrs.len() > 0 || !vars.is_empty() {
+                        let frame = key_frames.entry(progress).or_default();
+                        let len = frame.len();
+                        frame.extend(attrs);
+                        if !vars.is_empty() {
+                            key_frame_vars.entry(progress).or_default().extend(vars, len);
+                        }
                     }
                 }
                 Err(e) => {
                     if i.is_exhausted() {
                         break;
                     } else {
-                        log::error!("parse_key_frames style error: {:?}", e);
+                        report.push_token_error(Severity::Error, &e, None);
                     }
                 }
             }
         }
-        Ok(key_frames)
+        // 没有属性的关键帧不会保存，其缓动函数也没有意义
+        timing_functions.retain(|progress, _| key_frames.contains_key(progress));
+        Ok((key_frames, timing_functions))
     })?)
 }
 
-pub fn parse_key_frame<'i, 't>(input: &mut Parser<'i, 't>, scope_hash: usize) -> Result<(NotNan<f32>, VecDeque<Attribute>), TokenParseError<'i>> {
+/// 解析一个关键帧，含有var()、相对长度或currentColor的声明记录在vars中（位置为在返回的属性列表中的位置）
+pub fn parse_key_frame<'i, 't>(
+    input: &mut Parser<'i, 't>,
+    scope_hash: usize,
+    vars: &mut VarDeclarations,
+    report: &mut ParseReport,
+) -> Result<(NotNan<f32>, VecDeque<Attribute>), TokenParseError<'i>> {
     let progress = parse_key_frame_progress(input)?;
     let mut attrs = VecDeque::default();
     input.expect_curly_bracket_block()?;
     if let Err(r) = input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|i| {
-        loop {
-            if let Err(e) = parse_style_item(&mut attrs,  scope_hash, i) {
-				if let ItemParseErrors::ValueError { .. } = e {
-					log::warn!("{}", e);
-				}
-                end_cur_attr(i);
-            } else {
-                // 成功后，尝试解析一个分号
-                let _r = i.try_parse(|i| i.expect_semicolon());
-            }
-            if i.is_exhausted() {
-                break;
-            }
-        }
+        parser_style_items_with_vars(i, &mut attrs, vars, scope_hash, report);
         Ok(())
     }) {
-        log::error!("parse_key_frames fail, {:?}", r);
+        report.push_token_error(Severity::Error, &r.into(), None);
     }
 
     Ok((progress, attrs))
 }
 
+// 取出attrs中的animation-timing-function（重复声明时取最后一个），decls中位于其后的声明的位置随之前移
+pub(crate) fn take_timing_function(attrs: &mut VecDeque<Attribute>, decls: &mut [VarDeclaration]) -> Option<AnimationTimingFunction> {
+    let mut ret = None;
+    let mut i = 0;
+    while i < attrs.len() {
+        if let Attribute::AnimationTimingFunction(r) = &attrs[i] {
+            if let Some(r) = r.0.first() {
+                ret = Some(r.clone());
+            }
+            attrs.remove(i);
+            for decl in decls.iter_mut() {
+                if decl.index > i {
+                    decl.index -= 1;
+                }
+            }
+        } else {
+            i += 1;
+        }
+    }
+    ret
+}
+
 pub fn end_cur_attr<'i, 't>(input: &mut Parser<'i, 't>) {
     loop {
         if input.is_exhausted() {
@@ -849,7 +2060,7 @@ fn parse_border_image_slice<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Border
     })
 }
 
-fn parse_top_right_bottom_left<'i, 't, T: StyleParse + Copy + Default>(
+fn parse_top_right_bottom_left<'i, 't, T: StyleParse + Clone + Default>(
     input: &mut Parser<'i, 't>,
 ) -> Result<Rect<T>, TokenParseError<'i>> {
     let r = match input.try_parse(|input| T::parse(input)) {
@@ -864,22 +2075,22 @@ fn parse_top_right_bottom_left<'i, 't, T: StyleParse + Copy + Default>(
                     },
                     Err(_) => Rect {
                         top: r1,
-                        right: r2,
+                        right: r2.clone(),
                         bottom: r3,
                         left: r2,
                     },
                 },
                 Err(_) => Rect {
-                    top: r1,
-                    right: r2,
+                    top: r1.clone(),
+                    right: r2.clone(),
                     bottom: r1,
                     left: r2,
                 },
             },
             Err(_) => Rect {
-                top: r1,
-                right: r1,
-                bottom: r1,
+                top: r1.clone(),
+                right: r1.clone(),
+                bottom: r1.clone(),
                 left: r1,
             },
         },
@@ -910,17 +2121,17 @@ pub fn parse_border_radius<'i, 't>(
 	})
 }
 
-fn to_four<T: Clone + Copy>(
+fn to_four<T: Clone>(
     value: Vec<T>,
 ) -> [T; 4] {
 	if value.len() == 1 {
-		return [value[0].clone(), value[0].clone(), value[0].clone(), value[0]];
+		return [value[0].clone(), value[0].clone(), value[0].clone(), value[0].clone()];
 	} else if value.len() == 2 {
-		return [value[0].clone(), value[1].clone(), value[0], value[1]];
+		return [value[0].clone(), value[1].clone(), value[0].clone(), value[1].clone()];
 	} else if value.len() == 3 {
-		return [value[0], value[1].clone(), value[2], value[1]];
+		return [value[0].clone(), value[1].clone(), value[2].clone(), value[1].clone()];
 	} else {
-		return [value[0], value[1], value[2], value[3]];
+		return [value[0].clone(), value[1].clone(), value[2].clone(), value[3].clone()];
 	}
 }
 
@@ -1189,13 +2400,11 @@ pub fn parse_outer_glow<'i, 't>(input: &mut Parser<'i, 't>) -> Result<OuterGlow,
 
 fn parse_transform_origin<'i, 't>(input: &mut Parser<'i, 't>) -> Result<TransformOrigin, TokenParseError<'i>> {
     let x = parse_transform_origin1(input)?;
-    Ok(TransformOrigin::XY(
-        x,
-        match input.try_parse(parse_transform_origin1) {
-            Ok(r) => r,
-            Err(_) => x,
-        },
-    ))
+    let y = match input.try_parse(parse_transform_origin1) {
+        Ok(r) => r,
+        Err(_) => x.clone(),
+    };
+    Ok(TransformOrigin::XY(x, y))
 }
 
 fn parse_transform_origin1<'i, 't>(input: &mut Parser<'i, 't>) -> Result<LengthUnit, TokenParseError<'i>> {
@@ -1206,6 +2415,10 @@ fn parse_transform_origin1<'i, 't>(input: &mut Parser<'i, 't>) -> Result<LengthU
         Token::Percentage { unit_value, .. } => Ok(LengthUnit::Percent(*unit_value)),
         Token::Dimension { value, .. } => Ok(LengthUnit::Pixel(*value)),
         Token::Number { value, .. } => Ok(LengthUnit::Pixel(*value)),
+        Token::Function(r) if is_calc_function(r) => {
+            let name = r.clone();
+            Ok(LengthUnit::from_calc(parse_calc_function(&name, input)?))
+        }
         _ => return Err(TokenParseError::from_expect(location, "center | <length> |<percentage> ",  token.clone()))?,
     }
 }
@@ -1217,10 +2430,119 @@ pub fn parse_len_or_percent<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Length
         Token::Percentage { unit_value, .. } => Ok(LengthUnit::Percent(*unit_value)),
         Token::Dimension { value, .. } => Ok(LengthUnit::Pixel(*value)),
         Token::Number { value, .. } => Ok(LengthUnit::Pixel(*value)),
+        Token::Function(r) if is_calc_function(r) => {
+            let name = r.clone();
+            Ok(LengthUnit::from_calc(parse_calc_function(&name, input)?))
+        }
         _ => return Err(TokenParseError::from_expect(location, "<length> |<percentage> ",  token.clone()))?,
     }
 }
 
+/// calc()中的值，数字与长度分开记录，用于检查乘除法是否合法
+enum CalcValue {
+    Number(f32),
+    Length(CalcExpr),
+}
+
+impl CalcValue {
+    // 单独的数字按px处理
+    fn into_length(self) -> CalcExpr {
+        match self {
+            CalcValue::Number(r) => CalcExpr::Value { pixel: r, percent: 0.0 },
+            CalcValue::Length(r) => r,
+        }
+    }
+}
+
+fn is_calc_function(name: &str) -> bool { matches!(name, "calc" | "min" | "max" | "clamp") }
+
+/// 解析calc()、min()、max()、clamp()，函数名已被读取
+/// 长度单位仅支持px和%；只含px的min()、max()、clamp()会被直接计算
+fn parse_calc_function<'i, 't>(name: &str, input: &mut Parser<'i, 't>) -> Result<CalcExpr, TokenParseError<'i>> {
+    Ok(input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|i| {
+        let location = i.current_source_location();
+        let mut args = vec![parse_calc_sum(i)?.into_length()];
+        while i.try_parse(|i| i.expect_comma()).is_ok() {
+            args.push(parse_calc_sum(i)?.into_length());
+        }
+        let pixel_only = args.iter().all(|r| matches!(r, CalcExpr::Value { percent, .. } if *percent == 0.0));
+        let r = match name {
+            "calc" if args.len() == 1 => return Ok(args.pop().unwrap()),
+            "min" => CalcExpr::Min(args),
+            "max" => CalcExpr::Max(args),
+            "clamp" if args.len() == 3 => {
+                let max = args.pop().unwrap();
+                let value = args.pop().unwrap();
+                let min = args.pop().unwrap();
+                CalcExpr::Clamp(Box::new(min), Box::new(value), Box::new(max))
+            }
+            _ => return Err(TokenParseError::from_message(location, format!("invalid argument count of {}()", name)))?,
+        };
+        if pixel_only {
+            Ok(CalcExpr::Value { pixel: r.eval(0.0), percent: 0.0 })
+        } else {
+            Ok(r)
+        }
+    })?)
+}
+
+fn parse_calc_sum<'i, 't>(input: &mut Parser<'i, 't>) -> Result<CalcValue, TokenParseError<'i>> {
+    let mut r = parse_calc_product(input)?;
+    // css要求+、-两侧有空白，`100% -20px`中的`-20px`会被解析为负数，因此不会被当作减法
+    while let Ok(sign) = input.try_parse::<_, _, TokenParseError>(|i| {
+        let location = i.current_source_location();
+        match i.next()? {
+            Token::Delim('+') => Ok(1.0),
+            Token::Delim('-') => Ok(-1.0),
+            t => Err(TokenParseError::from_expect(location, "+ | -", t.clone())),
+        }
+    }) {
+        r = match (r, parse_calc_product(input)?) {
+            (CalcValue::Number(r1), CalcValue::Number(r2)) => CalcValue::Number(r1 + r2 * sign),
+            (r1, r2) => CalcValue::Length(r1.into_length() + r2.into_length() * sign),
+        };
+    }
+    Ok(r)
+}
+
+fn parse_calc_product<'i, 't>(input: &mut Parser<'i, 't>) -> Result<CalcValue, TokenParseError<'i>> {
+    let mut r = parse_calc_value(input)?;
+    while let Ok(is_mul) = input.try_parse::<_, _, TokenParseError>(|i| {
+        let location = i.current_source_location();
+        match i.next()? {
+            Token::Delim('*') => Ok(true),
+            Token::Delim('/') => Ok(false),
+            t => Err(TokenParseError::from_expect(location, "* | /", t.clone())),
+        }
+    }) {
+        let location = input.current_source_location();
+        r = match (r, parse_calc_value(input)?, is_mul) {
+            (CalcValue::Number(r1), CalcValue::Number(r2), true) => CalcValue::Number(r1 * r2),
+            (CalcValue::Length(r1), CalcValue::Number(r2), true) | (CalcValue::Number(r2), CalcValue::Length(r1), true) => CalcValue::Length(r1 * r2),
+            (CalcValue::Number(r1), CalcValue::Number(r2), false) if r2 != 0.0 => CalcValue::Number(r1 / r2),
+            (CalcValue::Length(r1), CalcValue::Number(r2), false) if r2 != 0.0 => CalcValue::Length(r1 * (1.0 / r2)),
+            _ => return Err(TokenParseError::from_message(location, "invalid operand of * or /, one side must be a non-zero number".to_string())),
+        };
+    }
+    Ok(r)
+}
+
+fn parse_calc_value<'i, 't>(input: &mut Parser<'i, 't>) -> Result<CalcValue, TokenParseError<'i>> {
+    let location = input.current_source_location();
+    let token = input.next()?;
+    match token {
+        Token::Number { value, .. } => Ok(CalcValue::Number(*value)),
+        Token::Percentage { unit_value, .. } => Ok(CalcValue::Length(CalcExpr::Value { pixel: 0.0, percent: *unit_value })),
+        Token::Dimension { value, unit, .. } if unit.eq_ignore_ascii_case("px") => Ok(CalcValue::Length(CalcExpr::Value { pixel: *value, percent: 0.0 })),
+        Token::ParenthesisBlock => Ok(input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|i| Ok(parse_calc_sum(i)?))?),
+        Token::Function(r) if is_calc_function(r) => {
+            let name = r.clone();
+            Ok(CalcValue::Length(parse_calc_function(&name, input)?))
+        }
+        _ => Err(TokenParseError::from_expect(location, "<number> | <length> | <percentage> | calc()", token.clone())),
+    }
+}
+
 pub fn parse_number<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, TokenParseError<'i>> {
 	Ok(input.expect_number()?)
 }
@@ -1246,8 +2568,10 @@ pub fn parse_transform<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Vec<Transfo
                 "scaleY" => input.parse_nested_block(|input| Ok(TransformFunc::ScaleY(input.expect_number()?))),
                 "translate" => input.parse_nested_block(|input| {
                     let x = parse_len_or_percent(input)?;
-                    input.expect_comma()?;
-                    let y = parse_len_or_percent(input)?;
+                    let y = match input.try_parse(|input| input.expect_comma()) {
+                        Ok(_) => parse_len_or_percent(input)?,
+                        Err(_) => LengthUnit::Pixel(0.0),
+                    };
                     Ok(TransformFunc::Translate(x, y))
                 }),
                 "translateX" => input.parse_nested_block(|input| Ok(TransformFunc::TranslateX(parse_len_or_percent(input)? ))),
@@ -1257,7 +2581,39 @@ pub fn parse_transform<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Vec<Transfo
 				"rotateY" => input.parse_nested_block(|input| Ok(TransformFunc::RotateY(parse_angle(input)?))),
                 "skewX" => input.parse_nested_block(|input| Ok(TransformFunc::SkewX(parse_angle(input)?))),
                 "skewY" => input.parse_nested_block(|input| Ok(TransformFunc::SkewY(parse_angle(input)?))),
-                _ => return Err(TokenParseError::from_expect(location, "scale | scaleX | scaleY | translate | translateX | translateY | rotate | rotateX | rotateY | rotateZ | skewX | skewY", Token::Ident(f.clone())))?,
+                "skew" => input.parse_nested_block(|input| {
+                    let x = parse_angle(input)?;
+                    let y = match input.try_parse(|input| input.expect_comma()) {
+                        Ok(_) => parse_angle(input)?,
+                        Err(_) => 0.0,
+                    };
+                    Ok(TransformFunc::Skew(x, y))
+                }),
+                "translateZ" => input.parse_nested_block(|input| Ok(TransformFunc::TranslateZ(parse_len(input)?))),
+                "translate3d" => input.parse_nested_block(|input| {
+                    let x = parse_len_or_percent(input)?;
+                    input.expect_comma()?;
+                    let y = parse_len_or_percent(input)?;
+                    input.expect_comma()?;
+                    Ok(TransformFunc::Translate3D(x, y, parse_len(input)?))
+                }),
+                "scaleZ" => input.parse_nested_block(|input| Ok(TransformFunc::ScaleZ(input.expect_number()?))),
+                "scale3d" => input.parse_nested_block(|input| {
+                    let [x, y, z] = parse_number_list(input)?;
+                    Ok(TransformFunc::Scale3D(x, y, z))
+                }),
+                "rotate3d" => input.parse_nested_block(|input| {
+                    let [x, y, z] = parse_number_list(input)?;
+                    input.expect_comma()?;
+                    Ok(TransformFunc::Rotate3D(x, y, z, parse_angle(input)?))
+                }),
+                "perspective" => input.parse_nested_block(|input| Ok(TransformFunc::Perspective(parse_perspective(input)?))),
+                "matrix" => input.parse_nested_block(|input| {
+                    let [a, b, c, d, e, f] = parse_number_list(input)?;
+                    Ok(TransformFunc::Matrix(a, b, c, d, e, f))
+                }),
+                "matrix3d" => input.parse_nested_block(|input| Ok(TransformFunc::Matrix3D(Box::new(parse_number_list(input)?)))),
+                _ => return Err(TokenParseError::from_expect(location, "scale | scaleX | scaleY | scaleZ | scale3d | translate | translateX | translateY | translateZ | translate3d | rotate | rotateX | rotateY | rotateZ | rotate3d | skew | skewX | skewY | perspective | matrix | matrix3d", Token::Ident(f.clone())))?,
             }
         });
 		match r {
@@ -1275,6 +2631,26 @@ pub fn parse_transform<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Vec<Transfo
     }
 }
 
+/// 解析以逗号分隔的N个数字
+fn parse_number_list<'i, 't, const N: usize>(input: &mut Parser<'i, 't>) -> Result<[f32; N], TokenParseError<'i>> {
+    let mut list = [0.0; N];
+    for (i, r) in list.iter_mut().enumerate() {
+        if i > 0 {
+            input.expect_comma()?;
+        }
+        *r = input.expect_number()?;
+    }
+    Ok(list)
+}
+
+/// 解析透视距离：none | <length>，none解析为0
+fn parse_perspective<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, TokenParseError<'i>> {
+    if input.try_parse(|input| input.expect_ident_matching("none")).is_ok() {
+        return Ok(0.0);
+    }
+    parse_len(input)
+}
+
 fn parse_object_fit<'i, 't>(input: &mut Parser<'i, 't>) -> Result<FitType, TokenParseError<'i>> {
     let location = input.current_source_location();
     let item = input.expect_ident()?;
@@ -1368,48 +2744,6 @@ pub fn parse_color_hex(value: &str) -> Result<CgColor, ()> {
 
 fn rgba(red: u8, green: u8, blue: u8, alpha: f32) -> CgColor { CgColor::new(red as f32 / 255.0, green as f32 / 255.0, blue as f32 / 255.0, alpha) }
 
-fn parser_color_stop_last<'i>(
-    v: f32,
-    list: &mut Vec<CgColor>,
-    color_stop: &mut Vec<ColorAndPosition>,
-    pre_percent: &mut f32,
-    last_color: Option<CgColor>,
-) -> Result<(), TokenParseError<'i>> {
-    if list.len() > 0 {
-        if color_stop.len() != 0 {
-            let pos = (v - *pre_percent) / list.len() as f32;
-            for i in 0..list.len() {
-                color_stop.push(ColorAndPosition {
-                    position: *pre_percent + pos * (i + 1) as f32,
-                    rgba: list[i].clone(),
-                });
-            }
-        } else {
-            let pos = if list.len() == 1 {
-                0.0
-            } else {
-                (v - *pre_percent) / (list.len() as f32 - 1.0)
-            };
-            for i in 0..list.len() {
-                color_stop.push(ColorAndPosition {
-                    position: *pre_percent + pos * i as f32,
-                    rgba: list[i].clone(),
-                });
-            }
-        }
-
-        list.clear();
-    }
-    *pre_percent = v;
-    if let Some(last_color) = last_color {
-        color_stop.push(ColorAndPosition {
-            position: v,
-            rgba: last_color,
-        });
-    }
-    Ok(())
-}
-
 fn from_hex(c: u8) -> Result<u8, ()> {
     match c {
         b'0'..=b'9' => Ok(c - b'0'),
@@ -1448,6 +2782,7 @@ fn trans_hsi_i(mut i: f32) -> f32 {
 
 
 pub fn parse_style_item<'i, 't>(buffer: &mut VecDeque<Attribute>, scope_hash: usize, input: &mut Parser<'i, 't>) -> Result<(), ItemParseErrors<'i>> {
+    input.skip_whitespace();
     let location = input.current_source_location();
 	let key_token = input.next()?;
 	let name = match key_token {
@@ -1500,8 +2835,8 @@ pub fn parse_style_item_value<'i, 't>(location: SourceLocation, name: CowRcStr<'
         "background-image" => {
             input.expect_colon()?;
             match parse_gradient_image(input)? {
-                GradientImage::Linear(gradient) => {
-                    let ty = BackgroundColorType(Color::LinearGradient(gradient));
+                GradientImage::Gradient(gradient) => {
+                    let ty = BackgroundColorType(gradient);
                     log::trace!("{:?}", ty);
                     buffer.push_back(Attribute::BackgroundColor(ty));
                 }
@@ -1559,8 +2894,14 @@ pub fn parse_style_item_value<'i, 't>(location: SourceLocation, name: CowRcStr<'
         "mask-image" => {
             input.expect_colon()?;
             match parse_gradient_image(input)? {
-                GradientImage::Linear(gradient) => {
-                    let ty = MaskImageType(MaskImage::LinearGradient(gradient));
+                GradientImage::Gradient(gradient) => {
+                    let ty = MaskImageType(match gradient {
+                        Color::LinearGradient(r) => MaskImage::LinearGradient(r),
+                        Color::RadialGradient(r) => MaskImage::RadialGradient(r),
+                        Color::ConicGradient(r) => MaskImage::ConicGradient(r),
+                        // parse_gradient_image只会返回渐变
+                        Color::RGBA(_) => unreachable!(),
+                    });
                     log::trace!("{:?}", ty);
                     buffer.push_back(Attribute::MaskImage(ty));
                 }
@@ -1769,67 +3110,67 @@ pub fn parse_style_item_value<'i, 't>(location: SourceLocation, name: CowRcStr<'
         }
         "width" => {
             input.expect_colon()?;
-            let ty = WidthType(Dimension::parse(input)?);
+            let ty = WidthType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::Width(ty));
         }
         "height" => {
             input.expect_colon()?;
-            let ty = HeightType(Dimension::parse(input)?);
+            let ty = HeightType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::Height(ty));
         }
         "left" => {
             input.expect_colon()?;
-            let ty = PositionLeftType(Dimension::parse(input)?);
+            let ty = PositionLeftType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::PositionLeft(ty));
         }
         "bottom" => {
             input.expect_colon()?;
-            let ty = PositionBottomType(Dimension::parse(input)?);
+            let ty = PositionBottomType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::PositionBottom(ty));
         }
         "right" => {
             input.expect_colon()?;
-            let ty = PositionRightType(Dimension::parse(input)?);
+            let ty = PositionRightType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::PositionRight(ty));
         }
         "top" => {
             input.expect_colon()?;
-            let ty = PositionTopType(Dimension::parse(input)?);
+            let ty = PositionTopType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::PositionTop(ty));
         }
         "margin-left" => {
             input.expect_colon()?;
-            let ty = MarginLeftType(Dimension::parse(input)?);
+            let ty = MarginLeftType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::MarginLeft(ty));
         }
         "margin-bottom" => {
             input.expect_colon()?;
-            let ty = MarginBottomType(Dimension::parse(input)?);
+            let ty = MarginBottomType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::MarginBottom(ty));
         }
         "margin-right" => {
             input.expect_colon()?;
-            let ty = MarginRightType(Dimension::parse(input)?);
+            let ty = MarginRightType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::MarginRight(ty));
         }
         "margin-top" => {
             input.expect_colon()?;
-            let ty = MarginTopType(Dimension::parse(input)?);
+            let ty = MarginTopType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::MarginTop(ty));
         }
         "margin" => {
             input.expect_colon()?;
-            let ty = parse_top_right_bottom_left(input)?;
+            let ty = parse_top_right_bottom_left::<LayoutDimension>(input)?;
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::MarginTop(MarginTopType(ty.top)));
             buffer.push_back(Attribute::MarginRight(MarginRightType(ty.right)));
@@ -1838,31 +3179,31 @@ pub fn parse_style_item_value<'i, 't>(location: SourceLocation, name: CowRcStr<'
         }
         "padding-left" => {
             input.expect_colon()?;
-            let ty = PaddingLeftType(Dimension::parse(input)?);
+            let ty = PaddingLeftType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::PaddingLeft(ty));
         }
         "padding-bottom" => {
             input.expect_colon()?;
-            let ty = PaddingBottomType(Dimension::parse(input)?);
+            let ty = PaddingBottomType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::PaddingBottom(ty));
         }
         "padding-right" => {
             input.expect_colon()?;
-            let ty = PaddingRightType(Dimension::parse(input)?);
+            let ty = PaddingRightType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::PaddingRight(ty));
         }
         "padding-top" => {
             input.expect_colon()?;
-            let ty = PaddingTopType(Dimension::parse(input)?);
+            let ty = PaddingTopType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::PaddingTop(ty));
         }
         "padding" => {
             input.expect_colon()?;
-            let ty = parse_top_right_bottom_left(input)?;
+            let ty = parse_top_right_bottom_left::<LayoutDimension>(input)?;
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::PaddingTop(PaddingTopType(ty.top)));
             buffer.push_back(Attribute::PaddingRight(PaddingRightType(ty.right)));
@@ -1913,31 +3254,31 @@ pub fn parse_style_item_value<'i, 't>(location: SourceLocation, name: CowRcStr<'
         }
         "min-width" => {
             input.expect_colon()?;
-            let ty = MinWidthType(Dimension::parse(input)?);
+            let ty = MinWidthType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::MinWidth(ty));
         }
         "min-height" => {
             input.expect_colon()?;
-            let ty = MinHeightType(Dimension::parse(input)?);
+            let ty = MinHeightType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::MinHeight(ty));
         }
         "max-width" => {
             input.expect_colon()?;
-            let ty = MaxWidthType(Dimension::parse(input)?);
+            let ty = MaxWidthType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::MaxWidth(ty));
         }
         "max-height" => {
             input.expect_colon()?;
-            let ty = MaxHeightType(Dimension::parse(input)?);
+            let ty = MaxHeightType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::MaxHeight(ty));
         }
         "flex-basis" => {
             input.expect_colon()?;
-            let ty = FlexBasisType(Dimension::parse(input)?);
+            let ty = FlexBasisType(LayoutDimension::parse(input)?);
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::FlexBasis(ty));
         }
@@ -1983,6 +3324,24 @@ pub fn parse_style_item_value<'i, 't>(location: SourceLocation, name: CowRcStr<'
             log::trace!("{:?}", ty);
             buffer.push_back(Attribute::AutoReduce(ty));
         }
+        "perspective" => {
+            input.expect_colon()?;
+            let ty = PerspectiveType(parse_perspective(input)?);
+            log::trace!("{:?}", ty);
+            buffer.push_back(Attribute::Perspective(ty));
+        }
+        "transform-style" => {
+            input.expect_colon()?;
+            let location = input.current_source_location();
+            let ident = input.expect_ident()?;
+            let ty = TransformStyleType(match ident.as_ref() {
+                "flat" => TransformStyle::Flat,
+                "preserve-3d" => TransformStyle::Preserve3D,
+                _ => return Err(TokenParseError::from_expect(location, "flat | preserve-3d", Token::Ident(ident.clone()))),
+            });
+            log::trace!("{:?}", ty);
+            buffer.push_back(Attribute::TransformStyle(ty));
+        }
         "flex-direction" => {
             input.expect_colon()?;
             let ty = FlexDirectionType(parse_yg_direction(input)?);
@@ -2191,58 +3550,45 @@ pub fn parse_style_item_value<'i, 't>(location: SourceLocation, name: CowRcStr<'
 // }
 
 
+/// 解析animation简写，多个动画用逗号分隔
+/// 每个动画中，关键字优先作为尚未出现的其它属性的值，否则作为动画名（css规范），
+/// 如`animation: ease ease`中，前一个ease为缓动函数，后一个为动画名；`animation: none`中的none为填充模式，动画名取初始值none
+/// 未声明的项取各属性的初始值，因此每个animation-*属性的列表长度都与动画数量相同
 pub fn parse_animation<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Animation, TokenParseError<'i>> {
-	
     let mut animations = Animation::default();
     parse_comma_separated::<_, ()>(input, |input| {
-        let mut has_duration = false;
-        let location = input.current_source_location();
-        let mut name = Atom::from("");
-        let mut duration = Time::default();
-        let mut timing_function = AnimationTimingFunction::default();
-        let mut iteration_count = IterationCount(1 as f32);
-        let mut delay = Time::default();
-        let mut direction = AnimationDirection::default();
-        let mut fill_mode = AnimationFillMode::default();
-        let mut play_state = AnimationPlayState::default();
+        let mut name = None;
+        let mut duration = None;
+        let mut delay = None;
+        let mut timing_function = None;
+        let mut iteration_count = None;
+        let mut direction = None;
+        let mut fill_mode = None;
+        let mut play_state = None;
         loop {
+            let location = input.current_source_location();
             let token = match input.next() {
                 Ok(r) => r,
                 Err(_r) => break,
             };
 
             match token {
-                Token::Ident(r) => match r.as_ref() {
-                    "normal" => direction = AnimationDirection::Normal,
-                    "reverse" => direction = AnimationDirection::Reverse,
-                    "alternate" => direction = AnimationDirection::Alternate,
-                    "alternate-reverse" => direction = AnimationDirection::AlternateReverse,
-					// 兼容老的gui的错误写法
-					"direction" => direction = AnimationDirection::Normal,
-                    "ease" => timing_function = AnimationTimingFunction::CubicBezier(0.25, 0.1, 0.25, 1.0),
-                    "ease-in" => timing_function = AnimationTimingFunction::CubicBezier(0.42, 0.0, 1.0, 1.0),
-                    "ease-out" => timing_function = AnimationTimingFunction::CubicBezier(0.0, 0.0, 0.58, 1.0),
-                    "ease-in-out" => timing_function = AnimationTimingFunction::CubicBezier(0.42, 0.0, 0.58, 1.0),
-                    "linear" => timing_function = AnimationTimingFunction::Linear,
-					"step" => timing_function = AnimationTimingFunction::Step(1, EStepMode::JumpEnd), // 兼容曾经不规范的写法，移除？TODO
-                    "step-start" => timing_function = AnimationTimingFunction::Step(1, EStepMode::JumpStart),
-                    "step-end" => timing_function = AnimationTimingFunction::Step(1, EStepMode::JumpEnd),
-                    "none" => fill_mode = AnimationFillMode::None,
-                    "forwards" => fill_mode = AnimationFillMode::Forwards,
-                    "backwards" => fill_mode = AnimationFillMode::Backwards,
-                    "both" => fill_mode = AnimationFillMode::Both,
-                    "paused" => play_state = AnimationPlayState::Paused,
-                    "running" => play_state = AnimationPlayState::Running,
-					"infinite" => iteration_count = IterationCount( f32::INFINITY),
-                    ref name_str => {
-                        if name.as_ref() != "" {
-							continue; // 暂时不支持声明多个动画
-                            // return Err(TokenParseError::from_message(location, format!("animation name is multiple, {} and {}", name.as_str(), name_str)));
-                        } else {
-                            name = Atom::from(*name_str);
-                        }
+                Token::Ident(r) => {
+                    let r: &str = r.as_ref();
+                    let is_keyword = set_once(&mut timing_function, timing_function_keyword(r))
+                        || set_once(&mut iteration_count, (r == "infinite").then_some(IterationCount(f32::INFINITY)))
+                        || set_once(&mut direction, direction_keyword(r))
+                        || set_once(&mut fill_mode, fill_mode_keyword(r))
+                        || set_once(&mut play_state, play_state_keyword(r));
+                    if !is_keyword && !set_once(&mut name, Some(Atom::from(r))) {
+                        return Err(TokenParseError::from_message(location, format!("unexpected {} in animation", r)));
                     }
-                },
+                }
+                Token::QuotedString(r) => {
+                    if !set_once(&mut name, Some(Atom::from(r.as_ref()))) {
+                        return Err(TokenParseError::from_message(location, "animation name is multiple".to_string()));
+                    }
+                }
                 Token::Dimension { value, unit, .. } => {
                     let time = if unit.as_ref() == "s" {
                         Time((value * 1000.0) as usize)
@@ -2251,41 +3597,92 @@ pub fn parse_animation<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Animation,
                     } else {
                         return Err(TokenParseError::from_expect(location, "<time>", token.clone()));
                     };
-                    if has_duration {
-                        delay = time;
-                    } else {
-                        duration = time;
-                        has_duration = true;
+                    // 第一个时间为duration，第二个为delay
+                    if !set_once(&mut duration, Some(time)) && !set_once(&mut delay, Some(time)) {
+                        return Err(TokenParseError::from_message(location, "too many <time> in animation".to_string()));
                     }
                 }
-                Token::Function(name) => {
+                Token::Function(name) if timing_function.is_none() => {
 					let name = unsafe{transmute(name.clone())};
-					timing_function = parse_timing_function(input, name)?;
+					timing_function = Some(parse_timing_function(input, name)?);
 				},
 				// 支持老版本gui的写法， 小于0表示无穷次迭代
-                Token::Number { value, .. } => if *value < 0.0 {
-					iteration_count = IterationCount( f32::INFINITY);
-				} else {
-					iteration_count = IterationCount(*value)
-				},
-                _ => break, // 可能是分号，在这里结束解析
+                Token::Number { value, .. } if iteration_count.is_none() => {
+                    iteration_count = Some(IterationCount(if *value < 0.0 { f32::INFINITY } else { *value }));
+                }
+                _ => return Err(TokenParseError::from_expect(location, "<single-animation>", token.clone())),
             };
         }
 
-		animations.name.value.push(name.clone());
-		animations.duration.push(duration);
-		animations.timing_function.push(timing_function.clone());
-		animations.iteration_count.push(iteration_count);
-		animations.delay.push(delay);
-		animations.direction.push(direction);
-		animations.fill_mode.push(fill_mode.clone());
-		animations.play_state.push(play_state.clone());
-      
+		animations.name.value.push(name.unwrap_or_else(|| Atom::from("none")));
+		animations.duration.push(duration.unwrap_or_default());
+		animations.timing_function.push(timing_function.unwrap_or(AnimationTimingFunction::CubicBezier(0.25, 0.1, 0.25, 1.0)));
+		animations.iteration_count.push(iteration_count.unwrap_or_default());
+		animations.delay.push(delay.unwrap_or_default());
+		animations.direction.push(direction.unwrap_or_default());
+		animations.fill_mode.push(fill_mode.unwrap_or_default());
+		animations.play_state.push(play_state.unwrap_or_default());
+
         Ok(())
     })?;
     Ok(animations)
 }
 
+// slot尚未设置且value有效时，设置slot，返回是否设置成功
+fn set_once<T>(slot: &mut Option<T>, value: Option<T>) -> bool {
+    match (slot.is_none(), value) {
+        (true, Some(r)) => {
+            *slot = Some(r);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn timing_function_keyword(name: &str) -> Option<AnimationTimingFunction> {
+    Some(match name {
+        "ease" => AnimationTimingFunction::CubicBezier(0.25, 0.1, 0.25, 1.0),
+        "ease-in" => AnimationTimingFunction::CubicBezier(0.42, 0.0, 1.0, 1.0),
+        "ease-out" => AnimationTimingFunction::CubicBezier(0.0, 0.0, 0.58, 1.0),
+        "ease-in-out" => AnimationTimingFunction::CubicBezier(0.42, 0.0, 0.58, 1.0),
+        "linear" => AnimationTimingFunction::Linear,
+        "step" => AnimationTimingFunction::Step(1, EStepMode::JumpEnd), // 兼容曾经不规范的写法，移除？TODO
+        "step-start" => AnimationTimingFunction::Step(1, EStepMode::JumpStart),
+        "step-end" => AnimationTimingFunction::Step(1, EStepMode::JumpEnd),
+        _ => return None,
+    })
+}
+
+fn direction_keyword(name: &str) -> Option<AnimationDirection> {
+    Some(match name {
+        "normal" => AnimationDirection::Normal,
+        "reverse" => AnimationDirection::Reverse,
+        "alternate" => AnimationDirection::Alternate,
+        "alternate-reverse" => AnimationDirection::AlternateReverse,
+        // 兼容老的gui的错误写法
+        "direction" => AnimationDirection::Normal,
+        _ => return None,
+    })
+}
+
+fn fill_mode_keyword(name: &str) -> Option<AnimationFillMode> {
+    Some(match name {
+        "none" => AnimationFillMode::None,
+        "forwards" => AnimationFillMode::Forwards,
+        "backwards" => AnimationFillMode::Backwards,
+        "both" => AnimationFillMode::Both,
+        _ => return None,
+    })
+}
+
+fn play_state_keyword(name: &str) -> Option<AnimationPlayState> {
+    Some(match name {
+        "running" => AnimationPlayState::Running,
+        "paused" => AnimationPlayState::Paused,
+        _ => return None,
+    })
+}
+
 impl StyleParse for Transition {
     fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, TokenParseError<'i>> {
 		let mut transition = Transition::default();
@@ -2378,28 +3775,105 @@ fn parse_timing_function<'a, 'i, 't>(input: &mut Parser<'i, 't>, name: CowRcStr<
 				},
 			))
 		})?,
-		"linear" => AnimationTimingFunction::Linear,
-		"steps" => input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|input| {
-			let location = input.current_source_location();
-			Ok(AnimationTimingFunction::Step(input.expect_number()? as usize, {
-				if let Ok(_r) = input.expect_comma() {
-					let p = input.expect_ident()?;
-					match p.as_ref() {
-						"jump-start" | "start" => EStepMode::JumpStart,
-						"jump-end" | "end" => EStepMode::JumpEnd,
-						"jump-none" => EStepMode::JumpNone,
-						"jump-both" => EStepMode::JumpEnd,
-						_ => return Err(TokenParseError::from_expect(location, "jump-start | start | jump-end | end | jump-none | jump-both", Token::Ident(p.clone())))?,
-					}
-				} else {
-					EStepMode::JumpStart
-				}
-			}))
-		})?,
+		"linear" => input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|input| Ok(parse_linear_points(input)?))?,
+		"steps" => input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|input| Ok(parse_steps(input)?))?,
 		_ => AnimationTimingFunction::Linear,
 	})
 }
 
+// 解析steps(...)的参数，省略跳跃方式时为jump-end
+fn parse_steps<'i, 't>(input: &mut Parser<'i, 't>) -> Result<AnimationTimingFunction, TokenParseError<'i>> {
+	let location = input.current_source_location();
+	let count = input.expect_integer()?;
+	let mode = if input.try_parse(|input| input.expect_comma()).is_ok() {
+		let location = input.current_source_location();
+		let p = input.expect_ident()?;
+		match p.as_ref() {
+			"jump-start" | "start" => EStepMode::JumpStart,
+			"jump-end" | "end" => EStepMode::JumpEnd,
+			"jump-none" => EStepMode::JumpNone,
+			"jump-both" => EStepMode::JumpBoth,
+			_ => return Err(TokenParseError::from_expect(location, "jump-start | start | jump-end | end | jump-none | jump-both", Token::Ident(p.clone()))),
+		}
+	} else {
+		EStepMode::JumpEnd
+	};
+	// 步数至少为1，jump-none时至少为2
+	let min = if matches!(mode, EStepMode::JumpNone) { 2 } else { 1 };
+	if count < min {
+		return Err(TokenParseError::from_message(location, format!("invalid step count {} of steps()", count)));
+	}
+	Ok(AnimationTimingFunction::Step(count as usize, mode))
+}
+
+// 解析linear(...)的参数，每项为一个输出进度和0~2个输入进度（百分比）
+// 省略的输入进度按css规范补齐：首尾分别为0、1，中间的在前后两个点之间均匀分布；小于前面的输入进度时取前面的值
+fn parse_linear_points<'i, 't>(input: &mut Parser<'i, 't>) -> Result<AnimationTimingFunction, TokenParseError<'i>> {
+	// 兼容曾经的linear()写法
+	if input.is_exhausted() {
+		return Ok(AnimationTimingFunction::Linear);
+	}
+	let location = input.current_source_location();
+	let stops = parse_comma_separated(input, |input| {
+		let location = input.current_source_location();
+		let mut output = None;
+		let mut inputs = Vec::new();
+		while let Ok(token) = input.next() {
+			match token {
+				Token::Number { value, .. } if output.is_none() => output = Some(*value),
+				Token::Percentage { unit_value, .. } if inputs.len() < 2 => inputs.push(*unit_value),
+				token => return Err(TokenParseError::from_expect(location, "<number> && <percentage>{0,2}", token.clone())),
+			}
+		}
+		match output {
+			Some(r) => Ok((r, inputs)),
+			None => Err(TokenParseError::from_message(location, "missing output progress of linear()".to_string())),
+		}
+	})?;
+
+	let mut points: Vec<(Option<f32>, f32)> = Vec::new();
+	for (output, inputs) in stops {
+		if inputs.is_empty() {
+			points.push((None, output));
+		} else {
+			points.extend(inputs.into_iter().map(|r| (Some(r), output)));
+		}
+	}
+	if points.len() < 2 {
+		return Err(TokenParseError::from_message(location, "linear() requires at least 2 points".to_string()));
+	}
+
+	let last = points.len() - 1;
+	if points[0].0.is_none() {
+		points[0].0 = Some(0.0);
+	}
+	if points[last].0.is_none() {
+		points[last].0 = Some(points.iter().filter_map(|r| r.0).fold(1.0, f32::max));
+	}
+	let mut largest = f32::NEG_INFINITY;
+	for r in points.iter_mut() {
+		if let Some(input) = &mut r.0 {
+			*input = input.max(largest);
+			largest = *input;
+		}
+	}
+	let mut i = 1;
+	while i < last {
+		if points[i].0.is_none() {
+			// 首尾的输入进度已经确定，因此前后一定能找到确定的点
+			let start = i - 1;
+			let end = i + points[i..].iter().position(|r| r.0.is_some()).unwrap();
+			let (a, b) = (points[start].0.unwrap(), points[end].0.unwrap());
+			for (j, r) in points.iter_mut().enumerate().take(end).skip(i) {
+				r.0 = Some(a + (b - a) * (j - start) as f32 / (end - start) as f32);
+			}
+			i = end;
+		}
+		i += 1;
+	}
+	Ok(AnimationTimingFunction::LinearPoints(points.into_iter().map(|(input, output)| (input.unwrap(), output)).collect()))
+}
+
 pub fn parse_transation_property<'a, 'i, 't>(input: &mut Parser<'i, 't>) -> Result<usize, TokenParseError<'i>> {
 	let location = input.current_source_location();
 	let token = input.expect_ident()?;
@@ -2449,9 +3923,11 @@ pub fn parse_transation_property1<'a, 'i, 't>(name: &CowRcStr<'a>) -> Result<usi
 		"max-width" => StyleType::MaxWidth as usize,
 		"max-height" => StyleType::MaxHeight as usize,
 		"opacity" => StyleType::Opacity as usize,
-		"tanslate" => StyleType::Translate as usize,
+		// 兼容老的gui的错误写法
+		"translate" | "tanslate" => StyleType::Translate as usize,
 		"scale" => StyleType::Scale as usize,
 		"rotate" => StyleType::Rotate as usize,
+		"perspective" => StyleType::Perspective as usize,
 
 		_ => return Err(()), // 其他属性忽略
 	};
@@ -2475,6 +3951,37 @@ impl StyleParse for Dimension {
             Token::Dimension { value, ref unit, .. } if unit.as_ref() == "px" => Dimension::Points(value),
             Token::Percentage { unit_value, .. } => Dimension::Percent(unit_value),
             Token::Number { value, .. } => Dimension::Points(value),
+            // 布局只支持单一单位，表达式需能化简为px或%
+            Token::Function(ref r) if is_calc_function(r) => {
+                let name = r.clone();
+                match LengthUnit::from_calc(parse_calc_function(&name, input)?) {
+                    LengthUnit::Pixel(r) => Dimension::Points(r),
+                    LengthUnit::Percent(r) => Dimension::Percent(r),
+                    LengthUnit::Calc(_) => return Err(TokenParseError::from_message(location, format!("{}() mixing px and % is not supported by layout", &*name)))?,
+                }
+            }
+            _ => return Err(TokenParseError::from_expect(location, "<length> | <percentage>", token.clone()))?,
+        };
+        Ok(dimension)
+    }
+}
+
+impl StyleParse for LayoutDimension {
+    fn parse<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Self, TokenParseError<'i>> {
+        if input.try_parse(|i| i.expect_ident_matching("auto")).is_ok() {
+            return Ok(LayoutDimension::Auto);
+        }
+
+        let location = input.current_source_location();
+        let token = input.next()?;
+        let dimension = match *token {
+            Token::Dimension { value, ref unit, .. } if unit.as_ref() == "px" => LayoutDimension::Points(value),
+            Token::Percentage { unit_value, .. } => LayoutDimension::Percent(unit_value),
+            Token::Number { value, .. } => LayoutDimension::Points(value),
+            Token::Function(ref r) if is_calc_function(r) => {
+                let name = r.clone();
+                LayoutDimension::from_calc(parse_calc_function(&name, input)?)
+            }
             _ => return Err(TokenParseError::from_expect(location, "<length> | <percentage>", token.clone()))?,
         };
         Ok(dimension)
@@ -2489,6 +3996,10 @@ impl StyleParse for LengthUnit {
 			Token::Percentage { unit_value, .. } => Ok(LengthUnit::Percent(*unit_value)),
 			Token::Dimension { value, .. } => Ok(LengthUnit::Pixel(*value)),
 			Token::Number { value, .. } => Ok(LengthUnit::Pixel(*value)),
+			Token::Function(r) if is_calc_function(r) => {
+				let name = r.clone();
+				Ok(LengthUnit::from_calc(parse_calc_function(&name, input)?))
+			}
 			_ => return Err(TokenParseError::from_expect(location, "<length> | <percentage>", token.clone()))?,
 		}
     }
@@ -2627,31 +4138,8 @@ impl StyleParse for AnimationTimingFunction {
                             },
                         ))
                     })?),
-                    "linear" => {
-                        input.parse_nested_block(|_input| {
-							Ok(())
-                        })?;
-						Ok(AnimationTimingFunction::Linear)
-                        // TODO
-                        
-                    }
-                    "steps" => Ok(input.parse_nested_block(|input| {
-                        let location = input.current_source_location();
-                        Ok(AnimationTimingFunction::Step(input.expect_number()? as usize, {
-                            if let Ok(_r) = input.expect_comma() {
-                                let p = input.expect_ident()?;
-                                match p.as_ref() {
-                                    "jump-start" | "start" => EStepMode::JumpStart,
-                                    "jump-end" | "end" => EStepMode::JumpEnd,
-                                    "jump-none" => EStepMode::JumpNone,
-                                    "jump-both" => EStepMode::JumpEnd,
-                                    _ => return Err(TokenParseError::from_expect(location, "jump-start | start | jump-end | end | jump-none | jump-both", Token::Ident(p.clone())))?,
-                                }
-                            } else {
-                                EStepMode::JumpStart
-                            }
-                        }))
-                    })?),
+                    "linear" => Ok(input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|input| Ok(parse_linear_points(input)?))?),
+                    "steps" => Ok(input.parse_nested_block::<_, _, TokenErrorsInfo<'i>>(|input| Ok(parse_steps(input)?))?),
                     _ => return Err(TokenParseError::from_expect(location, "ease | ease-in | ease-out | ease-in-out | linear | step-start | step-end | cubic-bezier(...) | steps | steps(...)", token.clone()))?
                 }
             }
@@ -2763,8 +4251,8 @@ impl StyleParse for BaseShape {
 				Ok(BaseShape::Ellipse { rx, ry, center })
 			})?),
 			"sector" => Ok(input.parse_nested_block(|input| {
-				let rotate = parse_angle(input)?/180.0 * 3.1415926535;
-				let angle = parse_angle(input)?/180.0 * 3.1415926535;
+				let rotate = Deg(parse_angle(input)?);
+				let angle = Deg(parse_angle(input)?);
 				let radius = parse_len_or_percent(input)?;
 				let center = parse_center(input);
 				Ok(BaseShape::Sector { rotate, angle, radius, center: center })
@@ -2774,7 +4262,7 @@ impl StyleParse for BaseShape {
     }
 }
 
-pub fn parse_mult<'i, 't, const C: usize, O: Default + Copy, F: Fn(&mut Parser<'i, 't>) -> Result<O, TokenParseError<'i>> >(input: &mut Parser<'i, 't>, mut arr: [O;C], f: F) -> Result<[O;C], TokenParseError<'i>> {
+pub fn parse_mult<'i, 't, const C: usize, O: Default + Clone, F: Fn(&mut Parser<'i, 't>) -> Result<O, TokenParseError<'i>> >(input: &mut Parser<'i, 't>, mut arr: [O;C], f: F) -> Result<[O;C], TokenParseError<'i>> {
 	let mut i = 0;
 	while i < C {
 		if let Ok(r) = input.try_parse(|input| {f(input)}) {
@@ -2785,13 +4273,13 @@ pub fn parse_mult<'i, 't, const C: usize, O: Default + Copy, F: Fn(&mut Parser<'
 		i += 1;
 	}
 	if C > 1 && i <= 1 {
-		arr[1] = arr[0];
+		arr[1] = arr[0].clone();
 	}
 	if C > 2 && i <= 2 {
-		arr[2] = arr[0];
+		arr[2] = arr[0].clone();
 	}
 	if C > 3 && i <= 3 {
-		arr[3] = arr[1];
+		arr[3] = arr[1].clone();
 	}
 	Ok(arr)
 }
@@ -2828,7 +4316,7 @@ pub fn parse_len<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, TokenParseEr
 fn parse_filter1<'i, 't>(buffer: &mut VecDeque<Attribute>, input: &mut Parser<'i, 't>) -> Result<(), TokenParseError<'i>> {
     let mut hah_hsi = false;
     let mut hsi = Hsi {
-        hue_rotate: 0.0,
+        hue_rotate: Turn(0.0),
         saturate: 0.0,
         bright_ness: 0.0,
     };
@@ -2849,8 +4337,9 @@ fn parse_filter1<'i, 't>(buffer: &mut VecDeque<Attribute>, input: &mut Parser<'i
                     buffer.push_back(Attribute::Blur(ty));
                 }
                 "hue-rotate" => {
-                    let r = i.try_parse(|i| parse_angle(i))?;
-                    hsi.hue_rotate = if r > 180.0 { r - 360.0 } else { r };
+                    // 换算到-180 ~ 180度，hsi中色相以圈数保存
+                    let r = i.try_parse(parse_angle)?.rem_euclid(360.0);
+                    hsi.hue_rotate = Turn(trans_hsi_h(if r > 180.0 { r - 360.0 } else { r }));
                     hah_hsi = true;
                 }
                 "saturate" => {
@@ -2869,7 +4358,7 @@ fn parse_filter1<'i, 't>(buffer: &mut VecDeque<Attribute>, input: &mut Parser<'i
                     i.try_parse(|i| {
                         i.skip_whitespace();
                         i.parse_until_before::<_, _, TokenErrorsInfo<'i>>(Delimiter::Comma, |i| {
-                            hsi.hue_rotate = trans_hsi_h(i.expect_number()?);
+                            hsi.hue_rotate = Turn(trans_hsi_h(i.expect_number()?));
                             Ok(())
                         })?;
 						let location1 = i.current_source_location();
@@ -2923,7 +4412,7 @@ pub fn parse_color<'i, 't>(input: &mut Parser<'i, 't>) -> Result<CgColor, TokenP
     let token = input.next()?;
     match *token {
         Token::Hash(ref value) | Token::IDHash(ref value) if let Ok(r) = parse_color_hex(value.as_ref()) => Ok(r),
-        Token::Ident(ref value) if let Ok(r) = parse_color_keyword(value.as_ref()) => Ok(r),
+        Token::Ident(ref value) if let Ok(r) = parse_color_keyword(&value.to_ascii_lowercase()) => Ok(r),
         Token::Function(ref name) => {
 			let n = name.clone();
             Ok(input.parse_nested_block(|input| Ok(parse_color_function(location, n, input)?))?)
@@ -2934,66 +4423,267 @@ pub fn parse_color<'i, 't>(input: &mut Parser<'i, 't>) -> Result<CgColor, TokenP
 
 fn parse_background<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Color, TokenParseError<'i>> {
     let location = input.current_source_location();
-    let function = input.expect_function()?;
-    match function.as_ref() {
-        "linear-gradient" => Ok(Color::LinearGradient(input.parse_nested_block(|input: &mut Parser| {Ok(parse_linear(input)?)})?)),
-        _ => return Err(TokenParseError::from_expect(location, "linear-gradient(...)", Token::Ident(function.clone()))),
+    let function = input.expect_function()?.clone();
+    match parse_gradient(&function, input)? {
+        Some(r) => Ok(r),
+        None => Err(TokenParseError::from_expect(location, GRADIENT_EXPECT, Token::Function(function))),
     }
 }
 
-fn parse_linear<'i, 't>(input: &mut Parser<'i, 't>) -> Result<LinearGradientColor, TokenParseError<'i>> {
-    let direction = if let Ok(d) = input.try_parse(|i| parse_angle(i)) {
+const GRADIENT_EXPECT: &str = "linear-gradient(...) | radial-gradient(...) | conic-gradient(...) | repeating-linear-gradient(...) | repeating-radial-gradient(...) | repeating-conic-gradient(...)";
+
+/// 解析渐变函数（函数名已被读取），name不是渐变函数时返回None
+fn parse_gradient<'i, 't>(name: &str, input: &mut Parser<'i, 't>) -> Result<Option<Color>, TokenParseError<'i>> {
+    let name = name.to_ascii_lowercase();
+    let (name, repeat) = match name.strip_prefix("repeating-") {
+        Some(r) => (r, true),
+        None => (name.as_str(), false),
+    };
+    let r = match name {
+        "linear-gradient" => Color::LinearGradient(input.parse_nested_block(|i| Ok(parse_linear(i, repeat)?))?),
+        "radial-gradient" => Color::RadialGradient(input.parse_nested_block(|i| Ok(parse_radial(i, repeat)?))?),
+        "conic-gradient" => Color::ConicGradient(input.parse_nested_block(|i| Ok(parse_conic(i, repeat)?))?),
+        _ => return Ok(None),
+    };
+    Ok(Some(r))
+}
+
+/// linear-gradient([<angle> | to <side-or-corner>]?, <color-stop-list>)
+/// 缺省方向为to bottom；角的方向（如to top right）与渐变区域的宽高比有关，记录在corner中
+fn parse_linear<'i, 't>(input: &mut Parser<'i, 't>, repeat: bool) -> Result<LinearGradientColor, TokenParseError<'i>> {
+    let (angle, corner) = if let Ok(r) = input.try_parse(parse_angle) {
         input.expect_comma()?;
-        d - 90.0
+        (r, None)
+    } else if input.try_parse(|i| i.expect_ident_matching("to")).is_ok() {
+        let r = parse_side_or_corner(input)?;
+        input.expect_comma()?;
+        r
     } else {
-        0.0
+        (180.0, None)
     };
 
+    let stops = parse_color_stop_list(input, parse_len_or_percent)?;
+    // 位置都是百分比时，可直接计算出色标位置
+    let is_percent = stops.iter().all(|r| matches!(r, GradientStop::Color(_, None | Some(LengthUnit::Percent(_))) | GradientStop::Hint(LengthUnit::Percent(_))));
+    let (list, stops) = if is_percent {
+        (resolve_color_stops(&stops, 0.0), Vec::new())
+    } else {
+        (Vec::new(), stops)
+    };
     Ok(LinearGradientColor {
-        direction,
-        list: parse_stops(input)?,
+        direction: angle - 90.0,
+        list,
+        repeat,
+        stops,
+        corner,
     })
 }
 
-fn parse_stops<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Vec<ColorAndPosition>, TokenParseError<'i>> {
-    let mut list = Vec::new();
-    let mut color_stop = Vec::new();
-    let mut pre_percent = 0.0;
+/// 解析`to`后面的方向，返回css角度，指向角时同时返回角的方向（角度为正方形区域下的值）
+fn parse_side_or_corner<'i, 't>(input: &mut Parser<'i, 't>) -> Result<(f32, Option<(i8, i8)>), TokenParseError<'i>> {
+    // 水平、垂直方向：-1（left、top），0，1（right、bottom）
+    fn parse_side<'i, 't>(input: &mut Parser<'i, 't>, x: &mut i8, y: &mut i8) -> Result<(), TokenParseError<'i>> {
+        let location = input.current_source_location();
+        let ident = input.expect_ident_cloned()?;
+        match &*ident.to_ascii_lowercase() {
+            "left" if *x == 0 => *x = -1,
+            "right" if *x == 0 => *x = 1,
+            "top" if *y == 0 => *y = -1,
+            "bottom" if *y == 0 => *y = 1,
+            _ => return Err(TokenParseError::from_expect(location, "left | right | top | bottom", Token::Ident(ident))),
+        }
+        Ok(())
+    }
+
+    let (mut x, mut y) = (0, 0);
+    parse_side(input, &mut x, &mut y)?;
+    let _ = input.try_parse(|i| parse_side(i, &mut x, &mut y));
+    let corner = if x != 0 && y != 0 { Some((x, y)) } else { None };
+    let angle = match (x, y) {
+        (0, -1) => 0.0,
+        (1, -1) => 45.0,
+        (1, 0) => 90.0,
+        (1, 1) => 135.0,
+        (0, 1) => 180.0,
+        (-1, 1) => 225.0,
+        (-1, 0) => 270.0,
+        _ => 315.0,
+    };
+    Ok((angle, corner))
+}
 
+/// radial-gradient([<shape> || <size>]? [at <position>]?, <color-stop-list>)
+/// size只支持关键字（closest-side、farthest-side、closest-corner、farthest-corner）
+fn parse_radial<'i, 't>(input: &mut Parser<'i, 't>, repeat: bool) -> Result<RadialGradientColor, TokenParseError<'i>> {
+    let mut shape = None;
+    let mut size = None;
     loop {
-        if let Err(e) = parse_stop_item(&mut list, &mut color_stop, &mut pre_percent, input) {
-            log::error!("parse_stops fail: {:?}", e);
+        let r = input.try_parse::<_, _, TokenParseError>(|i| {
+            let location = i.current_source_location();
+            let ident = i.expect_ident_cloned()?;
+            match &*ident.to_ascii_lowercase() {
+                "circle" if shape.is_none() => shape = Some(RadialGradientShape::Circle),
+                "ellipse" if shape.is_none() => shape = Some(RadialGradientShape::Ellipse),
+                "closest-side" if size.is_none() => size = Some(RadialGradientSize::ClosestSide),
+                "farthest-side" if size.is_none() => size = Some(RadialGradientSize::FarthesSide),
+                "closest-corner" if size.is_none() => size = Some(RadialGradientSize::ClosestCorner),
+                "farthest-corner" if size.is_none() => size = Some(RadialGradientSize::Farthescorner),
+                _ => return Err(TokenParseError::from_expect(location, "<shape> | <size>", Token::Ident(ident))),
+            }
+            Ok(())
+        });
+        if r.is_err() {
+            break;
         }
-        match input.next() {
-            Ok(&Token::Comma) => continue,
-            _ => break,
+    }
+    let mut has_prelude = shape.is_some() || size.is_some();
+    let center = parse_gradient_center(input, &mut has_prelude)?;
+    if has_prelude {
+        input.expect_comma()?;
+    }
+
+    Ok(RadialGradientColor {
+        center,
+        shape: shape.unwrap_or(RadialGradientShape::Ellipse),
+        size: size.unwrap_or(RadialGradientSize::Farthescorner),
+        list: resolve_color_stops(&parse_color_stop_list(input, |i| Ok(LengthUnit::Percent(i.expect_percentage()?)))?, 0.0),
+        repeat,
+    })
+}
+
+/// conic-gradient([from <angle>]? [at <position>]?, <color-stop-list>)
+/// 色标位置可以是百分比或角度
+fn parse_conic<'i, 't>(input: &mut Parser<'i, 't>, repeat: bool) -> Result<ConicGradientColor, TokenParseError<'i>> {
+    let mut has_prelude = false;
+    let from = if input.try_parse(|i| i.expect_ident_matching("from")).is_ok() {
+        has_prelude = true;
+        parse_angle(input)?
+    } else {
+        0.0
+    };
+    let center = parse_gradient_center(input, &mut has_prelude)?;
+    if has_prelude {
+        input.expect_comma()?;
+    }
+
+    Ok(ConicGradientColor {
+        from,
+        center,
+        list: resolve_color_stops(
+            &parse_color_stop_list(input, |i| {
+                let location = i.current_source_location();
+                let token = i.next()?;
+                match *token {
+                    Token::Percentage { unit_value, .. } => Ok(LengthUnit::Percent(unit_value)),
+                    Token::Dimension { value, ref unit, .. } if let Some(r) = AngleUnit::from_name(unit) => Ok(LengthUnit::Percent(r.to_deg(value) / 360.0)),
+                    _ => Err(TokenParseError::from_expect(location, "<percentage> | <angle>", token.clone())),
+                }
+            })?,
+            0.0,
+        ),
+        repeat,
+    })
+}
+
+/// 解析渐变的中心（`at <position>`），没有时为中心点
+fn parse_gradient_center<'i, 't>(input: &mut Parser<'i, 't>, has_prelude: &mut bool) -> Result<Center, TokenParseError<'i>> {
+    if input.try_parse(|i| i.expect_ident_matching("at")).is_ok() {
+        *has_prelude = true;
+        parse_position(input)
+    } else {
+        Ok(Center { x: LengthUnit::Percent(0.5), y: LengthUnit::Percent(0.5) })
+    }
+}
+
+/// 解析<position>，一个或两个值，可以是长度、百分比或关键字（left、center、right、top、bottom）
+fn parse_position<'i, 't>(input: &mut Parser<'i, 't>) -> Result<Center, TokenParseError<'i>> {
+    // 返回值及是否为垂直方向的关键字（top、bottom），水平方向的关键字为Some(false)
+    fn parse_item<'i, 't>(input: &mut Parser<'i, 't>) -> Result<(LengthUnit, Option<bool>), TokenParseError<'i>> {
+        if let Ok(ident) = input.try_parse(|i| i.expect_ident_cloned()) {
+            let location = input.current_source_location();
+            return match &*ident.to_ascii_lowercase() {
+                "left" => Ok((LengthUnit::Percent(0.0), Some(false))),
+                "right" => Ok((LengthUnit::Percent(1.0), Some(false))),
+                "top" => Ok((LengthUnit::Percent(0.0), Some(true))),
+                "bottom" => Ok((LengthUnit::Percent(1.0), Some(true))),
+                "center" => Ok((LengthUnit::Percent(0.5), None)),
+                _ => Err(TokenParseError::from_expect(location, "left | center | right | top | bottom", Token::Ident(ident))),
+            };
+        }
+        Ok((parse_len_or_percent(input)?, None))
+    }
+
+    let (x, vertical) = parse_item(input)?;
+    match input.try_parse(parse_item) {
+        Ok((y, vertical1)) => {
+            if vertical == Some(true) || vertical1 == Some(false) {
+                Ok(Center { x: y, y: x })
+            } else {
+                Ok(Center { x, y })
+            }
         }
+        Err(_) if vertical == Some(true) => Ok(Center { x: LengthUnit::Percent(0.5), y: x }),
+        Err(_) => Ok(Center { x, y: LengthUnit::Percent(0.5) }),
     }
-    parser_color_stop_last(1.0, &mut list, &mut color_stop, &mut pre_percent, None)?;
-    Ok(color_stop)
 }
 
-fn parse_stop_item<'i, 't>(
-    list: &mut Vec<CgColor>,
-    color_stop: &mut Vec<ColorAndPosition>,
-    pre_percent: &mut f32,
+/// 解析色标列表：<color-stop> [, <color-hint>? , <color-stop>]#，position解析位置
+/// 位置可以写在颜色之前；颜色之后可以有两个位置（`red 10% 20%`等价于`red 10%, red 20%`）
+fn parse_color_stop_list<'i, 't, F: Fn(&mut Parser<'i, 't>) -> Result<LengthUnit, TokenParseError<'i>>>(
     input: &mut Parser<'i, 't>,
-) -> Result<(), TokenParseError<'i>> {
-    let pos = input.try_parse(|i| i.expect_percentage());
-    let color = parse_color(input)?;
+    position: F,
+) -> Result<Vec<GradientStop>, TokenParseError<'i>> {
+    let mut stops = Vec::new();
+    let mut color_count = 0;
+    loop {
+        let location = input.current_source_location();
+        // 只有位置的为过渡提示
+        let hint = input.try_parse(|i| {
+            let r = position(i)?;
+            let state = i.state();
+            let is_end = i.is_exhausted() || i.expect_comma().is_ok();
+            i.reset(&state);
+            if is_end {
+                Ok(r)
+            } else {
+                Err(TokenParseError::from_message(location, "not a color hint".to_string()))
+            }
+        });
+        if let Ok(r) = hint {
+            if !matches!(stops.last(), Some(GradientStop::Color(..))) {
+                return Err(TokenParseError::from_message(location, "color hint must follow a color stop".to_string()));
+            }
+            stops.push(GradientStop::Hint(r));
+        } else {
+            let before = input.try_parse(|i| position(i)).ok();
+            let has_before = before.is_some();
+            let color = parse_color(input)?;
+            let first = match before {
+                Some(r) => Some(r),
+                None => input.try_parse(|i| position(i)).ok(),
+            };
+            let second = if !has_before && first.is_some() { input.try_parse(|i| position(i)).ok() } else { None };
+            stops.push(GradientStop::Color(color.clone(), first));
+            if let Some(r) = second {
+                stops.push(GradientStop::Color(color, Some(r)));
+            }
+            color_count += 1;
+        }
 
-    if let Ok(v) = pos {
-        parser_color_stop_last(v, list, color_stop, pre_percent, Some(color))?;
-    } else {
-		let pos = input.try_parse(|i| i.expect_percentage());
-		if let Ok(v) = pos {
-			parser_color_stop_last(v, list, color_stop, pre_percent, Some(color))?;
-		} else {
-			list.push(color);
-		}
+        if input.is_exhausted() {
+            break;
+        }
+        input.expect_comma()?;
     }
 
-    Ok(())
+    let location = input.current_source_location();
+    if color_count < 2 {
+        return Err(TokenParseError::from_message(location, "at least two color stops are required".to_string()));
+    }
+    if matches!(stops.last(), Some(GradientStop::Hint(_))) {
+        return Err(TokenParseError::from_message(location, "color hint must be followed by a color stop".to_string()));
+    }
+    Ok(stops)
 }
 
 pub fn parse_text_shadow<'i, 't>(input: &mut Parser<'i, 't>) -> Result<SmallVec<[TextShadow; 1]>, TokenParseError<'i>> {
@@ -3055,7 +4745,7 @@ fn parse_box_shadow<'i, 't>(input: &mut Parser<'i, 't>) -> Result<BoxShadow, Tok
 }
 
 pub enum GradientImage<'a> {
-    Linear(LinearGradientColor),
+    Gradient(Color),
     Url(CowRcStr<'a>),
 }
 
@@ -3068,13 +4758,15 @@ fn parse_gradient_image<'i, 't>(input: &mut Parser<'i, 't>) -> Result<GradientIm
         Token::Function(ref name) => {
             if name.eq_ignore_ascii_case("url") {
                 Ok(input.parse_nested_block(|input| input.expect_string().map_err(Into::into).map(|s| GradientImage::Url(s.clone())))?)
-            } else if name.eq_ignore_ascii_case("linear-gradient") {
-                Ok(GradientImage::Linear(input.parse_nested_block(|input| {Ok(parse_linear(input)?)})?))
             } else {
-				return Err(TokenParseError::from_expect(location, "url(...) | <url> | linear-gradient(...)", token.clone()));
+                let name = name.clone();
+                match parse_gradient(&name, input)? {
+                    Some(r) => Ok(GradientImage::Gradient(r)),
+                    None => Err(TokenParseError::from_expect(location, "url(...) | <url> | <gradient>", Token::Function(name))),
+                }
             }
         }
-        _ => return Err(TokenParseError::from_expect(location, "url(...) | <url> | linear-gradient(...)", token.clone()))
+        _ => return Err(TokenParseError::from_expect(location, "url(...) | <url> | <gradient>", token.clone()))
     }
 }
 
@@ -3119,6 +4811,17 @@ pub enum ItemParseErrors<'i> {
 	}
 }
 
+impl<'i> TokenErrorsInfo<'i> {
+	/// 出错的token
+	pub fn token(&self) -> Option<String> {
+		match self {
+			TokenErrorsInfo::ExpectError(_, r) => Some(r.to_css_string()),
+			TokenErrorsInfo::BaseParseError(BasicParseErrorKind::UnexpectedToken(r)) => Some(r.to_css_string()),
+			_ => None,
+		}
+	}
+}
+
 impl<'i> From<BasicParseError<'i>> for ItemParseErrors<'i> {
     fn from(v: BasicParseError<'i>) -> ItemParseErrors<'i> {
 		ItemParseErrors::KeyError {
@@ -3204,15 +4907,15 @@ impl<'i> From<BasicParseError<'i>> for TokenParseError<'i> {
 //     }
 // }
 
+/// 解析角度，支持deg、rad、grad、turn单位，以及不带单位的0，返回deg（见AngleUnit）
 pub fn parse_angle<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, TokenParseError<'i>> {
     let location = input.current_source_location();
-    let t = input.next()?;
-	if let Token::Dimension { value, ref unit, .. } = *t {
-		if unit.as_ref() == "deg" {
-			return Ok(value);
-		}
-	}
-	Err(TokenParseError::from_expect(location, "deg", t.clone()))?
+    let token = input.next()?;
+    match *token {
+        Token::Dimension { value, ref unit, .. } if let Some(r) = AngleUnit::from_name(unit) => Ok(r.to_deg(value)),
+        Token::Number { value, .. } if value == 0.0 => Ok(0.0),
+        _ => Err(TokenParseError::from_expect(location, "<angle>", token.clone())),
+    }
 }
 
 #[inline]
@@ -3380,10 +5083,11 @@ pub fn parse_color_keyword(ident: &str) -> Result<CgColor, ()> {
 }
 
 pub fn parse_color_function<'i, 't>(location: SourceLocation, name: CowRcStr<'i>, input: &mut Parser<'i, 't>) -> Result<CgColor, TokenParseError<'i>> {
-    let (red, green, blue, uses_commas) = match name.as_ref() {
+    let (red, green, blue, uses_commas) = match &*name.to_ascii_lowercase() {
         "rgb" | "rgba" => parse_rgb_components_rgb(input)?,
-        // "hsl" | "hsla" => parse_rgb_components_hsl(input)?,
-        _ => return Err(TokenParseError::from_expect(location, "rgb(...) | rgba(...)", Token::Ident(name.clone()))),
+        "hsl" | "hsla" => parse_rgb_components_hsl(input)?,
+        "hwb" => parse_rgb_components_hwb(input)?,
+        _ => return Err(TokenParseError::from_expect(location, "rgb(...) | rgba(...) | hsl(...) | hsla(...) | hwb(...)", Token::Ident(name.clone()))),
     };
 
     let alpha = if !input.is_exhausted() {
@@ -3392,7 +5096,7 @@ pub fn parse_color_function<'i, 't>(location: SourceLocation, name: CowRcStr<'i>
         } else {
             input.expect_delim('/')?;
         };
-        input.expect_number()?
+        parse_alpha(input)?
     } else {
         1.0
     };
@@ -3402,21 +5106,120 @@ pub fn parse_color_function<'i, 't>(location: SourceLocation, name: CowRcStr<'i>
 }
 
 fn parse_rgb_components_rgb<'i, 't>(input: &mut Parser<'i, 't>) -> Result<(f32, f32, f32, bool), TokenParseError<'i>> {
-    // Either integers or percentages, but all the same type.
+    // 用逗号分隔时，三个分量须同为数字或同为百分比
     // https://drafts.csswg.org/css-color/#rgb-functions
-    let red = input.expect_number()? / 256.0;
+    let (red, is_percent) = parse_rgb_channel(input)?;
+    let uses_commas = input.try_parse(|i| i.expect_comma()).is_ok();
+
+    let location = input.current_source_location();
+    let (green, is_percent1) = parse_rgb_channel(input)?;
+
+    if uses_commas {
+        input.expect_comma()?;
+    }
+    let (blue, is_percent2) = parse_rgb_channel(input)?;
+
+    if uses_commas && (is_percent1 != is_percent || is_percent2 != is_percent) {
+        return Err(TokenParseError::from_message(location, "rgb() channels must be all numbers or all percentages".to_string()));
+    }
+    Ok((red, green, blue, uses_commas))
+}
+
+fn parse_rgb_components_hsl<'i, 't>(input: &mut Parser<'i, 't>) -> Result<(f32, f32, f32, bool), TokenParseError<'i>> {
+    // https://drafts.csswg.org/css-color/#the-hsl-notation
+    let hue = parse_hue(input)?;
     let uses_commas = input.try_parse(|i| i.expect_comma()).is_ok();
 
-    let green = input.expect_number()? / 256.0;
+    let saturation = parse_color_percent(input, uses_commas)?;
 
     if uses_commas {
         input.expect_comma()?;
     }
-    let blue = input.expect_number()? / 256.0;
+    let lightness = parse_color_percent(input, uses_commas)?;
 
+    let (red, green, blue) = hsl_to_rgb(hue, saturation, lightness);
     Ok((red, green, blue, uses_commas))
 }
 
+fn parse_rgb_components_hwb<'i, 't>(input: &mut Parser<'i, 't>) -> Result<(f32, f32, f32, bool), TokenParseError<'i>> {
+    // hwb()只有空格分隔的写法
+    // https://drafts.csswg.org/css-color/#the-hwb-notation
+    let hue = parse_hue(input)?;
+    let whiteness = parse_color_percent(input, false)?;
+    let blackness = parse_color_percent(input, false)?;
+
+    let (red, green, blue) = hwb_to_rgb(hue, whiteness, blackness);
+    Ok((red, green, blue, false))
+}
+
+/// 解析rgb()的分量，返回0~1的值及是否为百分比
+fn parse_rgb_channel<'i, 't>(input: &mut Parser<'i, 't>) -> Result<(f32, bool), TokenParseError<'i>> {
+    let location = input.current_source_location();
+    let token = input.next()?;
+    match *token {
+        Token::Number { value, .. } => Ok(((value / 255.0).clamp(0.0, 1.0), false)),
+        Token::Percentage { unit_value, .. } => Ok((unit_value.clamp(0.0, 1.0), true)),
+        _ => Err(TokenParseError::from_expect(location, "<number> | <percentage>", token.clone())),
+    }
+}
+
+/// 解析hsl()、hwb()中的百分比分量，返回0~1的值；不用逗号分隔时，也可以是0~100的数字
+fn parse_color_percent<'i, 't>(input: &mut Parser<'i, 't>, uses_commas: bool) -> Result<f32, TokenParseError<'i>> {
+    let location = input.current_source_location();
+    let token = input.next()?;
+    match *token {
+        Token::Percentage { unit_value, .. } => Ok(unit_value.clamp(0.0, 1.0)),
+        Token::Number { value, .. } if !uses_commas => Ok((value / 100.0).clamp(0.0, 1.0)),
+        _ => Err(TokenParseError::from_expect(location, "<percentage>", token.clone())),
+    }
+}
+
+/// 解析色相，返回角度（deg），数字表示角度
+fn parse_hue<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, TokenParseError<'i>> {
+    let location = input.current_source_location();
+    let token = input.next()?;
+    match *token {
+        Token::Number { value, .. } => Ok(value),
+        Token::Dimension { value, ref unit, .. } if let Some(r) = AngleUnit::from_name(unit) => Ok(r.to_deg(value)),
+        _ => Err(TokenParseError::from_expect(location, "<number> | <angle>", token.clone())),
+    }
+}
+
+/// 解析透明度，数字或百分比，返回0~1的值
+fn parse_alpha<'i, 't>(input: &mut Parser<'i, 't>) -> Result<f32, TokenParseError<'i>> {
+    let location = input.current_source_location();
+    let token = input.next()?;
+    match *token {
+        Token::Number { value, .. } => Ok(value.clamp(0.0, 1.0)),
+        Token::Percentage { unit_value, .. } => Ok(unit_value.clamp(0.0, 1.0)),
+        _ => Err(TokenParseError::from_expect(location, "<number> | <percentage>", token.clone())),
+    }
+}
+
+/// hsl转换为rgb，hue单位为deg，其余分量及返回值均为0~1
+/// https://drafts.csswg.org/css-color/#hsl-to-rgb
+pub fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (f32, f32, f32) {
+    let hue = hue.rem_euclid(360.0);
+    let a = saturation * lightness.min(1.0 - lightness);
+    let f = |n: f32| {
+        let k = (n + hue / 30.0) % 12.0;
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    (f(0.0), f(8.0), f(4.0))
+}
+
+/// hwb转换为rgb，hue单位为deg，其余分量及返回值均为0~1
+/// https://drafts.csswg.org/css-color/#hwb-to-rgb
+pub fn hwb_to_rgb(hue: f32, whiteness: f32, blackness: f32) -> (f32, f32, f32) {
+    if whiteness + blackness >= 1.0 {
+        let gray = whiteness / (whiteness + blackness);
+        return (gray, gray, gray);
+    }
+    let (red, green, blue) = hsl_to_rgb(hue, 1.0, 0.5);
+    let f = |v: f32| v * (1.0 - whiteness - blackness) + whiteness;
+    (f(red), f(green), f(blue))
+}
+
 #[test]
 fn test_error() {
 	env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug")).init();
@@ -3543,6 +5346,57 @@ fn test_animation() {
 	}
 }
 
+#[test]
+fn test_animation_shorthand() {
+    let animation = |s: &str| {
+        let attrs = parse_style_list_from_string(s, 0).unwrap();
+        let mut r = Animation::default();
+        for attr in attrs {
+            match attr {
+                Attribute::AnimationName(v) => r.name = v.0,
+                Attribute::AnimationDuration(v) => r.duration = v.0,
+                Attribute::AnimationTimingFunction(v) => r.timing_function = v.0,
+                Attribute::AnimationIterationCount(v) => r.iteration_count = v.0,
+                Attribute::AnimationDelay(v) => r.delay = v.0,
+                Attribute::AnimationDirection(v) => r.direction = v.0,
+                Attribute::AnimationFillMode(v) => r.fill_mode = v.0,
+                Attribute::AnimationPlayState(v) => r.play_state = v.0,
+                _ => (),
+            }
+        }
+        r
+    };
+    let names = |r: &Animation| r.name.value.to_vec();
+    let atoms = |names: &[&str]| names.iter().map(|r| Atom::from(*r)).collect::<Vec<Atom>>();
+
+    let r = animation("animation: a 1s, b 2s 500ms infinite alternate");
+    assert_eq!(names(&r), atoms(&["a", "b"]));
+    assert_eq!(&r.duration[..], &[Time(1000), Time(2000)]);
+    assert_eq!(&r.delay[..], &[Time(0), Time(500)]);
+    assert_eq!(&r.iteration_count[..], &[IterationCount(1.0), IterationCount(f32::INFINITY)]);
+    assert_eq!(&r.direction[..], &[AnimationDirection::Normal, AnimationDirection::Alternate]);
+    assert_eq!(r.timing_function.len(), 2);
+    assert_eq!(r.fill_mode.len(), 2);
+    assert_eq!(r.play_state.len(), 2);
+    // 缓动函数默认为ease
+    assert!(matches!(r.timing_function[0], AnimationTimingFunction::CubicBezier(x1, _, _, _) if x1 == 0.25));
+
+    // 关键字优先作为其它属性的值，重复出现时作为动画名
+    let r = animation("animation: ease 1s ease, none none, \"paused\" paused");
+    assert_eq!(names(&r), atoms(&["ease", "none", "paused"]));
+    assert!(matches!(r.play_state[2], AnimationPlayState::Paused));
+    // animation: none
+    let r = animation("animation: none");
+    assert_eq!(names(&r), atoms(&["none"]));
+    assert!(matches!(r.fill_mode[0], AnimationFillMode::None));
+
+    // 多个动画名，或多余的时间，声明无效
+    for s in ["animation: a b 1s", "animation: a 1s 2s 3s", "animation: a 1s ease linear"] {
+        let (attrs, report) = parse_style_list_from_string_with_report(s, 0);
+        assert!(attrs.is_empty() && !report.is_empty(), "{}", s);
+    }
+}
+
 #[test]
 fn test_mul_semicolon() {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug")).init();
@@ -3695,3 +5549,440 @@ fn text_content() {
     }
 }
 
+
+#[test]
+fn test_read_class_sheet() {
+    let s = r#"
+	.c1 {
+		width: 10px;
+		background-image: url(a.png);
+		transform: scale(0.5, 2);
+	}"#;
+
+	let class_map = parse_class_map_from_string(s, 0).unwrap();
+	let mut class_sheet = ClassSheet::default();
+	class_map.to_class_sheet(&mut class_sheet);
+
+	let list = unsafe { class_sheet.iter_class(1) }.unwrap().collect::<Result<Vec<ClassStyle>, _>>().unwrap();
+	assert_eq!(list.len(), 3);
+	match &list[0] {
+		ClassStyle::Set(Attribute::Width(r)) => assert!(matches!(r.0, LayoutDimension::Points(v) if v == 10.0)),
+		r => panic!("unexpected style: {:?}", r),
+	}
+	match &list[1] {
+		ClassStyle::Set(Attribute::BackgroundImage(r)) => assert_eq!(r.0.as_ref(), "a.png"),
+		r => panic!("unexpected style: {:?}", r),
+	}
+	match &list[2] {
+		ClassStyle::Set(Attribute::Transform(r)) => assert!(matches!(r.0[0], TransformFunc::Scale(x, y) if x == 0.5 && y == 2.0)),
+		r => panic!("unexpected style: {:?}", r),
+	}
+
+	// 重置样式
+	let mut buffer = Vec::new();
+	unsafe { ResetWidthType.write(&mut buffer) };
+	let mut offset = 0;
+	assert!(matches!(unsafe { style_from_buffer(&buffer, &mut offset) }, Ok(ClassStyle::Reset(StyleType::Width))));
+
+	// 错误的数据（结构上的错误）
+	assert!(unsafe { class_sheet.iter_class(2) }.is_err());
+	let class_meta = ClassMeta { start: 0, end: class_sheet.style_buffer.len() + 1, class_style_mark: BitArray::default() };
+	assert!(unsafe { ClassStyleReader::new(&class_sheet.style_buffer, &class_meta) }.is_err());
+	let mut reader = unsafe { ClassStyleReader::new(&class_sheet.style_buffer[..3], &ClassMeta { start: 0, end: 3, class_style_mark: BitArray::default() }) }.unwrap();
+	assert!(matches!(reader.next(), Some(Err(ClassReadError::UnexpectedEnd { .. }))));
+	assert!(reader.next().is_none());
+}
+
+#[test]
+fn test_parse_report() {
+    let s = ".c1 {
+	width: 10px;
+	unknown-attr: 10px;
+	height: 10deg;
+}
+@keyframes move {
+	from { opacity: abc; }
+}";
+    let (class_map, report) = parse_class_map_from_string_with_report(s, 0);
+    assert_eq!(class_map.classes.len(), 1);
+    assert_eq!(class_map.classes[0].count, 1);
+    assert_eq!(report.diagnostics.len(), 3);
+    assert!(report.has_errors());
+
+    let r = &report.diagnostics[0];
+    assert_eq!(r.severity, Severity::Warning);
+    assert_eq!((r.line, r.column), (3, 2));
+    assert_eq!(r.class_name.as_deref(), Some("c1"));
+    assert_eq!(r.property.as_deref(), Some("unknown-attr"));
+
+    let r = &report.diagnostics[1];
+    assert_eq!(r.severity, Severity::Error);
+    assert_eq!(r.line, 4);
+    assert_eq!(r.class_name.as_deref(), Some("c1"));
+    assert_eq!(r.property.as_deref(), Some("height"));
+    assert_eq!(r.token.as_deref(), Some("10deg"));
+
+    let r = &report.diagnostics[2];
+    assert_eq!(r.severity, Severity::Error);
+    assert_eq!(r.line, 7);
+    assert_eq!(r.key_frames.as_deref(), Some("move"));
+    assert_eq!(r.property.as_deref(), Some("opacity"));
+    assert_eq!(r.token.as_deref(), Some("abc"));
+}
+
+#[test]
+fn test_class_name() {
+    assert_eq!(class_name_hash("a"), 0xe40c292c);
+    assert_eq!(class_name_hash("foobar"), 0xbf9cf968);
+    assert_eq!(resolve_class_name("c123"), 123);
+    assert_eq!(resolve_class_name("c"), class_name_hash("c"));
+    assert_eq!(resolve_class_name("c12a"), class_name_hash("c12a"));
+
+    let s = ".btn-primary {
+	width: 10px;
+}
+.c12 {
+	height: 10px;
+	opacity: 0.5;
+}
+.a { width: 1px; }
+.c3826002220 { width: 2px; }";
+    let (class_map, report) = parse_class_map_from_string_with_report(s, 0);
+    let ids: Vec<usize> = class_map.classes.iter().map(|r| r.class_name).collect();
+    assert_eq!(ids, vec![class_name_hash("btn-primary"), 12, 0xe40c292c]);
+    let counts: Vec<usize> = class_map.classes.iter().map(|r| r.count).collect();
+    assert_eq!(counts, vec![1, 2, 1]);
+    assert_eq!(class_map.class_name(class_name_hash("btn-primary")), Some("btn-primary"));
+    assert_eq!(class_map.class_name(12), Some("c12"));
+
+    // "a"与"c3826002220"的id相同
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].severity, Severity::Error);
+    assert_eq!(report.diagnostics[0].class_name.as_deref(), Some("c3826002220"));
+}
+
+#[test]
+fn test_class_name_collision() {
+    // cNNNN的id为NNNN，构造与foo冲突的class名
+    let id = class_name_hash("foo");
+    let s = format!(
+        ".foo {{ width: 10px; }}
+.c{} {{ height: 20px; opacity: 0.5; }}
+.c{}:hover {{ opacity: 0.2; }}
+.bar {{ width: 30px; }}",
+        id, id
+    );
+    let (class_map, report) = parse_class_map_from_string_with_report(&s, 0);
+    assert_eq!(report.diagnostics.len(), 2);
+    assert!(report.errors().all(|r| r.message.contains("collision")));
+
+    // 冲突的规则被忽略，其属性不会进入attrs
+    let ids: Vec<usize> = class_map.classes.iter().map(|r| r.class_name).collect();
+    assert_eq!(ids, vec![id, class_name_hash("bar")]);
+    assert_eq!(class_map.attrs.len(), 2);
+    assert!(class_map.selectors.is_empty() && class_map.selector_attrs.is_empty());
+    assert_eq!(class_map.class_name(id), Some("foo"));
+
+    // 先定义的class保持不变
+    let mut class_sheet = ClassSheet::default();
+    assert!(class_map.to_class_sheet(&mut class_sheet).is_empty());
+    let styles = |class_name: usize| -> String {
+        let list: Vec<Attribute> = unsafe { class_sheet.iter_class(class_name) }
+            .unwrap()
+            .map(|r| match r.unwrap() {
+                ClassStyle::Set(r) => r,
+                ClassStyle::Reset(_) => unreachable!(),
+            })
+            .collect();
+        format!("{:?}", list)
+    };
+    let expect = |s: &str| -> String { format!("{:?}", parse_style_list_from_string(s, 0).unwrap().into_iter().collect::<Vec<Attribute>>()) };
+    assert_eq!(styles(id), expect("width: 10px"));
+    assert_eq!(styles(class_name_hash("bar")), expect("width: 30px"));
+}
+
+#[test]
+fn test_calc() {
+    use crate::style_css::ToCss;
+    use pi_curves::curve::frame::FrameDataValue;
+
+    let parse = |s: &str| -> Option<LengthUnit> {
+        let mut input = ParserInput::new(s);
+        let mut parser = Parser::new(&mut input);
+        let r = parse_len_or_percent(&mut parser).ok();
+        if parser.is_exhausted() { r } else { None }
+    };
+    let r = parse("calc(100% - 20px)").unwrap();
+    assert_eq!(r.get_absolute_value(200.0), 180.0);
+    assert_eq!(r.to_css_string(), "calc(100% - 20px)");
+    assert_eq!(parse("calc((10px + 50%) * 2 / 4)").unwrap().get_absolute_value(100.0), 30.0);
+    assert_eq!(parse("min(100%, 50px + 10%)").unwrap().get_absolute_value(100.0), 60.0);
+    assert_eq!(parse("max(100%, 50px)").unwrap().get_absolute_value(20.0), 50.0);
+    assert_eq!(parse("clamp(10px, 50%, 100px)").unwrap().get_absolute_value(400.0), 100.0);
+    assert_eq!(parse("clamp(10px, 50%, 100px)").unwrap().to_css_string(), "clamp(10px, 50%, 100px)");
+    assert!(matches!(parse("calc(10px * 2 + 5px)"), Some(LengthUnit::Pixel(r)) if r == 25.0));
+    assert!(matches!(parse("min(10px, 20px)"), Some(LengthUnit::Pixel(r)) if r == 10.0));
+    assert!(matches!(parse("calc(50% + 10%)"), Some(LengthUnit::Percent(r)) if (r - 0.6).abs() < 1e-6));
+    assert!(parse("calc(100% -20px)").is_none());
+    assert!(parse("calc(10px * 10px)").is_none());
+    assert!(parse("calc(10px / 0)").is_none());
+    assert!(parse("clamp(10px, 20px)").is_none());
+
+    // 不同单位之间插值
+    let (from, to) = (LengthUnit::Pixel(100.0), LengthUnit::Percent(1.0));
+    let r = from.scale(0.75).add(&to.scale(0.25));
+    assert_eq!(r.to_css_string(), "calc(25% + 75px)");
+    assert_eq!(r.get_absolute_value(200.0), 125.0);
+
+    // 布局属性中能化简为单一单位的表达式直接使用Points或Percent
+    let (class_map, report) = parse_class_map_from_string_with_report(".c1 { width: calc(50% + 10%); height: calc(2 * 10px); }", 0);
+    assert!(report.is_empty());
+    assert!(matches!(&class_map.attrs[0], Attribute::Width(r) if matches!(r.0, LayoutDimension::Percent(r) if (r - 0.6).abs() < 1e-6)));
+    assert!(matches!(&class_map.attrs[1], Attribute::Height(r) if matches!(r.0, LayoutDimension::Points(r) if r == 20.0)));
+
+    // 混合单位的值保存在各自的布局属性中，布局时根据父节点的尺寸计算
+    let s = ".c1 {\n\twidth: calc(100% - 20px);\n\tmargin-top: 0px;\n\tmargin-right: calc(50% - 10px);\n\tmargin-bottom: 0px;\n\tmargin-left: calc(50% - 10px);\n\ttop: calc(25% + 5px);\n\theight: calc(50% + 10px);\n}\n";
+    let (class_map, report) = parse_class_map_from_string_with_report(".c1 { width: calc(100% - 20px); margin: 0 calc(50% - 10px); top: calc(25% + 5px); height: calc(50% + 10px); }", 0);
+    assert!(report.is_empty());
+    assert_eq!(class_map.attrs.len(), 7);
+    let value = |i: usize| match &class_map.attrs[i] {
+        Attribute::Width(r) => r.0.clone(),
+        Attribute::Height(r) => r.0.clone(),
+        Attribute::MarginRight(r) => r.0.clone(),
+        Attribute::MarginLeft(r) => r.0.clone(),
+        Attribute::PositionTop(r) => r.0.clone(),
+        r => panic!("{:?}", r),
+    };
+    assert!(matches!(value(0), LayoutDimension::Calc(_)));
+    assert!(matches!(value(0).resolve(200.0), Dimension::Points(r) if r == 180.0));
+    assert!(matches!(value(2).resolve(200.0), Dimension::Points(r) if r == 90.0));
+    assert!(matches!(value(5).resolve(100.0), Dimension::Points(r) if r == 30.0));
+    assert!(matches!(value(6).resolve(100.0), Dimension::Points(r) if r == 60.0));
+    assert_eq!(class_map.to_css_string(), s);
+    let (_, report) = parse_class_map_from_string_with_report(".c1 { width: calc(10px * 10px); border-top: calc(100% - 20px); }", 0);
+    assert_eq!(report.diagnostics.len(), 2);
+
+    // 表达式与单一单位的值之间插值
+    let r = WidthType(value(0)).interpolate(&WidthType(LayoutDimension::Points(100.0)), 0.5);
+    assert!(matches!(r.resolve(200.0), Dimension::Points(r) if r == 140.0));
+    let (_, report) = parse_class_map_from_string_with_report(".c1 { transform: translateX(calc(50% - 10px)); border-radius: min(10px, 5%); }", 0);
+    assert!(report.is_empty());
+}
+
+#[test]
+fn test_color() {
+    use crate::style_type::ClassSheet;
+
+    let parse = |s: &str| -> Option<[f32; 4]> {
+        let mut input = ParserInput::new(s);
+        let mut parser = Parser::new(&mut input);
+        let r = parse_color(&mut parser).ok();
+        if parser.is_exhausted() { r.map(|r| [r.x, r.y, r.z, r.w]) } else { None }
+    };
+    // 与参考值（0~255）的误差不超过0.5
+    let check = |s: &str, expect: [f32; 4]| {
+        let r = parse(s).unwrap_or_else(|| panic!("parse fail: {}", s));
+        for i in 0..3 {
+            assert!((r[i] * 255.0 - expect[i]).abs() <= 0.5, "{}: {:?}", s, r);
+        }
+        assert!((r[3] - expect[3]).abs() < 1e-6, "{}: {:?}", s, r);
+    };
+    check("rgb(255, 155, 0)", [255.0, 155.0, 0.0, 1.0]);
+    check("rgba(255, 155, 0, 0.5)", [255.0, 155.0, 0.0, 0.5]);
+    check("rgb(100%, 50%, 0%)", [255.0, 127.5, 0.0, 1.0]);
+    check("rgb(255 0 128 / 25%)", [255.0, 0.0, 128.0, 0.25]);
+    check("rgb(300, -5, 0, 2)", [255.0, 0.0, 0.0, 1.0]);
+    check("hsl(120, 100%, 50%)", [0.0, 255.0, 0.0, 1.0]);
+    check("hsl(0 100% 25%)", [128.0, 0.0, 0.0, 1.0]);
+    check("hsl(210, 65%, 20%)", [18.0, 51.0, 84.0, 1.0]);
+    check("hsla(240deg, 100%, 50%, 50%)", [0.0, 0.0, 255.0, 0.5]);
+    check("hsl(0.5turn 100 50 / 0.3)", [0.0, 255.0, 255.0, 0.3]);
+    check("hsl(-120, 100%, 50%)", [0.0, 0.0, 255.0, 1.0]);
+    check("hwb(0 0% 0%)", [255.0, 0.0, 0.0, 1.0]);
+    check("hwb(120 20% 30%)", [51.0, 178.5, 51.0, 1.0]);
+    check("hwb(90 60% 60%)", [127.5, 127.5, 127.5, 1.0]);
+    check("RED", [255.0, 0.0, 0.0, 1.0]);
+    check("transparent", [0.0, 0.0, 0.0, 0.0]);
+    assert!(parse("rgb(10%, 20, 30)").is_none());
+    assert!(parse("hsl(120, 100, 50)").is_none());
+    assert!(parse("hwb(120, 20%, 30%)").is_none());
+    assert!(parse("rgb(1 2 3, 0.5)").is_none());
+
+    // currentColor取同一样式列表中之前的color
+    let (class_map, report) = parse_class_map_from_string_with_report(".c1 { color: hsl(120, 100%, 50%); border-color: currentColor; } .c2 { border-color: currentcolor; }", 0);
+    assert!(report.is_empty());
+    let mut class_sheet = ClassSheet::default();
+    class_map.to_class_sheet(&mut class_sheet);
+    let border_color = |class_name: usize| -> [f32; 4] {
+        let r = unsafe { class_sheet.iter_class(class_name) }.unwrap().find_map(|r| match r.unwrap() {
+            ClassStyle::Set(Attribute::BorderColor(r)) => Some(r.0),
+            _ => None,
+        });
+        let r = r.unwrap();
+        [r.x, r.y, r.z, r.w]
+    };
+    assert_eq!(border_color(1), [0.0, 1.0, 0.0, 1.0]);
+    assert_eq!(border_color(2), [0.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn test_gradient() {
+    use crate::style_css::ToCss;
+    use std::hash::{Hash, Hasher};
+
+    let parse = |s: &str| -> Color {
+        let mut input = ParserInput::new(s);
+        let mut parser = Parser::new(&mut input);
+        let r = parse_background(&mut parser).unwrap_or_else(|e| panic!("parse fail: {}, {:?}", s, e));
+        assert!(parser.is_exhausted());
+        r
+    };
+    let hash = |r: &Color| -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        r.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let r = parse("radial-gradient(circle closest-side at left 20%, #ff0000 0%, #0000ff 100%)");
+    match &r {
+        Color::RadialGradient(g) => {
+            assert!(matches!(g.shape, RadialGradientShape::Circle));
+            assert!(matches!(g.size, RadialGradientSize::ClosestSide));
+            assert!(matches!((&g.center.x, &g.center.y), (LengthUnit::Percent(x), LengthUnit::Percent(y)) if *x == 0.0 && *y == 0.2));
+            assert!(!g.repeat);
+        }
+        _ => panic!("{:?}", r),
+    }
+    assert_eq!(r.to_css_string(), "radial-gradient(circle closest-side at 0% 20%, #ff0000 0%, #0000ff 100%)");
+    assert!(r.is_opaque());
+
+    // 默认为ellipse farthest-corner，中心在50% 50%；关键字可交换顺序
+    let r = parse("repeating-radial-gradient(#ff0000, rgba(0, 0, 255, 0.5) 20%)");
+    assert_eq!(r.to_css_string(), "repeating-radial-gradient(ellipse farthest-corner at 50% 50%, #ff0000 0%, rgba(0, 0, 255, 0.5) 20%)");
+    assert!(!r.is_opaque());
+    assert_eq!(hash(&r), hash(&parse("repeating-radial-gradient(farthest-corner ellipse at center, #ff0000 0%, rgba(0, 0, 255, 0.5) 20%)")));
+    assert_ne!(hash(&r), hash(&parse("radial-gradient(#ff0000, rgba(0, 0, 255, 0.5) 20%)")));
+
+    let r = parse("conic-gradient(from 0.25turn at top, #ff0000, #00ff00 90deg, #0000ff)");
+    assert_eq!(r.to_css_string(), "conic-gradient(from 90deg at 50% 0%, #ff0000 0deg, #00ff00 90deg, #0000ff 360deg)");
+    assert_eq!(hash(&r), hash(&parse(&r.to_css_string())));
+    let r = parse("repeating-conic-gradient(#ff0000 0%, #0000ff 25%)");
+    assert_eq!(r.to_css_string(), "repeating-conic-gradient(from 0deg at 50% 50%, #ff0000 0deg, #0000ff 90deg)");
+
+    let r = parse("repeating-linear-gradient(45deg, #ff0000 0%, #0000ff 10%)");
+    assert_eq!(r.to_css_string(), "repeating-linear-gradient(45deg, #ff0000 0%, #0000ff 10%)");
+    assert_ne!(hash(&r), hash(&parse("linear-gradient(45deg, #ff0000 0%, #0000ff 10%)")));
+
+    // mask-image与background-image同样支持
+    let list = parse_style_list_from_string("mask-image: conic-gradient(#ff0000, #0000ff); background-image: radial-gradient(#ff0000, #0000ff)", 0).unwrap();
+    assert!(matches!(list[0], Attribute::MaskImage(MaskImageType(MaskImage::ConicGradient(_)))));
+    assert!(matches!(list[1], Attribute::BackgroundColor(BackgroundColorType(Color::RadialGradient(_)))));
+
+    for s in ["radial-gradient(circle at, #ff0000, #0000ff)", "conic-gradient(from 10px, #ff0000, #0000ff)", "radial-gradient(circle at left 10% 20%, #ff0000)"] {
+        let mut input = ParserInput::new(s);
+        assert!(parse_background(&mut Parser::new(&mut input)).is_err(), "{}", s);
+    }
+}
+
+#[test]
+fn test_linear_gradient() {
+    use crate::style_css::ToCss;
+
+    let parse = |s: &str| -> Result<LinearGradientColor, String> {
+        let mut input = ParserInput::new(s);
+        let mut parser = Parser::new(&mut input);
+        match parse_background(&mut parser) {
+            Ok(Color::LinearGradient(r)) if parser.is_exhausted() => Ok(r),
+            r => Err(format!("{:?}", r)),
+        }
+    };
+    let positions = |list: &[ColorAndPosition]| -> Vec<f32> { list.iter().map(|r| (r.position * 1000.0).round() / 1000.0).collect() };
+
+    // 缺省方向为to bottom
+    let r = parse("linear-gradient(#ff0000, #0000ff)").unwrap();
+    assert_eq!(r.direction + 90.0, 180.0);
+    assert_eq!(r.to_css_string(), "linear-gradient(180deg, #ff0000 0%, #0000ff 100%)");
+    for (s, angle) in [("to right", 90.0), ("to top", 0.0), ("to left top", 315.0), ("to bottom right", 135.0), ("0.25turn", 90.0), ("100grad", 90.0), ("0", 0.0), ("-45deg", -45.0)] {
+        let r = parse(&format!("linear-gradient({}, #ff0000, #0000ff)", s)).unwrap();
+        assert!((r.direction + 90.0 - angle).abs() < 1e-4, "{}: {}", s, r.direction);
+    }
+
+    // 色标位置的修正
+    assert_eq!(positions(&parse("linear-gradient(red, green 40% 60%, blue)").unwrap().list), vec![0.0, 0.4, 0.6, 1.0]);
+    assert_eq!(positions(&parse("linear-gradient(red 50%, blue 20%)").unwrap().list), vec![0.5, 0.5]);
+    assert_eq!(positions(&parse("linear-gradient(red, green, blue 80%, yellow, black)").unwrap().list), vec![0.0, 0.4, 0.8, 0.9, 1.0]);
+    assert_eq!(positions(&parse("linear-gradient(20deg, 10% #555, 100% #fff)").unwrap().list), vec![0.1, 1.0]);
+
+    // 过渡提示，位于中点时与线性插值相同
+    assert_eq!(parse("linear-gradient(#000000, 50%, #ffffff)").unwrap().list.len(), 2);
+    let r = parse("linear-gradient(#000000, 25%, #ffffff)").unwrap();
+    assert_eq!(positions(&r.list), vec![0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0]);
+    // 插值曲线在提示位置（25%）处取到中间色，50%处的权重为0.5^0.5
+    assert!((r.list[5].rgba.x - 0.5f32.powf(0.5)).abs() < 1e-4);
+    assert!(r.list.windows(2).all(|r| r[0].rgba.x <= r[1].rgba.x));
+
+    // 指向角时，角度与渐变区域的宽高比有关，渐变线垂直于相邻两个角的连线
+    let r = parse("linear-gradient(to top right, #ff0000, #0000ff)").unwrap();
+    assert_eq!(r.corner, Some((1, -1)));
+    assert!((r.angle(100.0, 100.0) + 90.0 - 45.0).abs() < 1e-4);
+    // 宽200高100时，对角线（左上到右下）方向为(2, 1)，渐变方向为(1, -2)，即css角度atan(0.5)
+    assert!((r.angle(200.0, 100.0) + 90.0 - 0.5f32.atan().to_degrees()).abs() < 1e-4);
+    assert!((r.line_length(200.0, 100.0) - 2.0 * 200.0 * 100.0 / 50000.0f32.sqrt()).abs() < 1e-2);
+    assert_eq!(r.to_css_string(), "linear-gradient(to right top, #ff0000 0%, #0000ff 100%)");
+    for (s, angle) in [("to bottom right", 180.0 - 0.5f32.atan().to_degrees()), ("to left bottom", 180.0 + 0.5f32.atan().to_degrees()), ("to top left", 360.0 - 0.5f32.atan().to_degrees())] {
+        let r = parse(&format!("linear-gradient({}, #ff0000, #0000ff)", s)).unwrap();
+        assert!((r.angle(200.0, 100.0) + 90.0 - angle).to_radians().cos() > 1.0 - 1e-6, "{}: {}", s, r.angle(200.0, 100.0));
+        assert_eq!(parse(&r.to_css_string()).unwrap().corner, r.corner);
+    }
+    // 指向边时，与宽高比无关
+    assert_eq!(parse("linear-gradient(to right, #ff0000, #0000ff)").unwrap().angle(200.0, 100.0), 0.0);
+
+    // 长度位置需要根据渐变线长度计算
+    let r = parse("linear-gradient(to right, #ff0000 20px, #0000ff 50%)").unwrap();
+    assert!(r.list.is_empty());
+    assert_eq!(positions(&r.resolve(200.0, 100.0)), vec![0.1, 0.5]);
+    assert_eq!(positions(&r.resolve(400.0, 100.0)), vec![0.05, 0.5]);
+    assert_eq!(r.to_css_string(), "linear-gradient(90deg, #ff0000 20px, #0000ff 50%)");
+
+    for s in [
+        "linear-gradient(#ff0000)",
+        "linear-gradient(#ff0000, 20%)",
+        "linear-gradient(20%, #ff0000, #0000ff)",
+        "linear-gradient(#ff0000, 10%, 20%, #0000ff)",
+        "linear-gradient(to middle, #ff0000, #0000ff)",
+        "linear-gradient(to left right, #ff0000, #0000ff)",
+        "linear-gradient(10px, #ff0000, #0000ff)",
+        "linear-gradient(#ff0000 10% 20% 30%, #0000ff)",
+        "linear-gradient(#ff0000, #0000ff,)",
+    ] {
+        assert!(parse(s).is_err(), "{}", s);
+    }
+}
+
+#[test]
+fn test_angle() {
+    let parse = |s: &str| -> Attribute { parse_style_list_from_string(s, 0).unwrap().pop_front().unwrap() };
+
+    match parse("transform: rotate(0.25turn) skewX(1rad) rotateX(100grad) rotateY(0) rotateZ(45DEG)") {
+        Attribute::Transform(r) => {
+            let angles: Vec<f32> = r
+                .0
+                .iter()
+                .map(|r| match r {
+                    TransformFunc::RotateX(r) | TransformFunc::RotateY(r) | TransformFunc::RotateZ(r) | TransformFunc::SkewX(r) => (r * 1000.0).round() / 1000.0,
+                    r => panic!("{:?}", r),
+                })
+                .collect();
+            assert_eq!(angles, vec![90.0, 57.296, 90.0, 0.0, 45.0]);
+        }
+        r => panic!("{:?}", r),
+    }
+    assert!(matches!(parse("rotate: -0.5turn"), Attribute::Rotate(r) if r.0 == -180.0));
+    // sector()中的角度同样以deg保存
+    assert!(matches!(parse("clip-path: sector(0.5turn 90deg 50px)"), Attribute::ClipPath(r) if matches!(r.0, BaseShape::Sector { rotate, angle, .. } if rotate == Deg(180.0) && angle == Deg(90.0))));
+    // hsi中色相以圈数保存，换算到-0.5 ~ 0.5
+    assert!(matches!(parse("filter: hue-rotate(90deg)"), Attribute::Hsi(r) if r.0.hue_rotate == Turn(0.25)));
+    assert!(matches!(parse("filter: hue-rotate(0.75turn)"), Attribute::Hsi(r) if r.0.hue_rotate == Turn(-0.25)));
+    let (list, report) = parse_style_list_from_string_with_report("transform: rotate(10px)", 0);
+    assert!(list.is_empty() && !report.is_empty());
+}
@@ -0,0 +1,288 @@
+//! 选择器
+//!
+//! 支持由class组成的选择器：
+//! * 复合选择器：`.btn.disabled`，元素需同时拥有所有class
+//! * 后代选择器：`.panel .title`
+//! * 子选择器：`.list > .item`
+//!
+//! 只有一个class的选择器（`.title`）仍记录在ClassSheet的class_map中，其他选择器记录在选择器表（ClassSheet::selectors）中
+//!
+//! 单个class还可以带有伪类（`.btn:hover`、`.btn:active:hover`），记录在ClassSheet的state_map中
+
+use std::ops::{BitOr, BitOrAssign};
+
+use smallvec::SmallVec;
+
+use crate::style_type::{ClassMeta, ClassSheet};
+
+/// 伪类状态的集合
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PseudoState(u8);
+
+impl PseudoState {
+    pub const NONE: PseudoState = PseudoState(0);
+    pub const HOVER: PseudoState = PseudoState(1);
+    pub const ACTIVE: PseudoState = PseudoState(1 << 1);
+    pub const FOCUS: PseudoState = PseudoState(1 << 2);
+    pub const DISABLED: PseudoState = PseudoState(1 << 3);
+
+    /// 所有状态及其名称，按优先级从低到高排列
+    const NAMES: [(PseudoState, &'static str); 4] = [
+        (PseudoState::HOVER, "hover"),
+        (PseudoState::ACTIVE, "active"),
+        (PseudoState::FOCUS, "focus"),
+        (PseudoState::DISABLED, "disabled"),
+    ];
+
+    /// 根据伪类名称（不含`:`）取到状态
+    pub fn from_name(name: &str) -> Option<PseudoState> {
+        PseudoState::NAMES.iter().find(|(_, r)| name.eq_ignore_ascii_case(r)).map(|(r, _)| *r)
+    }
+
+    /// 从位表示创建，忽略未定义的位
+    pub fn from_bits(bits: u8) -> PseudoState { PseudoState(bits & 0b1111) }
+
+    pub fn bits(self) -> u8 { self.0 }
+
+    pub fn is_empty(self) -> bool { self.0 == 0 }
+
+    /// 是否包含other中的所有状态
+    pub fn contains(self, other: PseudoState) -> bool { self.0 & other.0 == other.0 }
+
+    /// 状态的数量
+    pub fn count(self) -> u32 { self.0.count_ones() }
+
+    /// 依次取到所含的每个状态的名称
+    pub fn names(self) -> impl Iterator<Item = &'static str> {
+        PseudoState::NAMES.iter().filter(move |(r, _)| self.contains(*r)).map(|(_, r)| *r)
+    }
+}
+
+impl BitOr for PseudoState {
+    type Output = PseudoState;
+    fn bitor(self, rhs: PseudoState) -> PseudoState { PseudoState(self.0 | rhs.0) }
+}
+
+impl BitOrAssign for PseudoState {
+    fn bitor_assign(&mut self, rhs: PseudoState) { self.0 |= rhs.0; }
+}
+
+/// 组合符，描述祖先选择器与其后一个选择器之间的关系
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Combinator {
+    /// 后代（空白）
+    Descendant,
+    /// 子（`>`）
+    Child,
+}
+
+/// 复合选择器，如`.btn.disabled`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CompoundSelector {
+    pub classes: SmallVec<[usize; 2]>,
+}
+
+impl CompoundSelector {
+    /// 元素的class列表是否包含所有class
+    pub fn matches(&self, classes: &[usize]) -> bool { self.classes.iter().all(|r| classes.contains(r)) }
+}
+
+/// 选择器
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Selector {
+    /// 匹配元素自身的复合选择器
+    pub compound: CompoundSelector,
+    /// 祖先选择器，从近到远排列，组合符为该祖先与其后一个选择器之间的关系
+    /// 例如`.a .b > .c`，compound为`.c`，ancestors为`[(Child, .b), (Descendant, .a)]`
+    pub ancestors: Vec<(Combinator, CompoundSelector)>,
+}
+
+impl Selector {
+    /// 是否是只有一个class的选择器
+    pub fn is_single_class(&self) -> bool { self.ancestors.is_empty() && self.compound.classes.len() == 1 }
+
+    /// 优先级，即选择器中class的数量
+    pub fn specificity(&self) -> u32 {
+        (self.compound.classes.len() + self.ancestors.iter().map(|(_, r)| r.classes.len()).sum::<usize>()) as u32
+    }
+
+    /// 是否匹配
+    /// * classes：元素的class列表
+    /// * ancestors：祖先的class列表，从父节点到根节点排列
+    pub fn matches(&self, classes: &[usize], ancestors: &[&[usize]]) -> bool {
+        self.compound.matches(classes) && match_ancestors(&self.ancestors, ancestors)
+    }
+}
+
+fn match_ancestors(selectors: &[(Combinator, CompoundSelector)], ancestors: &[&[usize]]) -> bool {
+    let (combinator, compound) = match selectors.first() {
+        Some(r) => r,
+        None => return true,
+    };
+    match combinator {
+        Combinator::Child => match ancestors.first() {
+            Some(r) => compound.matches(r) && match_ancestors(&selectors[1..], &ancestors[1..]),
+            None => false,
+        },
+        Combinator::Descendant => {
+            (0..ancestors.len()).any(|i| compound.matches(ancestors[i]) && match_ancestors(&selectors[1..], &ancestors[i + 1..]))
+        }
+    }
+}
+
+/// 选择器表中的一项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SelectorMeta {
+    pub selector: Selector,
+    pub meta: ClassMeta,
+}
+
+impl ClassSheet {
+    /// 取到元素需要应用的ClassMeta，按应用顺序排列（后面的覆盖前面的）
+    /// * classes：元素的class列表
+    /// * ancestors：祖先的class列表，从父节点到根节点排列
+    ///
+    /// 先按class列表的顺序返回单个class的样式，再返回匹配的选择器样式；选择器按优先级从低到高排列，优先级相同时按定义顺序排列
+    pub fn match_classes(&self, classes: &[usize], ancestors: &[&[usize]]) -> Vec<&ClassMeta> {
+        let mut ret: Vec<&ClassMeta> = classes.iter().filter_map(|r| self.class_map.get(r)).collect();
+
+        let mut selectors: Vec<&SelectorMeta> = self.selectors.iter().filter(|r| r.selector.matches(classes, ancestors)).collect();
+        // 稳定排序，优先级相同时保持定义顺序
+        selectors.sort_by_key(|r| r.selector.specificity());
+        ret.extend(selectors.into_iter().map(|r| &r.meta));
+        ret
+    }
+
+    /// 取到class在给定状态下的有效样式，按应用顺序排列（后面的覆盖前面的）
+    /// * class_name：class id
+    /// * states：当前处于激活状态的伪类
+    ///
+    /// 先返回class本身的样式，再返回所含状态都处于激活状态的伪类样式；
+    /// 伪类样式按状态的数量从少到多排列，数量相同时按位值排列（hover < active < focus < disabled）
+    pub fn match_class_states(&self, class_name: usize, states: PseudoState) -> Vec<&ClassMeta> {
+        let mut ret: Vec<&ClassMeta> = self.class_map.get(&class_name).into_iter().collect();
+        if states.is_empty() || self.state_map.is_empty() {
+            return ret;
+        }
+
+        // 枚举states的所有非空子集
+        let states = PseudoState::from_bits(states.bits());
+        let mut subsets: Vec<PseudoState> = (1..=states.bits()).map(PseudoState).filter(|r| states.contains(*r)).collect();
+        subsets.sort_by_key(|r| (r.count(), r.bits()));
+        ret.extend(subsets.into_iter().filter_map(|r| self.state_map.get(&(class_name, r))));
+        ret
+    }
+}
+
+#[test]
+fn test_selector() {
+    use crate::style_parse::{class_name_hash, parse_class_map_from_string_with_report};
+
+    let s = ".panel .title { width: 1px; }
+.list > .item { width: 2px; }
+.btn.disabled { width: 3px; }
+.a .b > .c.d { width: 4px; }
+.title { width: 5px; }";
+    let (class_map, report) = parse_class_map_from_string_with_report(s, 0);
+    assert!(report.is_empty());
+    assert_eq!(class_map.classes.len(), 1);
+    assert_eq!(class_map.selectors.len(), 4);
+    assert_eq!(class_map.selector_attrs.len(), 4);
+
+    let (panel, title, list, item, btn, disabled) = (
+        class_name_hash("panel"),
+        class_name_hash("title"),
+        class_name_hash("list"),
+        class_name_hash("item"),
+        class_name_hash("btn"),
+        class_name_hash("disabled"),
+    );
+    let selector = &class_map.selectors[3].selector;
+    assert_eq!(selector.specificity(), 4);
+    assert_eq!(selector.ancestors[0].0, Combinator::Child);
+    assert_eq!(selector.ancestors[1].0, Combinator::Descendant);
+
+    let mut class_sheet = ClassSheet::default();
+    class_map.to_class_sheet(&mut class_sheet);
+    let metas = |classes: &[usize], ancestors: &[&[usize]]| -> Vec<usize> { class_sheet.match_classes(classes, ancestors).iter().map(|r| r.start).collect() };
+    let start = |i: usize| class_sheet.selectors[i].meta.start;
+
+    // 后代选择器，单个class的样式在前
+    assert_eq!(metas(&[title], &[&[], &[panel]]), vec![class_sheet.class_map[&title].start, start(0)]);
+    assert_eq!(metas(&[title], &[&[list]]), vec![class_sheet.class_map[&title].start]);
+    // 子选择器
+    assert_eq!(metas(&[item], &[&[list]]), vec![start(1)]);
+    assert!(metas(&[item], &[&[], &[list]]).is_empty());
+    // 复合选择器
+    assert_eq!(metas(&[disabled, btn], &[]), vec![start(2)]);
+    assert!(metas(&[btn], &[]).is_empty());
+
+    let (a, b, c, d) = (class_name_hash("a"), class_name_hash("b"), class_name_hash("c"), class_name_hash("d"));
+    assert_eq!(metas(&[c, d], &[&[b], &[], &[a]]), vec![start(3)]);
+    assert!(metas(&[c, d], &[&[], &[b], &[a]]).is_empty());
+    // 优先级高的在后
+    assert_eq!(metas(&[c, d, btn, disabled], &[&[b, panel], &[a]]), vec![start(2), start(3)]);
+
+    // 错误的选择器
+    let (class_map, report) = parse_class_map_from_string_with_report(".a > { width: 1px; } .b . c { width: 1px; } .e { width: 1px; }", 0);
+    assert!(report.has_errors());
+    assert_eq!(class_map.classes.len(), 1);
+    assert!(class_map.selectors.is_empty());
+}
+
+#[test]
+fn test_pseudo_state() {
+    use crate::style_parse::parse_class_map_from_string_with_report;
+
+    assert_eq!(PseudoState::from_name("hover"), Some(PseudoState::HOVER));
+    assert_eq!(PseudoState::from_name("visited"), None);
+    assert_eq!((PseudoState::HOVER | PseudoState::DISABLED).names().collect::<Vec<_>>(), vec!["hover", "disabled"]);
+
+    let s = ".c1 {
+	opacity: 0.1;
+}
+.c1:hover {
+	opacity: 0.2;
+}
+.c1:active {
+	opacity: 0.3;
+}
+.c1:hover:active {
+	opacity: 0.4;
+}
+.c1:disabled {
+	opacity: 0.5;
+}
+";
+    let (class_map, report) = parse_class_map_from_string_with_report(s, 0);
+    assert!(report.is_empty());
+    assert_eq!(class_map.classes.len(), 5);
+    assert_eq!(class_map.to_css_string(), s);
+
+    let mut class_sheet = ClassSheet::default();
+    class_map.to_class_sheet(&mut class_sheet);
+    assert_eq!(class_sheet.class_map.len(), 1);
+    assert_eq!(class_sheet.state_map.len(), 4);
+
+    let metas = |states: PseudoState| -> Vec<usize> { class_sheet.match_class_states(1, states).iter().map(|r| r.start).collect() };
+    let start = |state: PseudoState| class_sheet.state_map[&(1, state)].start;
+    let base = class_sheet.class_map[&1].start;
+    assert_eq!(metas(PseudoState::NONE), vec![base]);
+    assert_eq!(metas(PseudoState::HOVER | PseudoState::FOCUS), vec![base, start(PseudoState::HOVER)]);
+    assert_eq!(
+        metas(PseudoState::ACTIVE | PseudoState::HOVER | PseudoState::DISABLED),
+        vec![
+            base,
+            start(PseudoState::HOVER),
+            start(PseudoState::ACTIVE),
+            start(PseudoState::DISABLED),
+            start(PseudoState::HOVER | PseudoState::ACTIVE)
+        ]
+    );
+
+    // 伪类只能跟在单个class之后
+    let (class_map, report) = parse_class_map_from_string_with_report(".a.b:hover { width: 1px; } .a:hover .b { width: 1px; } .a:visited { width: 1px; }", 0);
+    assert_eq!(report.errors().count(), 3);
+    assert!(class_map.classes.is_empty());
+    assert!(class_map.selectors.is_empty());
+}
@@ -0,0 +1,291 @@
+//! 动画时间轴
+//!
+//! 不依赖渲染，根据Animation（各animation-*属性）和关键帧曲线（见KeyFrameList::build_curves），计算任意时刻各属性的值，
+//! 可用于在没有渲染的环境下测试动画。时间单位均为ms
+//!
+//! 计算方式与css动画一致：
+//! * 延迟期间及播放结束后，是否应用关键帧的值由animation-fill-mode决定
+//! * 迭代次数可以是小数，结束时停在最后一次迭代的对应进度
+//! * 反向播放时，在反向的进度上对关键帧取值，因此缓动函数也随之反向（ease-in反向后表现为ease-out）
+//! * 暂停期间时间轴不前进，恢复后从暂停处继续
+//! * animation-*的列表长度不足动画数量时，循环使用列表中的值
+
+use pi_atom::Atom;
+use pi_hash::XHashMap;
+use smallvec::SmallVec;
+
+use crate::style::{Animation, AnimationDirection, AnimationFillMode, AnimationPlayState, AnimationTimingFunction};
+use crate::style_animation::{group_key, AttributeKeyFrames};
+use crate::style_parse::Attribute;
+
+/// 一组同时开始播放的动画（即一个节点的animation）
+#[derive(Debug, Clone)]
+pub struct AnimationTimeline {
+    animations: Vec<TimelineItem>,
+}
+
+// 时间轴上的一个动画
+#[derive(Debug, Clone)]
+struct TimelineItem {
+    duration: f32,
+    delay: f32,
+    iteration_count: f32,
+    direction: AnimationDirection,
+    fill_mode: AnimationFillMode,
+    timing_function: AnimationTimingFunction,
+    curves: Vec<AttributeKeyFrames>,
+    // 开始播放的时刻，暂停的时长会累加到开始时刻上
+    start: f32,
+    // 暂停的时刻，None表示正在播放
+    paused: Option<f32>,
+}
+
+impl AnimationTimeline {
+    /// 在start时刻开始播放animation，curves为KeyFrameList::build_curves的结果
+    /// 找不到关键帧的动画（包括none）不产生任何值，但仍占据其在animation中的位置
+    pub fn new(animation: &Animation, curves: &XHashMap<Atom, Vec<AttributeKeyFrames>>, start: f32) -> Self {
+        let animations = animation
+            .name
+            .value
+            .iter()
+            .enumerate()
+            .map(|(i, name)| TimelineItem {
+                duration: nth(&animation.duration, i).0 as f32,
+                delay: nth(&animation.delay, i).0 as f32,
+                iteration_count: nth(&animation.iteration_count, i).0.max(0.0),
+                direction: nth(&animation.direction, i),
+                fill_mode: nth(&animation.fill_mode, i),
+                timing_function: nth(&animation.timing_function, i),
+                curves: curves.get(name).cloned().unwrap_or_default(),
+                start,
+                paused: match nth(&animation.play_state, i) {
+                    AnimationPlayState::Running => None,
+                    AnimationPlayState::Paused => Some(start),
+                },
+            })
+            .collect();
+        Self { animations }
+    }
+
+    /// 在time时刻修改第index个动画的播放状态
+    pub fn set_play_state(&mut self, index: usize, play_state: AnimationPlayState, time: f32) {
+        if let Some(r) = self.animations.get_mut(index) {
+            match (play_state, r.paused) {
+                (AnimationPlayState::Paused, None) => r.paused = Some(time),
+                (AnimationPlayState::Running, Some(paused)) => {
+                    r.start += time - paused;
+                    r.paused = None;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// 在time时刻暂停所有动画
+    pub fn pause(&mut self, time: f32) {
+        for i in 0..self.animations.len() {
+            self.set_play_state(i, AnimationPlayState::Paused, time);
+        }
+    }
+
+    /// 在time时刻恢复播放所有动画
+    pub fn resume(&mut self, time: f32) {
+        for i in 0..self.animations.len() {
+            self.set_play_state(i, AnimationPlayState::Running, time);
+        }
+    }
+
+    /// time时刻各属性的值，不受动画影响的属性不在结果中
+    /// 多个动画修改同一属性时，animation中靠后的动画生效
+    pub fn sample(&self, time: f32) -> Vec<Attribute> {
+        let mut result: Vec<Attribute> = Vec::new();
+        for item in self.animations.iter() {
+            let progress = match item.progress(time) {
+                Some(r) => r,
+                None => continue,
+            };
+            for curve in item.curves.iter() {
+                if let Some(attr) = curve.sample(progress, &item.timing_function) {
+                    match result.iter_mut().find(|r| group_key(r) == group_key(&attr)) {
+                        Some(r) => *r = attr,
+                        None => result.push(attr),
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// time时刻是否所有动画都已播放结束（暂停的动画、无限循环的动画不会结束）
+    pub fn is_finished(&self, time: f32) -> bool {
+        self.animations
+            .iter()
+            .all(|r| r.paused.is_none() && time - r.start - r.delay >= r.active_duration())
+    }
+}
+
+impl TimelineItem {
+    // 所有迭代的总时长
+    fn active_duration(&self) -> f32 {
+        if self.duration <= 0.0 {
+            0.0
+        } else {
+            self.duration * self.iteration_count
+        }
+    }
+
+    // time时刻关键帧的进度（0~1，已考虑方向），None表示此时动画不产生值
+    fn progress(&self, time: f32) -> Option<f32> {
+        let local_time = self.paused.map_or(time, |r| r.min(time)) - self.start;
+        let active_time = local_time - self.delay;
+        let active_duration = self.active_duration();
+
+        let (iteration, progress) = if active_time < 0.0 {
+            if !matches!(self.fill_mode, AnimationFillMode::Backwards | AnimationFillMode::Both) {
+                return None;
+            }
+            (0.0, 0.0)
+        } else if active_time >= active_duration {
+            if !matches!(self.fill_mode, AnimationFillMode::Forwards | AnimationFillMode::Both) {
+                return None;
+            }
+            let count = self.iteration_count;
+            if count.is_infinite() {
+                // 时长为0的无限循环
+                (0.0, 1.0)
+            } else if count > 0.0 && count.fract() == 0.0 {
+                // 结束于某次迭代的末尾，而不是下一次迭代的开始
+                (count - 1.0, 1.0)
+            } else {
+                (count.floor(), count.fract())
+            }
+        } else {
+            let overall = active_time / self.duration;
+            (overall.floor(), overall.fract())
+        };
+
+        let reverse = match self.direction {
+            AnimationDirection::Normal => false,
+            AnimationDirection::Reverse => true,
+            AnimationDirection::Alternate => iteration % 2.0 == 1.0,
+            AnimationDirection::AlternateReverse => iteration % 2.0 == 0.0,
+        };
+        Some(if reverse { 1.0 - progress } else { progress })
+    }
+}
+
+// 第i个动画的属性值，列表长度不足时循环使用
+fn nth<T: Clone + Default>(list: &SmallVec<[T; 1]>, i: usize) -> T {
+    if list.is_empty() {
+        T::default()
+    } else {
+        list[i % list.len()].clone()
+    }
+}
+
+#[test]
+fn test_timeline() {
+    use crate::style_parse::{parse_animation, parse_class_map_from_string};
+    use cssparser::{Parser, ParserInput};
+
+    let class_map = parse_class_map_from_string("@keyframes fade { 0% { opacity: 0; } 100% { opacity: 1; } }", 0).unwrap();
+    let curves = class_map.key_frames.build_curves(|_| None);
+    let timeline = |animation: &str| {
+        let mut input = ParserInput::new(animation);
+        let animation = parse_animation(&mut Parser::new(&mut input)).unwrap();
+        AnimationTimeline::new(&animation, &curves, 0.0)
+    };
+    let opacity = |timeline: &AnimationTimeline, time: f32| {
+        timeline.sample(time).into_iter().find_map(|r| match r {
+            Attribute::Opacity(r) => Some(r.0),
+            _ => None,
+        })
+    };
+    let near = |r: Option<f32>, expect: f32| matches!(r, Some(r) if (r - expect).abs() < 1e-4);
+
+    // 延迟500ms，播放1.5次，交替方向，双向填充
+    let r = timeline("fade 1s linear 500ms 1.5 alternate both");
+    assert!(near(opacity(&r, 0.0), 0.0));
+    assert!(near(opacity(&r, 750.0), 0.25));
+    assert!(near(opacity(&r, 1750.0), 0.75));
+    // 停在第二次迭代的一半
+    assert!(near(opacity(&r, 3000.0), 0.5));
+    assert!(r.is_finished(2000.0));
+
+    // 不填充时，延迟期间及结束后不产生值；整数次迭代结束于末尾
+    let r = timeline("fade 1s linear 500ms");
+    assert_eq!(opacity(&r, 0.0), None);
+    assert_eq!(opacity(&r, 1500.0), None);
+    let r = timeline("fade 1s linear 2 reverse forwards");
+    assert!(near(opacity(&r, 1250.0), 0.75));
+    assert!(near(opacity(&r, 5000.0), 0.0));
+
+    // 反向播放时缓动函数也反向
+    let r = timeline("fade 1s ease-in alternate infinite");
+    let forward = opacity(&r, 250.0).unwrap();
+    assert!(forward < 0.25);
+    assert!(near(opacity(&r, 1750.0), forward));
+    assert!(!r.is_finished(1e9));
+
+    // 暂停与恢复
+    let mut r = timeline("fade 1s linear");
+    r.pause(250.0);
+    assert!(near(opacity(&r, 600.0), 0.25));
+    r.resume(600.0);
+    assert!(near(opacity(&r, 850.0), 0.5));
+    let mut r = timeline("fade 1s linear paused");
+    assert!(near(opacity(&r, 500.0), 0.0));
+    r.set_play_state(0, AnimationPlayState::Running, 500.0);
+    assert!(near(opacity(&r, 1000.0), 0.5));
+}
+
+#[test]
+fn test_timeline_calc() {
+    use crate::style::LayoutDimension;
+    use crate::style_parse::{parse_animation, parse_class_map_from_string};
+    use cssparser::{Parser, ParserInput};
+    use pi_flex_layout::style::Dimension;
+
+    let class_map = parse_class_map_from_string(
+        "@keyframes size {
+            from { width: 100px; height: calc(50% + 10px); }
+            to { width: calc(100% - 20px); height: 10px; }
+        }
+        @keyframes wide {
+            from { width: calc(50% + 20px); }
+            to { width: calc(50% + 20px); }
+        }",
+        0,
+    )
+    .unwrap();
+    let curves = class_map.key_frames.build_curves(|_| None);
+    let timeline = |animation: &str| {
+        let mut input = ParserInput::new(animation);
+        let animation = parse_animation(&mut Parser::new(&mut input)).unwrap();
+        AnimationTimeline::new(&animation, &curves, 0.0)
+    };
+    let resolve = |r: &LayoutDimension| match r.resolve(200.0) {
+        Dimension::Points(r) => r,
+        r => panic!("{:?}", r),
+    };
+
+    // width、height的表达式分别取值，不会合并
+    let r = timeline("size 1s linear").sample(500.0);
+    assert_eq!(r.len(), 2);
+    let width = r.iter().find_map(|r| match r {
+        Attribute::Width(r) => Some(resolve(&r.0)),
+        _ => None,
+    });
+    let height = r.iter().find_map(|r| match r {
+        Attribute::Height(r) => Some(resolve(&r.0)),
+        _ => None,
+    });
+    assert_eq!(width, Some(140.0));
+    assert_eq!(height, Some(60.0));
+
+    // 多个动画修改同一属性时，靠后的动画生效，其它属性保留
+    let r = timeline("size 1s linear, wide 1s linear").sample(500.0);
+    assert_eq!(r.len(), 2);
+    assert!(r.iter().any(|r| matches!(r, Attribute::Width(r) if resolve(&r.0) == 120.0)));
+    assert!(r.iter().any(|r| matches!(r, Attribute::Height(r) if resolve(&r.0) == 60.0)));
+}
@@ -0,0 +1,834 @@
+//! transform的矩阵计算与插值
+//!
+//! 矩阵：AllTransform::matrix按css的顺序（translate、rotate、scale属性，然后是transform属性）合成节点的变换矩阵，
+//! 以transform-origin为原点，百分比根据节点的宽高换算
+//!
+//! 按css Transforms规范的方式对两个变换函数列表插值：
+//! * 两个列表的函数依次对应（类型相同，或可转换为相同的基本函数，如translateX与translate）时，逐个插值，
+//!   较短的列表用对应函数的单位变换补齐
+//! * 否则，从第一个不对应的函数开始，两个列表剩余的部分分别合成为2D矩阵，分解为平移、旋转、剩余矩阵、缩放后插值，
+//!   插值结果重新合成为matrix
+//! * 剩余部分含有3D变换（或无法合成2D矩阵）时，合成为3D矩阵，分解为透视、平移、旋转（四元数）、斜切、缩放后插值，
+//!   插值结果重新合成为matrix3d
+//!
+//! 平移可以含有百分比，合成2D矩阵时保留为LengthUnit；
+//! 百分比会被旋转、斜切到另一个轴上时（两个轴的百分比参照的尺寸不同），无法合成2D矩阵；
+//! 合成3D矩阵时，平移只能是像素单位。无法合成矩阵时不能插值
+
+use nalgebra::{Quaternion, Rotation3, Unit, UnitQuaternion, Vector2, Vector3, Vector4};
+
+use crate::style::{AllTransform, LengthUnit, Matrix3, Matrix4, Point2, TransformFunc, TransformFuncs, TransformOrigin};
+use crate::style_type::{lerp, lerp_length, AnimatableValue};
+
+/// 2D仿射变换，同css的matrix(a, b, c, d, e, f)：x' = a * x + c * y + e，y' = b * x + d * y + f
+#[derive(Debug, Clone)]
+pub struct Transform2D {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: LengthUnit,
+    pub f: LengthUnit,
+}
+
+impl Default for Transform2D {
+    fn default() -> Self { Self::linear(1.0, 0.0, 0.0, 1.0) }
+}
+
+impl Transform2D {
+    /// 不含平移的变换
+    pub fn linear(a: f32, b: f32, c: f32, d: f32) -> Self {
+        Self {
+            a,
+            b,
+            c,
+            d,
+            e: LengthUnit::Pixel(0.0),
+            f: LengthUnit::Pixel(0.0),
+        }
+    }
+
+    /// 单个变换函数的矩阵，角度单位为deg；3D变换返回None
+    pub fn from_func(func: &TransformFunc) -> Option<Self> {
+        Some(match func {
+            TransformFunc::TranslateX(x) => Self { e: x.clone(), ..Self::default() },
+            TransformFunc::TranslateY(y) => Self { f: y.clone(), ..Self::default() },
+            TransformFunc::Translate(x, y) => Self {
+                e: x.clone(),
+                f: y.clone(),
+                ..Self::default()
+            },
+            TransformFunc::ScaleX(x) => Self::linear(*x, 0.0, 0.0, 1.0),
+            TransformFunc::ScaleY(y) => Self::linear(1.0, 0.0, 0.0, *y),
+            TransformFunc::Scale(x, y) => Self::linear(*x, 0.0, 0.0, *y),
+            TransformFunc::RotateZ(r) => {
+                let (sin, cos) = r.to_radians().sin_cos();
+                Self::linear(cos, sin, -sin, cos)
+            }
+            TransformFunc::SkewX(r) => Self::linear(1.0, 0.0, r.to_radians().tan(), 1.0),
+            TransformFunc::SkewY(r) => Self::linear(1.0, r.to_radians().tan(), 0.0, 1.0),
+            TransformFunc::Skew(x, y) => Self::linear(1.0, y.to_radians().tan(), x.to_radians().tan(), 1.0),
+            TransformFunc::Matrix(a, b, c, d, e, f) => Self {
+                e: LengthUnit::Pixel(*e),
+                f: LengthUnit::Pixel(*f),
+                ..Self::linear(*a, *b, *c, *d)
+            },
+            TransformFunc::RotateX(_)
+            | TransformFunc::RotateY(_)
+            | TransformFunc::TranslateZ(_)
+            | TransformFunc::Translate3D(..)
+            | TransformFunc::ScaleZ(_)
+            | TransformFunc::Scale3D(..)
+            | TransformFunc::Rotate3D(..)
+            | TransformFunc::Perspective(_)
+            | TransformFunc::Matrix3D(_) => return None,
+        })
+    }
+
+    /// 按css的顺序合成变换函数列表（先应用列表后面的函数）
+    pub fn from_funcs(funcs: &[TransformFunc]) -> Option<Self> {
+        funcs.iter().try_fold(Self::default(), |r, func| r.multiply(&Self::from_func(func)?))
+    }
+
+    /// self * rhs，即先应用rhs，再应用self；rhs平移中的百分比会被变换到另一个轴上时返回None
+    pub fn multiply(&self, rhs: &Self) -> Option<Self> {
+        if (self.c != 0.0 && !is_pixel(&rhs.f)) || (self.b != 0.0 && !is_pixel(&rhs.e)) {
+            return None;
+        }
+        Some(Self {
+            a: self.a * rhs.a + self.c * rhs.b,
+            b: self.b * rhs.a + self.d * rhs.b,
+            c: self.a * rhs.c + self.c * rhs.d,
+            d: self.b * rhs.c + self.d * rhs.d,
+            e: combine(self.a, &rhs.e, self.c, &rhs.f, &self.e),
+            f: combine(self.b, &rhs.e, self.d, &rhs.f, &self.f),
+        })
+    }
+
+    /// 分解为平移、旋转、剩余矩阵、缩放
+    /// https://drafts.csswg.org/css-transforms-1/#decomposing-a-2d-matrix
+    pub fn decompose(&self) -> Decomposed2D {
+        let (mut row0x, mut row0y, mut row1x, mut row1y) = (self.a, self.b, self.c, self.d);
+        let mut scale = ((row0x * row0x + row0y * row0y).sqrt(), (row1x * row1x + row1y * row1y).sqrt());
+
+        // 行列式为负时，有一个轴被翻转
+        if row0x * row1y - row0y * row1x < 0.0 {
+            if row0x < row1y {
+                scale.0 = -scale.0;
+            } else {
+                scale.1 = -scale.1;
+            }
+        }
+
+        if scale.0 != 0.0 {
+            row0x /= scale.0;
+            row0y /= scale.0;
+        }
+        if scale.1 != 0.0 {
+            row1x /= scale.1;
+            row1y /= scale.1;
+        }
+
+        let angle = row0y.atan2(row0x);
+        if angle != 0.0 {
+            let (sn, cs) = (-row0y, row0x);
+            let (m11, m12, m21, m22) = (row0x, row0y, row1x, row1y);
+            row0x = cs * m11 + sn * m21;
+            row0y = cs * m12 + sn * m22;
+            row1x = -sn * m11 + cs * m21;
+            row1y = -sn * m12 + cs * m22;
+        }
+
+        Decomposed2D {
+            translate: (self.e.clone(), self.f.clone()),
+            scale,
+            angle: angle.to_degrees(),
+            m11: row0x,
+            m12: row0y,
+            m21: row1x,
+            m22: row1y,
+        }
+    }
+}
+
+/// 分解后的2D变换，合成顺序为：平移、旋转、剩余矩阵matrix(m11, m12, m21, m22, 0, 0)、缩放
+#[derive(Debug, Clone)]
+pub struct Decomposed2D {
+    pub translate: (LengthUnit, LengthUnit),
+    pub scale: (f32, f32),
+    /// 单位：deg
+    pub angle: f32,
+    pub m11: f32,
+    pub m12: f32,
+    pub m21: f32,
+    pub m22: f32,
+}
+
+impl Decomposed2D {
+    /// https://drafts.csswg.org/css-transforms-1/#interpolation-of-decomposed-2d-matrix-values
+    pub fn interpolate(&self, rhs: &Self, amount: f32) -> Self {
+        let (mut from, mut to) = (self.clone(), rhs.clone());
+        // 一个翻转x轴、另一个翻转y轴时，转换为不翻转的旋转
+        if (from.scale.0 < 0.0 && to.scale.1 < 0.0) || (from.scale.1 < 0.0 && to.scale.0 < 0.0) {
+            from.scale = (-from.scale.0, -from.scale.1);
+            from.angle += if from.angle < 0.0 { 180.0 } else { -180.0 };
+        }
+        // 沿较短的方向旋转
+        if from.angle == 0.0 {
+            from.angle = 360.0;
+        }
+        if to.angle == 0.0 {
+            to.angle = 360.0;
+        }
+        if (from.angle - to.angle).abs() > 180.0 {
+            if from.angle > to.angle {
+                from.angle -= 360.0;
+            } else {
+                to.angle -= 360.0;
+            }
+        }
+
+        Self {
+            translate: (lerp_length(&from.translate.0, &to.translate.0, amount), lerp_length(&from.translate.1, &to.translate.1, amount)),
+            scale: (lerp(from.scale.0, to.scale.0, amount), lerp(from.scale.1, to.scale.1, amount)),
+            angle: lerp(from.angle, to.angle, amount),
+            m11: lerp(from.m11, to.m11, amount),
+            m12: lerp(from.m12, to.m12, amount),
+            m21: lerp(from.m21, to.m21, amount),
+            m22: lerp(from.m22, to.m22, amount),
+        }
+    }
+
+    /// 重新合成为矩阵，是decompose的逆运算
+    /// https://drafts.csswg.org/css-transforms-1/#recomposing-to-a-2d-matrix
+    pub fn recompose(&self) -> Transform2D {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        Transform2D {
+            a: self.scale.0 * (cos * self.m11 + sin * self.m21),
+            b: self.scale.0 * (cos * self.m12 + sin * self.m22),
+            c: self.scale.1 * (cos * self.m21 - sin * self.m11),
+            d: self.scale.1 * (cos * self.m22 - sin * self.m12),
+            e: self.translate.0.clone(),
+            f: self.translate.1.clone(),
+        }
+    }
+
+    /// 转换为变换函数matrix(...)
+    /// 平移含有百分比时不能放入matrix中，单独作为translate（平移在最外层，不受矩阵影响）
+    pub fn to_funcs(&self) -> TransformFuncs {
+        let m = self.recompose();
+        match (&m.e, &m.f) {
+            (LengthUnit::Pixel(e), LengthUnit::Pixel(f)) => vec![TransformFunc::Matrix(m.a, m.b, m.c, m.d, *e, *f)],
+            _ => vec![TransformFunc::Translate(m.e.clone(), m.f.clone()), TransformFunc::Matrix(m.a, m.b, m.c, m.d, 0.0, 0.0)],
+        }
+    }
+}
+
+/// 对两个变换函数列表插值，amount为0时为from，为1时为to；无法插值时返回None
+pub fn interpolate_transform_funcs(from: &[TransformFunc], to: &[TransformFunc], amount: f32) -> Option<TransformFuncs> {
+    let len = from.len().max(to.len());
+    let mut ret = Vec::with_capacity(len);
+    for i in 0..len {
+        match interpolate_func(from.get(i), to.get(i), amount) {
+            Some(r) => ret.push(r),
+            None => break,
+        }
+    }
+    if ret.len() < len {
+        // 剩余部分合成矩阵后插值
+        let start = ret.len();
+        let (from, to) = (&from[start.min(from.len())..], &to[start.min(to.len())..]);
+        match (Transform2D::from_funcs(from), Transform2D::from_funcs(to)) {
+            (Some(from), Some(to)) => ret.extend(from.decompose().interpolate(&to.decompose(), amount).to_funcs()),
+            _ => {
+                if !is_absolute(from) || !is_absolute(to) {
+                    return None;
+                }
+                let from = Decomposed3D::decompose(&transform_funcs_matrix(from, 0.0, 0.0))?;
+                let to = Decomposed3D::decompose(&transform_funcs_matrix(to, 0.0, 0.0))?;
+                let m = from.interpolate(&to, amount).recompose();
+                let mut values = [0.0; 16];
+                values.copy_from_slice(m.as_slice());
+                ret.push(TransformFunc::Matrix3D(Box::new(values)));
+            }
+        }
+    }
+    Some(ret)
+}
+
+/// 分解后的3D变换，合成顺序为：透视、平移、旋转、斜切、缩放
+/// https://drafts.csswg.org/css-transforms-2/#decomposing-a-3d-matrix
+#[derive(Debug, Clone)]
+pub struct Decomposed3D {
+    pub perspective: Vector4<f32>,
+    pub translate: Vector3<f32>,
+    pub quaternion: UnitQuaternion<f32>,
+    /// 依次为xy、xz、yz方向的斜切
+    pub skew: Vector3<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Decomposed3D {
+    /// 分解矩阵，矩阵不可逆时返回None
+    pub fn decompose(matrix: &Matrix4) -> Option<Self> {
+        if matrix[(3, 3)] == 0.0 {
+            return None;
+        }
+        let m = matrix / matrix[(3, 3)];
+
+        // 透视：m = 透视矩阵 * p，p为去掉透视（最后一行为(0, 0, 0, 1)）的矩阵
+        let mut p = m;
+        p[(3, 0)] = 0.0;
+        p[(3, 1)] = 0.0;
+        p[(3, 2)] = 0.0;
+        let inverse = p.try_inverse()?;
+        let perspective = if m[(3, 0)] != 0.0 || m[(3, 1)] != 0.0 || m[(3, 2)] != 0.0 {
+            inverse.transpose() * Vector4::new(m[(3, 0)], m[(3, 1)], m[(3, 2)], m[(3, 3)])
+        } else {
+            Vector4::new(0.0, 0.0, 0.0, 1.0)
+        };
+
+        let translate = Vector3::new(m[(0, 3)], m[(1, 3)], m[(2, 3)]);
+
+        // 对三个轴的像（矩阵的前三列）正交化，得到缩放与斜切
+        let column = |i: usize| Vector3::new(m[(0, i)], m[(1, i)], m[(2, i)]);
+        let (mut x, mut y, mut z) = (column(0), column(1), column(2));
+        let mut scale = Vector3::zeros();
+        let mut skew = Vector3::zeros();
+        scale.x = x.norm();
+        x /= scale.x;
+        skew.x = x.dot(&y);
+        y -= x * skew.x;
+        scale.y = y.norm();
+        y /= scale.y;
+        skew.x /= scale.y;
+        skew.y = x.dot(&z);
+        z -= x * skew.y;
+        skew.z = y.dot(&z);
+        z -= y * skew.z;
+        scale.z = z.norm();
+        z /= scale.z;
+        skew.y /= scale.z;
+        skew.z /= scale.z;
+
+        // 坐标系被翻转时，三个轴都取反
+        if x.dot(&y.cross(&z)) < 0.0 {
+            scale = -scale;
+            (x, y, z) = (-x, -y, -z);
+        }
+
+        let rotation = Rotation3::from_matrix_unchecked(Matrix3::from_columns(&[x, y, z]));
+        Some(Self {
+            perspective,
+            translate,
+            quaternion: UnitQuaternion::from_rotation_matrix(&rotation),
+            skew,
+            scale,
+        })
+    }
+
+    /// 旋转按四元数球面插值，其余分量线性插值
+    /// https://drafts.csswg.org/css-transforms-2/#interpolation-of-decomposed-3d-matrix-values
+    pub fn interpolate(&self, rhs: &Self, amount: f32) -> Self {
+        let (qa, qb) = (self.quaternion.coords, rhs.quaternion.coords);
+        let product = qa.dot(&qb).clamp(-1.0, 1.0);
+        let quaternion = if product.abs() >= 1.0 {
+            self.quaternion
+        } else {
+            let theta = product.acos();
+            let w = (amount * theta).sin() / (1.0 - product * product).sqrt();
+            UnitQuaternion::new_normalize(Quaternion::from_vector(qa * ((amount * theta).cos() - product * w) + qb * w))
+        };
+        Self {
+            perspective: self.perspective.lerp(&rhs.perspective, amount),
+            translate: self.translate.lerp(&rhs.translate, amount),
+            quaternion,
+            skew: self.skew.lerp(&rhs.skew, amount),
+            scale: self.scale.lerp(&rhs.scale, amount),
+        }
+    }
+
+    /// 重新合成为矩阵，是decompose的逆运算
+    pub fn recompose(&self) -> Matrix4 {
+        let mut perspective = Matrix4::identity();
+        perspective[(3, 0)] = self.perspective.x;
+        perspective[(3, 1)] = self.perspective.y;
+        perspective[(3, 2)] = self.perspective.z;
+        perspective[(3, 3)] = self.perspective.w;
+        let mut skew = Matrix4::identity();
+        skew[(0, 1)] = self.skew.x;
+        skew[(0, 2)] = self.skew.y;
+        skew[(1, 2)] = self.skew.z;
+        perspective * Matrix4::new_translation(&self.translate) * self.quaternion.to_homogeneous() * skew * Matrix4::new_nonuniform_scaling(&self.scale)
+    }
+}
+
+// 平移都是像素单位时，合成的矩阵与节点的尺寸无关
+fn is_absolute(funcs: &[TransformFunc]) -> bool {
+    funcs.iter().all(|r| match r {
+        TransformFunc::TranslateX(x) | TransformFunc::TranslateY(x) => is_pixel(x),
+        TransformFunc::Translate(x, y) | TransformFunc::Translate3D(x, y, _) => is_pixel(x) && is_pixel(y),
+        _ => true,
+    })
+}
+
+// 两个函数对应时插值，缺少的一方取另一方的单位变换；不对应时返回None
+fn interpolate_func(from: Option<&TransformFunc>, to: Option<&TransformFunc>, amount: f32) -> Option<TransformFunc> {
+    let (from, to) = match (from, to) {
+        (Some(r1), Some(r2)) => (r1.clone(), r2.clone()),
+        (Some(r1), None) => (r1.clone(), identity_func(r1)),
+        (None, Some(r2)) => (identity_func(r2), r2.clone()),
+        (None, None) => return None,
+    };
+    Some(match (&from, &to) {
+        (TransformFunc::TranslateX(r1), TransformFunc::TranslateX(r2)) => TransformFunc::TranslateX(lerp_length(r1, r2, amount)),
+        (TransformFunc::TranslateY(r1), TransformFunc::TranslateY(r2)) => TransformFunc::TranslateY(lerp_length(r1, r2, amount)),
+        (TransformFunc::TranslateZ(r1), TransformFunc::TranslateZ(r2)) => TransformFunc::TranslateZ(lerp(*r1, *r2, amount)),
+        (TransformFunc::ScaleX(r1), TransformFunc::ScaleX(r2)) => TransformFunc::ScaleX(lerp(*r1, *r2, amount)),
+        (TransformFunc::ScaleY(r1), TransformFunc::ScaleY(r2)) => TransformFunc::ScaleY(lerp(*r1, *r2, amount)),
+        (TransformFunc::ScaleZ(r1), TransformFunc::ScaleZ(r2)) => TransformFunc::ScaleZ(lerp(*r1, *r2, amount)),
+        (TransformFunc::RotateX(r1), TransformFunc::RotateX(r2)) => TransformFunc::RotateX(lerp(*r1, *r2, amount)),
+        (TransformFunc::RotateY(r1), TransformFunc::RotateY(r2)) => TransformFunc::RotateY(lerp(*r1, *r2, amount)),
+        (TransformFunc::RotateZ(r1), TransformFunc::RotateZ(r2)) => TransformFunc::RotateZ(lerp(*r1, *r2, amount)),
+        (TransformFunc::SkewX(r1), TransformFunc::SkewX(r2)) => TransformFunc::SkewX(lerp(*r1, *r2, amount)),
+        (TransformFunc::SkewY(r1), TransformFunc::SkewY(r2)) => TransformFunc::SkewY(lerp(*r1, *r2, amount)),
+        (TransformFunc::Perspective(r1), TransformFunc::Perspective(r2)) => TransformFunc::Perspective(interpolate_perspective(*r1, *r2, amount)),
+        // 转换为translate3d、scale3d、rotate3d、skew后插值
+        (r1, r2) if let (Some((x1, y1, z1)), Some((x2, y2, z2))) = (as_translate(r1), as_translate(r2)) => {
+            let (x, y, z) = (lerp_length(&x1, &x2, amount), lerp_length(&y1, &y2, amount), lerp(z1, z2, amount));
+            if z == 0.0 {
+                TransformFunc::Translate(x, y)
+            } else {
+                TransformFunc::Translate3D(x, y, z)
+            }
+        }
+        (r1, r2) if let (Some((x1, y1, z1)), Some((x2, y2, z2))) = (as_scale(r1), as_scale(r2)) => {
+            let (x, y, z) = (lerp(x1, x2, amount), lerp(y1, y2, amount), lerp(z1, z2, amount));
+            if z == 1.0 {
+                TransformFunc::Scale(x, y)
+            } else {
+                TransformFunc::Scale3D(x, y, z)
+            }
+        }
+        (r1, r2) if let (Some((axis1, a1)), Some((axis2, a2))) = (as_rotate(r1), as_rotate(r2)) => {
+            // 旋转轴不同时，需要按矩阵插值；其中一个角度为0时，使用另一个的旋转轴
+            let axis = if a1 == 0.0 {
+                axis2
+            } else if a2 == 0.0 || (axis1 - axis2).norm() < 1e-6 {
+                axis1
+            } else {
+                return None;
+            };
+            TransformFunc::Rotate3D(axis.x, axis.y, axis.z, lerp(a1, a2, amount))
+        }
+        (r1, r2) if let (Some((x1, y1)), Some((x2, y2))) = (as_skew(r1), as_skew(r2)) => TransformFunc::Skew(lerp(x1, x2, amount), lerp(y1, y2, amount)),
+        _ => return None,
+    })
+}
+
+// 同类型的单位变换
+fn identity_func(func: &TransformFunc) -> TransformFunc {
+    match func {
+        TransformFunc::TranslateX(_) => TransformFunc::TranslateX(LengthUnit::Pixel(0.0)),
+        TransformFunc::TranslateY(_) => TransformFunc::TranslateY(LengthUnit::Pixel(0.0)),
+        TransformFunc::Translate(_, _) => TransformFunc::Translate(LengthUnit::Pixel(0.0), LengthUnit::Pixel(0.0)),
+        TransformFunc::TranslateZ(_) => TransformFunc::TranslateZ(0.0),
+        TransformFunc::Translate3D(..) => TransformFunc::Translate3D(LengthUnit::Pixel(0.0), LengthUnit::Pixel(0.0), 0.0),
+        TransformFunc::ScaleX(_) => TransformFunc::ScaleX(1.0),
+        TransformFunc::ScaleY(_) => TransformFunc::ScaleY(1.0),
+        TransformFunc::Scale(_, _) => TransformFunc::Scale(1.0, 1.0),
+        TransformFunc::ScaleZ(_) => TransformFunc::ScaleZ(1.0),
+        TransformFunc::Scale3D(..) => TransformFunc::Scale3D(1.0, 1.0, 1.0),
+        TransformFunc::RotateX(_) => TransformFunc::RotateX(0.0),
+        TransformFunc::RotateY(_) => TransformFunc::RotateY(0.0),
+        TransformFunc::RotateZ(_) => TransformFunc::RotateZ(0.0),
+        TransformFunc::Rotate3D(x, y, z, _) => TransformFunc::Rotate3D(*x, *y, *z, 0.0),
+        TransformFunc::SkewX(_) => TransformFunc::SkewX(0.0),
+        TransformFunc::SkewY(_) => TransformFunc::SkewY(0.0),
+        TransformFunc::Skew(_, _) => TransformFunc::Skew(0.0, 0.0),
+        TransformFunc::Perspective(_) => TransformFunc::Perspective(0.0),
+        TransformFunc::Matrix(..) => TransformFunc::Matrix(1.0, 0.0, 0.0, 1.0, 0.0, 0.0),
+        TransformFunc::Matrix3D(_) => TransformFunc::Matrix3D(Box::new(IDENTITY_MATRIX3D)),
+    }
+}
+
+// 单位矩阵，按列排列
+const IDENTITY_MATRIX3D: [f32; 16] = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+
+fn as_translate(func: &TransformFunc) -> Option<(LengthUnit, LengthUnit, f32)> {
+    match func {
+        TransformFunc::TranslateX(x) => Some((x.clone(), LengthUnit::Pixel(0.0), 0.0)),
+        TransformFunc::TranslateY(y) => Some((LengthUnit::Pixel(0.0), y.clone(), 0.0)),
+        TransformFunc::TranslateZ(z) => Some((LengthUnit::Pixel(0.0), LengthUnit::Pixel(0.0), *z)),
+        TransformFunc::Translate(x, y) => Some((x.clone(), y.clone(), 0.0)),
+        TransformFunc::Translate3D(x, y, z) => Some((x.clone(), y.clone(), *z)),
+        _ => None,
+    }
+}
+
+fn as_scale(func: &TransformFunc) -> Option<(f32, f32, f32)> {
+    match func {
+        TransformFunc::ScaleX(x) => Some((*x, 1.0, 1.0)),
+        TransformFunc::ScaleY(y) => Some((1.0, *y, 1.0)),
+        TransformFunc::ScaleZ(z) => Some((1.0, 1.0, *z)),
+        TransformFunc::Scale(x, y) => Some((*x, *y, 1.0)),
+        TransformFunc::Scale3D(x, y, z) => Some((*x, *y, *z)),
+        _ => None,
+    }
+}
+
+// 旋转轴（单位向量）与角度
+fn as_rotate(func: &TransformFunc) -> Option<(Vector3<f32>, f32)> {
+    match func {
+        TransformFunc::RotateX(r) => Some((Vector3::x(), *r)),
+        TransformFunc::RotateY(r) => Some((Vector3::y(), *r)),
+        TransformFunc::RotateZ(r) => Some((Vector3::z(), *r)),
+        // 旋转轴为0向量时为单位变换
+        TransformFunc::Rotate3D(x, y, z, r) => Some(match Unit::try_new(Vector3::new(*x, *y, *z), 0.0) {
+            Some(axis) => (axis.into_inner(), *r),
+            None => (Vector3::z(), 0.0),
+        }),
+        _ => None,
+    }
+}
+
+fn as_skew(func: &TransformFunc) -> Option<(f32, f32)> {
+    match func {
+        TransformFunc::SkewX(x) => Some((*x, 0.0)),
+        TransformFunc::SkewY(y) => Some((0.0, *y)),
+        TransformFunc::Skew(x, y) => Some((*x, *y)),
+        _ => None,
+    }
+}
+
+/// 对透视距离插值，按距离的倒数线性插值；小于等于0表示none（即距离无穷大）
+pub fn interpolate_perspective(from: f32, to: f32, amount: f32) -> f32 {
+    let inverse = |r: f32| if r > 0.0 { 1.0 / r } else { 0.0 };
+    match lerp(inverse(from), inverse(to), amount) {
+        r if r > 0.0 => 1.0 / r,
+        _ => 0.0,
+    }
+}
+
+fn is_pixel(value: &LengthUnit) -> bool { matches!(value, LengthUnit::Pixel(_)) }
+
+// a * x + b * y + z，系数为0的项不参与计算，避免产生无意义的calc表达式
+fn combine(a: f32, x: &LengthUnit, b: f32, y: &LengthUnit, z: &LengthUnit) -> LengthUnit {
+    let mut r = z.clone();
+    if a != 0.0 {
+        r = r.add(&x.scale(a));
+    }
+    if b != 0.0 {
+        r = r.add(&y.scale(b));
+    }
+    r
+}
+
+impl AllTransform {
+    /// 节点的3D变换矩阵，width、height为节点的宽高
+    /// 坐标系与css相同：原点为节点左上角，y轴向下
+    pub fn matrix(&self, origin: &TransformOrigin, width: f32, height: f32) -> Matrix4 {
+        let origin = origin.to_value(width, height);
+        let mut m = Matrix4::new_translation(&Vector3::new(origin.x, origin.y, 0.0));
+        if let Some([x, y]) = &self.translate {
+            m *= Matrix4::new_translation(&Vector3::new(x.get_absolute_value(width), y.get_absolute_value(height), 0.0));
+        }
+        if let Some(r) = self.rotate {
+            m *= Matrix4::from_axis_angle(&Vector3::z_axis(), r.to_radians());
+        }
+        if let Some([x, y]) = self.scale {
+            m *= Matrix4::new_nonuniform_scaling(&Vector3::new(x, y, 1.0));
+        }
+        for func in self.transform.iter() {
+            m *= transform_func_matrix(func, width, height);
+        }
+        m * Matrix4::new_translation(&Vector3::new(-origin.x, -origin.y, 0.0))
+    }
+
+    /// 节点的2D变换矩阵，rotateX、rotateY被投影到xy平面上（即丢弃z轴）
+    pub fn matrix2d(&self, origin: &TransformOrigin, width: f32, height: f32) -> Matrix3 { flatten(&self.matrix(origin, width, height)) }
+}
+
+/// 单个变换函数的矩阵，平移中的百分比根据width、height换算
+pub fn transform_func_matrix(func: &TransformFunc, width: f32, height: f32) -> Matrix4 {
+    match func {
+        TransformFunc::TranslateX(x) => Matrix4::new_translation(&Vector3::new(x.get_absolute_value(width), 0.0, 0.0)),
+        TransformFunc::TranslateY(y) => Matrix4::new_translation(&Vector3::new(0.0, y.get_absolute_value(height), 0.0)),
+        TransformFunc::Translate(x, y) => Matrix4::new_translation(&Vector3::new(x.get_absolute_value(width), y.get_absolute_value(height), 0.0)),
+        TransformFunc::ScaleX(x) => Matrix4::new_nonuniform_scaling(&Vector3::new(*x, 1.0, 1.0)),
+        TransformFunc::ScaleY(y) => Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, *y, 1.0)),
+        TransformFunc::Scale(x, y) => Matrix4::new_nonuniform_scaling(&Vector3::new(*x, *y, 1.0)),
+        TransformFunc::RotateX(r) => Matrix4::from_axis_angle(&Vector3::x_axis(), r.to_radians()),
+        TransformFunc::RotateY(r) => Matrix4::from_axis_angle(&Vector3::y_axis(), r.to_radians()),
+        TransformFunc::RotateZ(r) => Matrix4::from_axis_angle(&Vector3::z_axis(), r.to_radians()),
+        TransformFunc::SkewX(r) => skew_matrix(r.to_radians().tan(), 0.0),
+        TransformFunc::SkewY(r) => skew_matrix(0.0, r.to_radians().tan()),
+        TransformFunc::TranslateZ(z) => Matrix4::new_translation(&Vector3::new(0.0, 0.0, *z)),
+        TransformFunc::Translate3D(x, y, z) => Matrix4::new_translation(&Vector3::new(x.get_absolute_value(width), y.get_absolute_value(height), *z)),
+        TransformFunc::ScaleZ(z) => Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 1.0, *z)),
+        TransformFunc::Scale3D(x, y, z) => Matrix4::new_nonuniform_scaling(&Vector3::new(*x, *y, *z)),
+        TransformFunc::Rotate3D(x, y, z, r) => match Unit::try_new(Vector3::new(*x, *y, *z), 0.0) {
+            Some(axis) => Matrix4::from_axis_angle(&axis, r.to_radians()),
+            None => Matrix4::identity(),
+        },
+        TransformFunc::Skew(x, y) => skew_matrix(x.to_radians().tan(), y.to_radians().tan()),
+        TransformFunc::Perspective(r) => perspective_matrix(*r),
+        TransformFunc::Matrix(a, b, c, d, e, f) => Matrix4::new(
+            *a, *c, 0.0, *e,
+            *b, *d, 0.0, *f,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ),
+        TransformFunc::Matrix3D(m) => Matrix4::from_column_slice(&m[..]),
+    }
+}
+
+/// 透视矩阵，distance为观察点到z=0平面的距离，小于等于0表示none
+/// 距离小于1px时按1px计算（同css）
+pub fn perspective_matrix(distance: f32) -> Matrix4 {
+    let mut m = Matrix4::identity();
+    if distance > 0.0 {
+        m[(3, 2)] = -1.0 / distance.max(1.0);
+    }
+    m
+}
+
+/// 节点的perspective属性作用于子节点的矩阵，origin为透视原点（css默认为节点中心）
+pub fn children_perspective_matrix(distance: f32, origin: Point2) -> Matrix4 {
+    Matrix4::new_translation(&Vector3::new(origin.x, origin.y, 0.0)) * perspective_matrix(distance) * Matrix4::new_translation(&Vector3::new(-origin.x, -origin.y, 0.0))
+}
+
+/// 变换函数列表的矩阵
+pub fn transform_funcs_matrix(funcs: &[TransformFunc], width: f32, height: f32) -> Matrix4 {
+    funcs.iter().fold(Matrix4::identity(), |m, func| m * transform_func_matrix(func, width, height))
+}
+
+/// 丢弃3D矩阵的z轴，得到2D矩阵
+pub fn flatten(m: &Matrix4) -> Matrix3 {
+    Matrix3::new(
+        m[(0, 0)], m[(0, 1)], m[(0, 3)],
+        m[(1, 0)], m[(1, 1)], m[(1, 3)],
+        m[(3, 0)], m[(3, 1)], m[(3, 3)],
+    )
+}
+
+/// 对点应用2D变换矩阵
+pub fn transform_point2(m: &Matrix3, x: f32, y: f32) -> Vector2<f32> {
+    let r = m * Vector3::new(x, y, 1.0);
+    Vector2::new(r.x / r.z, r.y / r.z)
+}
+
+// x' = x + x_tan * y，y' = y + y_tan * x
+fn skew_matrix(x_tan: f32, y_tan: f32) -> Matrix4 {
+    let mut m = Matrix4::identity();
+    m[(0, 1)] = x_tan;
+    m[(1, 0)] = y_tan;
+    m
+}
+
+#[test]
+fn test_transform_matrix() {
+    let assert_point = |m: &Matrix3, from: (f32, f32), to: (f32, f32)| {
+        let r = transform_point2(m, from.0, from.1);
+        assert!((r.x - to.0).abs() < 1e-3 && (r.y - to.1).abs() < 1e-3, "{:?} {:?}", r, to);
+    };
+
+    // 先translate、rotate、scale，再transform
+    let transform = AllTransform {
+        transform: vec![TransformFunc::TranslateX(LengthUnit::Percent(0.5))],
+        translate: Some([LengthUnit::Pixel(10.0), LengthUnit::Pixel(0.0)]),
+        scale: Some([2.0, 2.0]),
+        rotate: Some(90.0),
+    };
+    let m = transform.matrix2d(&TransformOrigin::XY(LengthUnit::Pixel(0.0), LengthUnit::Pixel(0.0)), 100.0, 50.0);
+    assert_point(&m, (1.0, 0.0), (10.0, 102.0));
+
+    // 以中心为原点旋转
+    let transform = AllTransform {
+        transform: vec![TransformFunc::RotateZ(180.0)],
+        ..Default::default()
+    };
+    let m = transform.matrix2d(&TransformOrigin::Center, 100.0, 50.0);
+    assert_point(&m, (0.0, 0.0), (100.0, 50.0));
+    assert_point(&m, (50.0, 25.0), (50.0, 25.0));
+
+    // 与插值使用的矩阵一致
+    let funcs = vec![
+        TransformFunc::Translate(LengthUnit::Pixel(5.0), LengthUnit::Pixel(-3.0)),
+        TransformFunc::SkewX(20.0),
+        TransformFunc::RotateZ(30.0),
+        TransformFunc::SkewY(-10.0),
+        TransformFunc::Scale(2.0, 0.5),
+    ];
+    let (m, t) = (flatten(&transform_funcs_matrix(&funcs, 0.0, 0.0)), Transform2D::from_funcs(&funcs).unwrap());
+    let (e, f) = (t.e.get_absolute_value(0.0), t.f.get_absolute_value(0.0));
+    assert_point(&m, (0.0, 0.0), (e, f));
+    assert_point(&m, (1.0, 0.0), (t.a + e, t.b + f));
+    assert_point(&m, (0.0, 1.0), (t.c + e, t.d + f));
+
+    // rotateX投影到xy平面
+    let m = flatten(&transform_func_matrix(&TransformFunc::RotateX(60.0), 0.0, 0.0));
+    assert_point(&m, (1.0, 1.0), (1.0, 0.5));
+}
+
+#[test]
+fn test_transform_interpolation() {
+    use crate::style_parse::{parse_style_list_from_string, Attribute};
+
+    let parse = |s: &str| -> TransformFuncs {
+        match parse_style_list_from_string(&format!("transform: {}", s), 0).unwrap().pop_front() {
+            Some(Attribute::Transform(r)) => r.0,
+            r => panic!("{:?}", r),
+        }
+    };
+    let px = |r: &LengthUnit| match r {
+        LengthUnit::Pixel(r) => *r,
+        r => panic!("{:?}", r),
+    };
+    // 比较两个列表合成的矩阵
+    let assert_matrix = |funcs: &[TransformFunc], expect: &[TransformFunc]| {
+        let (r, e) = (Transform2D::from_funcs(funcs).unwrap(), Transform2D::from_funcs(expect).unwrap());
+        let (r, e) = ([r.a, r.b, r.c, r.d, px(&r.e), px(&r.f)], [e.a, e.b, e.c, e.d, px(&e.e), px(&e.f)]);
+        assert!(r.iter().zip(e.iter()).all(|(r, e)| (r - e).abs() < 1e-3), "{:?} {:?}", r, e);
+    };
+
+    // 前缀相同时，用单位变换补齐后逐个插值
+    let r = interpolate_transform_funcs(&parse("translateX(10px)"), &parse("translateX(20px) rotate(90deg)"), 0.5).unwrap();
+    assert!(matches!(&r[..], [TransformFunc::TranslateX(LengthUnit::Pixel(x)), TransformFunc::RotateZ(a)] if *x == 15.0 && *a == 45.0));
+    let r = interpolate_transform_funcs(&parse("translateX(10px)"), &parse("translateY(20px)"), 0.5).unwrap();
+    assert!(matches!(&r[..], [TransformFunc::Translate(LengthUnit::Pixel(x), LengthUnit::Pixel(y))] if *x == 5.0 && *y == 10.0));
+    let r = interpolate_transform_funcs(&[], &parse("scale(3)"), 0.5).unwrap();
+    assert!(matches!(&r[..], [TransformFunc::Scale(x, y)] if *x == 2.0 && *y == 2.0));
+
+    // 函数不对应时，分解矩阵后插值
+    let (from, to) = (parse("translateX(10px)"), parse("rotate(30deg) scale(2)"));
+    assert_matrix(&interpolate_transform_funcs(&from, &to, 0.0).unwrap(), &from);
+    assert_matrix(&interpolate_transform_funcs(&to, &from, 1.0).unwrap(), &from);
+    assert_matrix(&interpolate_transform_funcs(&from, &to, 1.0).unwrap(), &to);
+    assert_matrix(&interpolate_transform_funcs(&from, &to, 0.5).unwrap(), &parse("translate(5px, 0px) rotate(15deg) scale(1.5)"));
+    // 沿较短的方向旋转
+    assert_matrix(
+        &interpolate_transform_funcs(&parse("rotate(170deg)"), &parse("skewX(0deg) rotate(-170deg)"), 0.5).unwrap(),
+        &parse("rotate(180deg)"),
+    );
+    let (from, to) = (parse("skewX(20deg) translate(10px, 20px)"), parse("scale(2, 0.5)"));
+    assert_matrix(&interpolate_transform_funcs(&from, &to, 0.0).unwrap(), &from);
+    assert_matrix(&interpolate_transform_funcs(&from, &to, 1.0).unwrap(), &to);
+    // 旋转与斜切组合时，剩余矩阵的m12不为0，重新合成后仍与原矩阵相同
+    let (from, to) = (parse("rotate(30deg) skewX(20deg)"), parse("skewY(-15deg) rotate(-40deg) scale(1.5, -0.8)"));
+    for (funcs, amount) in [(&from, 0.0), (&to, 1.0)] {
+        let r = interpolate_transform_funcs(&from, &to, amount).unwrap();
+        assert!(matches!(&r[..], [TransformFunc::Matrix(..)]), "{:?}", r);
+        assert_matrix(&r, funcs);
+    }
+    let m = Transform2D::from_funcs(&parse("skewX(30deg) rotate(45deg)")).unwrap();
+    assert_matrix(&m.decompose().to_funcs(), &parse("skewX(30deg) rotate(45deg)"));
+    assert!(m.decompose().m12.abs() > 1e-3);
+
+    // 百分比保留在原来的轴上
+    let r = interpolate_transform_funcs(&parse("translate(50%, 10px)"), &parse("rotate(90deg)"), 0.5).unwrap();
+    assert!(matches!(&r[0], TransformFunc::Translate(LengthUnit::Percent(x), LengthUnit::Pixel(y)) if *x == 0.25 && *y == 5.0));
+    // 百分比会被旋转到另一个轴上，无法插值
+    assert!(interpolate_transform_funcs(&parse("rotate(90deg) translateX(50%)"), &parse("scale(2)"), 0.5).is_none());
+}
+
+#[test]
+fn test_transform_3d() {
+    use crate::style::TransformStyle;
+    use crate::style_parse::{parse_class_map_from_string, parse_style_list_from_string, Attribute};
+
+    let parse = |s: &str| -> TransformFuncs {
+        match parse_style_list_from_string(&format!("transform: {}", s), 0).unwrap().pop_front() {
+            Some(Attribute::Transform(r)) => r.0,
+            r => panic!("{:?}", r),
+        }
+    };
+
+    let s = ".c1 {
+\ttransform: translate3d(10px, 50%, 5px) translateZ(2px) scale3d(1, 2, 3) scaleZ(2) rotate3d(0, 1, 0, 90deg) skew(10deg, 20deg) perspective(100px) perspective(none) matrix(1, 0, 0, 1, 10, 20) matrix3d(1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1);
+\tperspective: 500px;
+\ttransform-style: preserve-3d;
+}
+";
+    let class_map = parse_class_map_from_string(s, 0).unwrap();
+    assert_eq!(class_map.to_css_string(), s);
+    assert!(matches!(class_map.attrs.get(1), Some(Attribute::Perspective(r)) if r.0 == 500.0));
+    assert!(matches!(class_map.attrs.get(2), Some(Attribute::TransformStyle(r)) if matches!(r.0, TransformStyle::Preserve3D)));
+    assert!(matches!(&parse("translate(10px)")[0], TransformFunc::Translate(LengthUnit::Pixel(x), LengthUnit::Pixel(y)) if *x == 10.0 && *y == 0.0));
+
+    // 透视：z = 50px的点，在距离100px处观察，放大为2倍
+    let m = transform_funcs_matrix(&parse("perspective(100px) translateZ(50px)"), 0.0, 0.0);
+    let r = m * Vector4::new(10.0, 0.0, 0.0, 1.0);
+    assert!((r.x / r.w - 20.0).abs() < 1e-4);
+    assert!((transform_point2(&flatten(&m), 10.0, 0.0).x - 20.0).abs() < 1e-4);
+
+    // matrix与对应的变换函数相同
+    let m1 = transform_funcs_matrix(&parse("matrix(1, 2, 3, 4, 5, 6)"), 0.0, 0.0);
+    let m2 = transform_funcs_matrix(&parse("matrix3d(1, 2, 0, 0, 3, 4, 0, 0, 0, 0, 1, 0, 5, 6, 0, 1)"), 0.0, 0.0);
+    assert!((m1 - m2).norm() < 1e-6);
+    let m1 = transform_funcs_matrix(&parse("rotate3d(0, 2, 0, 30deg)"), 0.0, 0.0);
+    let m2 = transform_funcs_matrix(&parse("rotateY(30deg)"), 0.0, 0.0);
+    assert!((m1 - m2).norm() < 1e-6);
+    let (tan_x, tan_y) = (10f32.to_radians().tan(), 20f32.to_radians().tan());
+    let m1 = transform_funcs_matrix(&parse("skew(10deg, 20deg)"), 0.0, 0.0);
+    let m2 = transform_funcs_matrix(&[TransformFunc::Matrix(1.0, tan_y, tan_x, 1.0, 0.0, 0.0)], 0.0, 0.0);
+    assert!((m1 - m2).norm() < 1e-6);
+
+    // 3D函数逐个插值
+    let r = interpolate_transform_funcs(&parse("rotateY(0deg)"), &parse("rotateY(180deg)"), 0.5).unwrap();
+    assert!(matches!(&r[..], [TransformFunc::RotateY(a)] if *a == 90.0));
+    let r = interpolate_transform_funcs(&parse("rotate3d(0, 2, 0, 40deg)"), &parse("rotateY(80deg)"), 0.5).unwrap();
+    assert!(matches!(&r[..], [TransformFunc::Rotate3D(x, y, z, a)] if *x == 0.0 && *y == 1.0 && *z == 0.0 && *a == 60.0));
+    let r = interpolate_transform_funcs(&parse("translateZ(10px)"), &parse("translateX(10px)"), 0.5).unwrap();
+    assert!(matches!(&r[..], [TransformFunc::Translate3D(LengthUnit::Pixel(x), LengthUnit::Pixel(y), z)] if *x == 5.0 && *y == 0.0 && *z == 5.0));
+    let r = interpolate_transform_funcs(&parse("skewX(10deg)"), &parse("skewY(20deg)"), 0.5).unwrap();
+    assert!(matches!(&r[..], [TransformFunc::Skew(x, y)] if *x == 5.0 && *y == 10.0));
+    let r = interpolate_transform_funcs(&parse("perspective(100px)"), &parse("perspective(none)"), 0.5).unwrap();
+    assert!(matches!(&r[..], [TransformFunc::Perspective(d)] if (*d - 200.0).abs() < 1e-3));
+    // matrix按分解后的结果插值
+    let r = interpolate_transform_funcs(&parse("matrix(1, 0, 0, 1, 10, 0)"), &parse("matrix(2, 0, 0, 2, 0, 0)"), 0.5).unwrap();
+    let m = Transform2D::from_funcs(&r).unwrap();
+    assert!((m.a - 1.5).abs() < 1e-5 && (m.d - 1.5).abs() < 1e-5 && (m.e.get_absolute_value(0.0) - 5.0).abs() < 1e-5);
+
+    // 不对应的部分含有3D变换时，分解3D矩阵后插值，比较时按齐次坐标归一化
+    let matrix = |funcs: &[TransformFunc]| {
+        let m = transform_funcs_matrix(funcs, 0.0, 0.0);
+        m / m[(3, 3)]
+    };
+    let assert_matrix3d = |funcs: &[TransformFunc], expect: &[TransformFunc]| {
+        let (r, e) = (matrix(funcs), matrix(expect));
+        assert!((r - e).norm() < 1e-3, "{} {}", r, e);
+    };
+    for (from, to) in [
+        ("rotateX(30deg)", "rotateY(30deg)"),
+        ("translateZ(10px) rotateY(90deg)", "scale3d(2, 2, 2)"),
+        ("perspective(100px) translateZ(50px)", "translateZ(0px)"),
+        ("rotate(30deg) skewX(20deg) rotateX(45deg)", "matrix3d(1, 0, 0, 0, 0, 2, 0, 0, 0, 0, -1, 0, 10, 20, 30, 1)"),
+    ] {
+        let (from, to) = (parse(from), parse(to));
+        for (funcs, amount) in [(&from, 0.0), (&to, 1.0)] {
+            let r = interpolate_transform_funcs(&from, &to, amount).unwrap();
+            assert!(matches!(&r[..], [TransformFunc::Matrix3D(_)]), "{:?}", r);
+            assert_matrix3d(&r, funcs);
+        }
+    }
+    // 旋转轴不同时，按四元数球面插值，中间为绕两轴中间方向的旋转
+    let r = interpolate_transform_funcs(&parse("rotateX(30deg)"), &parse("rotateY(30deg)"), 0.5).unwrap();
+    let (sin, cos) = 15f32.to_radians().sin_cos();
+    let angle = 2.0 * (cos / (0.5 * sin * sin + cos * cos).sqrt()).acos().to_degrees();
+    assert_matrix3d(&r, &[TransformFunc::Rotate3D(1.0, 1.0, 0.0, angle)]);
+    // matrix3d之间
+    let r = interpolate_transform_funcs(
+        &parse("matrix3d(1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1)"),
+        &parse("matrix3d(2, 0, 0, 0, 0, 2, 0, 0, 0, 0, 2, 0, 10, 0, 0, 1)"),
+        0.5,
+    )
+    .unwrap();
+    assert_matrix3d(&r, &parse("translateX(5px) scale3d(1.5, 1.5, 1.5)"));
+    // 前缀对应的部分仍逐个插值
+    let r = interpolate_transform_funcs(&parse("translateX(10px) rotateX(30deg)"), &parse("translateX(20px) rotateY(30deg)"), 1.0).unwrap();
+    assert!(matches!(&r[..], [TransformFunc::TranslateX(LengthUnit::Pixel(x)), TransformFunc::Matrix3D(_)] if *x == 20.0));
+    // 含有百分比平移时无法合成3D矩阵，不能插值；不可逆的矩阵同样不能插值
+    assert!(interpolate_transform_funcs(&parse("rotateX(30deg) translateX(50%)"), &parse("scale(2)"), 0.5).is_none());
+    assert!(interpolate_transform_funcs(&parse("rotateX(30deg)"), &parse("scale3d(1, 1, 0)"), 0.5).is_none());
+}
@@ -1,6 +1,8 @@
 //! * 定义样式类型
 //! * 为所有的样式类型实现Attr这个tarit
 //! * 为所有的样式类型实现Add和Scale trait，用于动画插值
+//! * 颜色、渐变等不能线性组合的值，直接实现FrameDataValue（见InterpolateValue）
+//! * 所有的样式类型实现InterpolateIn，可按指定的颜色空间插值（见KeyFrames::color_space）
 
 use bitvec::array::BitArray;
 use ordered_float::NotNan;
@@ -16,10 +18,16 @@ use smallvec::SmallVec;
 
 use crate::style::{
     Aabb2, AnimationDirection, AnimationFillMode, AnimationPlayState, AnimationTimingFunction, BlendMode, BorderImageSlice, BorderRadius, BoxShadow,
-    CgColor, Color, Enable, FitType, FontSize, FontStyle, Hsi, ImageRepeat, IterationCount, LengthUnit, LineHeight, MaskImage, NotNanRect, Point2,
-    Stroke, StyleType, TextAlign, TextContent, TextShadow, Time, TransformFunc, TransformFuncs, TransformOrigin, VerticalAlign, WhiteSpace, AnimationName, BaseShape, Center, AsImage,
-	TextOverflow, OuterGlow,
+    CgColor, Color, Deg, Enable, FitType, FontSize, FontStyle, Hsi, ImageRepeat, IterationCount, LayoutDimension, LengthUnit, LineHeight, MaskImage, NotNanRect, Point2,
+    Stroke, StyleType, TextAlign, TextContent, TextShadow, Time, TransformFunc, TransformFuncs, TransformOrigin, TransformStyle, Turn, VerticalAlign, WhiteSpace, AnimationName, BaseShape, Center, AsImage,
+	TextOverflow, OuterGlow, ColorAndPosition, ConicGradientColor, LinearGradientColor, RadialGradientColor,
 };
+use crate::style_color::{sample_color_stops, ColorSpace};
+use crate::style_length::LengthContext;
+use crate::style_media::MediaSheet;
+use crate::style_selector::{PseudoState, SelectorMeta};
+use crate::style_transform::{interpolate_perspective, interpolate_transform_funcs};
+use crate::style_var::Theme;
 use pi_curves::curve::frame::{FrameValueScale, FrameDataValue, KeyFrameCurveValue};
 use std::{mem::size_of, ops::Add};
 pub use pi_flex_layout::style::OverflowWrap;
@@ -39,6 +47,14 @@ pub trait Attr: 'static + Sync + Send {
         Self: Sized;
     /// 序列化自身到buffer中
     unsafe fn write(&self, buffer: &mut Vec<u8>);
+    /// 从buffer中读取样式属性（buffer为write写入的值，不含样式索引）
+    /// 读取时克隆一份，buffer中原有的值仍由buffer持有
+    ///
+    /// # Safety
+    /// buffer必须是当前进程中由write写入、且仍然存活的值，否则克隆其中的堆指针是未定义行为
+    unsafe fn read(buffer: &[u8]) -> Self
+    where
+        Self: Sized;
 }
 
 // use pi_print_any::{println_any, out_any};
@@ -48,6 +64,14 @@ pub trait Attr: 'static + Sync + Send {
 pub struct ClassSheet {
     pub style_buffer: Vec<u8>,                 // 所有class样式的buffer集合
     pub class_map: XHashMap<usize, ClassMeta>, // 每个class的元信息描述
+    #[serde(default)]
+    pub selectors: Vec<SelectorMeta>,          // 选择器表（不只含有单个class的选择器），按定义顺序排列
+    #[serde(default)]
+    pub state_map: XHashMap<(usize, PseudoState), ClassMeta>, // 带有伪类的class的元信息描述
+    #[serde(default)]
+    pub media: Vec<MediaSheet>,                // @media中的样式，按定义顺序排列
+    #[serde(skip)]
+    pub(crate) var_context: Option<(Theme, LengthContext)>, // 最近一次计算var()及相对长度所用的主题和环境，用于切换主题时只重新计算受影响的class
 }
 
 impl ClassSheet {
@@ -60,6 +84,34 @@ impl ClassSheet {
             meta.end += old_len;
             self.class_map.insert(i, meta);
         }
+        for mut selector in class_sheet.selectors.into_iter() {
+            selector.meta.start += old_len;
+            selector.meta.end += old_len;
+            self.selectors.push(selector);
+        }
+        for (i, mut meta) in class_sheet.state_map.into_iter() {
+            meta.start += old_len;
+            meta.end += old_len;
+            self.state_map.insert(i, meta);
+        }
+        self.media.extend(class_sheet.media);
+    }
+
+    /// 整理style_buffer，去掉不再被任何ClassMeta引用的空间（如切换主题后被替换的旧样式）
+    pub fn compact(&mut self) {
+        let mut buffer = Vec::with_capacity(self.style_buffer.len());
+        let metas = self
+            .class_map
+            .values_mut()
+            .chain(self.state_map.values_mut())
+            .chain(self.selectors.iter_mut().map(|r| &mut r.meta));
+        for meta in metas {
+            let start = buffer.len();
+            buffer.extend_from_slice(&self.style_buffer[meta.start..meta.end]);
+            meta.start = start;
+            meta.end = buffer.len();
+        }
+        self.style_buffer = buffer;
     }
 
     pub fn len(&self) -> usize {
@@ -151,6 +203,17 @@ macro_rules! write_reset {
     };
 }
 
+macro_rules! read_buffer {
+    ($value_ty: ty) => {
+        unsafe fn read(buffer: &[u8]) -> Self {
+            debug_assert!(buffer.len() >= <Self as Attr>::size());
+            // buffer中的值是按位拷贝进去的（原值已被forget），不能获取其所有权，只能克隆
+            let value = std::mem::ManuallyDrop::new(std::ptr::read_unaligned(buffer.as_ptr() as *const $value_ty));
+            Self((*value).clone())
+        }
+    };
+}
+
 macro_rules! impl_style {
     ($struct_name: ident) => {
         #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -161,6 +224,7 @@ macro_rules! impl_style {
             get_type!(StyleType::PaddingBottom);
             fn size() -> usize { 0 }
             write_buffer!();
+            unsafe fn read(_buffer: &[u8]) -> Self { Self }
         }
     };
     ($struct_name: ident, $ty: ident) => {
@@ -174,6 +238,7 @@ macro_rules! impl_style {
             get_type!(StyleType::$ty);
             size!($value_ty);
             write_buffer!();
+            read_buffer!($value_ty);
         }
 
         $crate::paste::item! {
@@ -189,6 +254,7 @@ macro_rules! impl_style {
                 }
                 get_type!(StyleType::$ty);
                 write_reset!();
+                unsafe fn read(_buffer: &[u8]) -> Self { Self }
             }
         }
     };
@@ -248,7 +314,7 @@ type TowF32 = [f32;2];
 impl_style!(DirectionType, Direction, Direction);
 impl_style!(AspectRatioType, AspectRatio, Number);
 impl_style!(OrderType, Order, isize);
-impl_style!(FlexBasisType, FlexBasis, Dimension);
+impl_style!(FlexBasisType, FlexBasis, LayoutDimension);
 
 
 impl_style!(DisplayType, Display, Display);
@@ -265,37 +331,39 @@ impl_style!(OverflowType, Overflow, bool);
 impl_style!(MaskImageType, MaskImage);
 impl_style!(MaskImageClipType, MaskImageClip, NotNanRect);
 
-impl_style!(WidthType, Width, Dimension);
-impl_style!(HeightType, Height, Dimension);
+impl_style!(WidthType, Width, LayoutDimension);
+impl_style!(HeightType, Height, LayoutDimension);
 
 
-impl_style!(MarginTopType, MarginTop, Dimension);
-impl_style!(MarginRightType, MarginRight, Dimension);
-impl_style!(MarginBottomType, MarginBottom, Dimension);
-impl_style!(MarginLeftType, MarginLeft, Dimension);
+impl_style!(MarginTopType, MarginTop, LayoutDimension);
+impl_style!(MarginRightType, MarginRight, LayoutDimension);
+impl_style!(MarginBottomType, MarginBottom, LayoutDimension);
+impl_style!(MarginLeftType, MarginLeft, LayoutDimension);
 
-impl_style!(PaddingTopType, PaddingTop, Dimension);
-impl_style!(PaddingRightType, PaddingRight, Dimension);
-impl_style!(PaddingBottomType, PaddingBottom, Dimension);
-impl_style!(PaddingLeftType, PaddingLeft, Dimension);
+impl_style!(PaddingTopType, PaddingTop, LayoutDimension);
+impl_style!(PaddingRightType, PaddingRight, LayoutDimension);
+impl_style!(PaddingBottomType, PaddingBottom, LayoutDimension);
+impl_style!(PaddingLeftType, PaddingLeft, LayoutDimension);
 
 impl_style!(BorderTopType, BorderTop, Dimension);
 impl_style!(BorderRightType, BorderRight, Dimension);
 impl_style!(BorderBottomType, BorderBottom, Dimension);
 impl_style!(BorderLeftType, BorderLeft, Dimension);
 
-impl_style!(PositionTopType, PositionTop, Dimension);
-impl_style!(PositionRightType, PositionRight, Dimension);
-impl_style!(PositionBottomType, PositionBottom, Dimension);
-impl_style!(PositionLeftType, PositionLeft, Dimension);
+impl_style!(PositionTopType, PositionTop, LayoutDimension);
+impl_style!(PositionRightType, PositionRight, LayoutDimension);
+impl_style!(PositionBottomType, PositionBottom, LayoutDimension);
+impl_style!(PositionLeftType, PositionLeft, LayoutDimension);
 
 impl_style!(RowGapType, RowGap, f32);
 impl_style!(ColumnGapType, ColumnGap, f32);
 impl_style!(AutoReduceType, AutoReduce, bool);
-impl_style!(MinWidthType, MinWidth, Dimension);
-impl_style!(MinHeightType, MinHeight, Dimension);
-impl_style!(MaxHeightType, MaxHeight, Dimension);
-impl_style!(MaxWidthType, MaxWidth, Dimension);
+impl_style!(PerspectiveType, Perspective, f32);
+impl_style!(TransformStyleType, TransformStyle, TransformStyle);
+impl_style!(MinWidthType, MinWidth, LayoutDimension);
+impl_style!(MinHeightType, MinHeight, LayoutDimension);
+impl_style!(MaxHeightType, MaxHeight, LayoutDimension);
+impl_style!(MaxWidthType, MaxWidth, LayoutDimension);
 impl_style!(JustifyContentType, JustifyContent, JustifyContent);
 impl_style!(FlexDirectionType, FlexDirection, FlexDirection);
 impl_style!(AlignContentType, AlignContent, AlignContent);
@@ -371,6 +439,7 @@ macro_rules! impl_interpolation {
 				std::mem::size_of::<Self>()
 			}
 		}
+		impl InterpolateIn for $ty {}
 
         // impl Add for $ty {
         //     type Output = Self;
@@ -391,6 +460,8 @@ macro_rules! impl_interpolation {
         impl FrameValueScale for $ty {
             fn scale(&self, rhs: KeyFrameCurveValue) -> Self { Self((self.0 as f32 * rhs) as $inner) }
         }
+
+        impl InterpolateIn for $ty {}
     };
 
     (@animatable_value, $ty: ident) => {
@@ -402,6 +473,33 @@ macro_rules! impl_interpolation {
         impl FrameValueScale for $ty {
             fn scale(&self, rhs: KeyFrameCurveValue) -> Self { Self(AnimatableValue::scale(&self.0, rhs)) }
         }
+
+        impl InterpolateIn for $ty {}
+    };
+
+    // 为需要特殊插值方式的值实现（值需要实现InterpolateValue）
+    // 不指定颜色空间时（如由pi_curves直接插值），按sRGB空间插值
+    (@interpolate, $ty: ident) => {
+		impl FrameDataValue for $ty {
+			fn interpolate(&self, rhs: &Self, amount: KeyFrameCurveValue) -> Self {
+				Self(InterpolateValue::interpolate(&self.0, &rhs.0, amount, ColorSpace::Srgb))
+			}
+			fn hermite(value1: &Self, tangent1: &Self, value2: &Self, tangent2: &Self, amount: KeyFrameCurveValue, frame_delta: KeyFrameCurveValue) -> Self {
+				Self(hermite_value(&value1.0, &tangent1.0, &value2.0, &tangent2.0, amount, frame_delta))
+			}
+			fn append(&self, rhs: &Self, amount: KeyFrameCurveValue) -> Self {
+				Self(self.0.add(&AnimatableValue::scale(&rhs.0, amount)))
+			}
+			fn size() -> usize {
+				std::mem::size_of::<Self>()
+			}
+		}
+
+		impl InterpolateIn for $ty {
+			fn interpolate_in(&self, rhs: &Self, amount: KeyFrameCurveValue, space: ColorSpace) -> Self {
+				Self(InterpolateValue::interpolate(&self.0, &rhs.0, amount, space))
+			}
+		}
     };
 
     (@animatable_value_next, $ty: ident, $inner: ident) => {
@@ -413,6 +511,8 @@ macro_rules! impl_interpolation {
         impl FrameValueScale for $ty {
             fn scale(&self, rhs: KeyFrameCurveValue) -> Self { Self($inner(AnimatableValue::scale(&self.0 .0, rhs))) }
         }
+
+        impl InterpolateIn for $ty {}
     };
 }
 
@@ -430,8 +530,8 @@ impl_interpolation!(@keep, WhiteSpaceType);
 impl_interpolation!(@keep, TextContentType);
 impl_interpolation!(@keep, TextAlignType);
 impl_interpolation!(@keep, VerticalAlignType);
-impl_interpolation!(@animatable_value, ColorType);
-impl_interpolation!(@animatable_value, TextStrokeType);
+impl_interpolation!(@interpolate, ColorType);
+impl_interpolation!(@interpolate, TextStrokeType);
 impl_interpolation!(@keep, TextShadowType);
 impl_interpolation!(@keep, TextOuterGlowType);
 
@@ -445,18 +545,35 @@ impl_interpolation!(@keep, BorderImageClipType);
 impl_interpolation!(@keep, BorderImageSliceType);
 impl_interpolation!(@keep, BorderImageRepeatType);
 
-impl_interpolation!(@animatable_value, BorderColorType);
+impl_interpolation!(@interpolate, BorderColorType);
 
-impl_interpolation!(@animatable_value, BackgroundColorType);
+impl_interpolation!(@interpolate, BackgroundColorType);
 
-impl_interpolation!(@animatable_value, BoxShadowType);
+impl_interpolation!(@interpolate, BoxShadowType);
 
 impl_interpolation!(@animatable_value, OpacityType);
 impl_interpolation!(@animatable_value, BorderRadiusType);
 impl_interpolation!(@animatable_value, HsiType);
 impl_interpolation!(@animatable_value, BlurType);
 impl_interpolation!(@animatable_value, TransformOriginType);
-impl_interpolation!(@animatable_value, TransformType);
+impl_interpolation!(@interpolate, TransformType);
+impl_interpolation!(@keep, TransformStyleType);
+// 透视距离按倒数插值，见interpolate_perspective
+impl FrameDataValue for PerspectiveType {
+	fn interpolate(&self, rhs: &Self, amount: KeyFrameCurveValue) -> Self {
+		Self(interpolate_perspective(self.0, rhs.0, amount))
+	}
+	fn hermite(value1: &Self, tangent1: &Self, value2: &Self, tangent2: &Self, amount: KeyFrameCurveValue, frame_delta: KeyFrameCurveValue) -> Self {
+		Self(hermite_value(&value1.0, &tangent1.0, &value2.0, &tangent2.0, amount, frame_delta))
+	}
+	fn append(&self, rhs: &Self, amount: KeyFrameCurveValue) -> Self {
+		Self(self.0 + rhs.0 * amount)
+	}
+	fn size() -> usize {
+		std::mem::size_of::<Self>()
+	}
+}
+impl InterpolateIn for PerspectiveType {}
 // impl_interpolation!(@animatable_value, TranslateType);
 // impl_interpolation!(@animatable_value, ScaleType);
 impl_interpolation!(@animatable_value, RotateType);
@@ -520,6 +637,25 @@ impl_interpolation!(@keep, PositionTypeType);
 impl_interpolation!(@keep, AlignSelfType);
 
 impl_interpolation!(@keep, BlendModeType);
+impl_interpolation!(@number, RowGapType, f32);
+impl_interpolation!(@number, ColumnGapType, f32);
+impl_interpolation!(@keep, AsImageType);
+impl_interpolation!(@keep, AutoReduceType);
+impl_interpolation!(@keep, OverflowWrapType);
+impl_interpolation!(@keep, TextOverflowType);
+// 动画、过渡属性在关键帧中没有意义，实现FrameDataValue只是为了所有属性的关键帧都能取值（见AttributeKeyFrames::sample）
+impl_interpolation!(@keep, AnimationNameType);
+impl_interpolation!(@keep, AnimationDurationType);
+impl_interpolation!(@keep, AnimationTimingFunctionType);
+impl_interpolation!(@keep, AnimationIterationCountType);
+impl_interpolation!(@keep, AnimationDelayType);
+impl_interpolation!(@keep, AnimationDirectionType);
+impl_interpolation!(@keep, AnimationFillModeType);
+impl_interpolation!(@keep, AnimationPlayStateType);
+impl_interpolation!(@keep, TransitionPropertyType);
+impl_interpolation!(@keep, TransitionDurationType);
+impl_interpolation!(@keep, TransitionTimingFunctionType);
+impl_interpolation!(@keep, TransitionDelayType);
 
 
 pub trait AnimatableValue {
@@ -527,6 +663,162 @@ pub trait AnimatableValue {
     fn scale(&self, other: f32) -> Self;
 }
 
+/// 不能由add、scale线性组合得到插值结果的值，如颜色需要在预乘透明度的空间中插值，渐变需要对齐色标
+pub trait InterpolateValue: AnimatableValue + Clone {
+    /// amount为0时为self，为1时为rhs；space为颜色插值使用的颜色空间，不含颜色的值忽略该参数
+    fn interpolate(&self, rhs: &Self, amount: f32, space: ColorSpace) -> Self;
+}
+
+/// 按指定的颜色空间插值，不含颜色的样式类型与FrameDataValue::interpolate相同
+pub trait InterpolateIn: FrameDataValue {
+    fn interpolate_in(&self, rhs: &Self, amount: KeyFrameCurveValue, _space: ColorSpace) -> Self { self.interpolate(rhs, amount) }
+}
+
+// 埃尔米特插值，切线乘以帧间隔
+fn hermite_value<T: AnimatableValue>(value1: &T, tangent1: &T, value2: &T, tangent2: &T, amount: f32, frame_delta: f32) -> T {
+    let squared = amount * amount;
+    let cubed = squared * amount;
+    let part1 = 2.0 * cubed - 3.0 * squared + 1.0;
+    let part2 = -2.0 * cubed + 3.0 * squared;
+    let part3 = cubed - 2.0 * squared + amount;
+    let part4 = cubed - squared;
+    value1
+        .scale(part1)
+        .add(&value2.scale(part2))
+        .add(&tangent1.scale(part3 * frame_delta))
+        .add(&tangent2.scale(part4 * frame_delta))
+}
+
+// 不能插值的值，前半段取self，后半段取rhs
+fn discrete<T: Clone>(value1: &T, value2: &T, amount: f32) -> T {
+    if amount < 0.5 {
+        value1.clone()
+    } else {
+        value2.clone()
+    }
+}
+
+pub(crate) fn lerp(value1: f32, value2: f32, amount: f32) -> f32 { value1 + (value2 - value1) * amount }
+
+pub(crate) fn lerp_length(value1: &LengthUnit, value2: &LengthUnit, amount: f32) -> LengthUnit { value1.scale(1.0 - amount).add(&value2.scale(amount)) }
+
+impl InterpolateValue for CgColor {
+    fn interpolate(&self, rhs: &Self, amount: f32, space: ColorSpace) -> Self { self.mix(rhs, amount, space) }
+}
+
+impl InterpolateValue for Stroke {
+    fn interpolate(&self, rhs: &Self, amount: f32, space: ColorSpace) -> Self {
+        Stroke {
+            width: NotNan::new(lerp(*self.width, *rhs.width, amount)).unwrap(),
+            color: self.color.interpolate(&rhs.color, amount, space),
+        }
+    }
+}
+
+impl InterpolateValue for BoxShadow {
+    fn interpolate(&self, rhs: &Self, amount: f32, space: ColorSpace) -> Self {
+        Self {
+            h: lerp(self.h, rhs.h, amount),
+            v: lerp(self.v, rhs.v, amount),
+            blur: lerp(self.blur, rhs.blur, amount),
+            spread: lerp(self.spread, rhs.spread, amount),
+            color: self.color.interpolate(&rhs.color, amount, space),
+        }
+    }
+}
+
+/// 对齐两个色标列表后插值：取两个列表所有色标的位置，在每个位置上分别取两个渐变的颜色再插值
+/// 同一位置有多个色标（颜色突变）时，保留较多的一方的色标数量
+pub fn interpolate_color_stops(list1: &[ColorAndPosition], list2: &[ColorAndPosition], amount: f32, space: ColorSpace) -> Vec<ColorAndPosition> {
+    // 取到list在position处的第index个颜色，没有色标时由前后色标插值得到
+    fn color_at(list: &[ColorAndPosition], position: f32, index: usize) -> CgColor {
+        let same: Vec<&ColorAndPosition> = list.iter().filter(|r| r.position == position).collect();
+        match same.get(index).or(same.last()) {
+            Some(r) => r.rgba.clone(),
+            None => sample_color_stops(list, position),
+        }
+    }
+
+    let mut positions: Vec<f32> = list1.iter().chain(list2.iter()).map(|r| r.position).collect();
+    positions.sort_by(|a, b| a.total_cmp(b));
+    positions.dedup();
+
+    let mut ret = Vec::with_capacity(positions.len());
+    for position in positions {
+        let count = |list: &[ColorAndPosition]| list.iter().filter(|r| r.position == position).count();
+        for index in 0..count(list1).max(count(list2)) {
+            ret.push(ColorAndPosition {
+                position,
+                rgba: color_at(list1, position, index).interpolate(&color_at(list2, position, index), amount, space),
+            });
+        }
+    }
+    ret
+}
+
+// 与gradient形状相同、颜色都为rgba的渐变，用于纯色与渐变之间的插值；无法构造时返回None
+fn solid_gradient(rgba: &CgColor, gradient: &Color) -> Option<Color> {
+    let solid = |list: &[ColorAndPosition]| -> Vec<ColorAndPosition> {
+        list.iter()
+            .map(|r| ColorAndPosition {
+                position: r.position,
+                rgba: rgba.clone(),
+            })
+            .collect()
+    };
+    match gradient {
+        Color::RGBA(_) => None,
+        Color::LinearGradient(r) if r.stops.is_empty() => Some(Color::LinearGradient(LinearGradientColor { list: solid(&r.list), ..r.clone() })),
+        Color::LinearGradient(_) => None,
+        Color::RadialGradient(r) => Some(Color::RadialGradient(RadialGradientColor { list: solid(&r.list), ..r.clone() })),
+        Color::ConicGradient(r) => Some(Color::ConicGradient(ConicGradientColor { list: solid(&r.list), ..r.clone() })),
+    }
+}
+
+/// * 纯色之间按space插值
+/// * 同类型的渐变之间，对齐色标后插值，角度、中心点等线性插值，形状、是否重复等离散变化
+/// * 纯色与渐变之间，将纯色视为色标颜色都相同的渐变
+/// * 其余情况（如线性渐变与径向渐变之间、含有长度色标或指向不同角的线性渐变）离散变化
+/// * 含有渐变时，amount在0~1之外取端点的值（对齐色标后的渐变与端点的值并不相同，如纯色会变为渐变）
+impl InterpolateValue for Color {
+    fn interpolate(&self, rhs: &Self, amount: f32, space: ColorSpace) -> Self {
+        match (self, rhs) {
+            (Color::RGBA(r1), Color::RGBA(r2)) => Color::RGBA(r1.interpolate(r2, amount, space)),
+            _ if amount <= 0.0 => self.clone(),
+            _ if amount >= 1.0 => rhs.clone(),
+            (Color::LinearGradient(r1), Color::LinearGradient(r2)) if r1.stops.is_empty() && r2.stops.is_empty() && r1.corner == r2.corner => Color::LinearGradient(LinearGradientColor {
+                direction: lerp(r1.direction, r2.direction, amount),
+                list: interpolate_color_stops(&r1.list, &r2.list, amount, space),
+                repeat: discrete(&r1.repeat, &r2.repeat, amount),
+                stops: Vec::new(),
+                corner: r1.corner,
+            }),
+            (Color::RadialGradient(r1), Color::RadialGradient(r2)) => Color::RadialGradient(RadialGradientColor {
+                center: Center {
+                    x: lerp_length(&r1.center.x, &r2.center.x, amount),
+                    y: lerp_length(&r1.center.y, &r2.center.y, amount),
+                },
+                shape: discrete(&r1.shape, &r2.shape, amount),
+                size: discrete(&r1.size, &r2.size, amount),
+                list: interpolate_color_stops(&r1.list, &r2.list, amount, space),
+                repeat: discrete(&r1.repeat, &r2.repeat, amount),
+            }),
+            (Color::ConicGradient(r1), Color::ConicGradient(r2)) => Color::ConicGradient(ConicGradientColor {
+                from: lerp(r1.from, r2.from, amount),
+                center: Center {
+                    x: lerp_length(&r1.center.x, &r2.center.x, amount),
+                    y: lerp_length(&r1.center.y, &r2.center.y, amount),
+                },
+                list: interpolate_color_stops(&r1.list, &r2.list, amount, space),
+                repeat: discrete(&r1.repeat, &r2.repeat, amount),
+            }),
+            (Color::RGBA(r1), r2) if let Some(gradient) = solid_gradient(r1, r2) => gradient.interpolate(r2, amount, space),
+            (r1, Color::RGBA(r2)) if let Some(gradient) = solid_gradient(r2, r1) => r1.interpolate(&gradient, amount, space),
+            _ => discrete(self, rhs, amount),
+        }
+    }
+}
+
 impl AnimatableValue for Dimension {
     fn add(&self, rhs: &Self) -> Self {
         log::trace!("add: {:?}, {:?}", self, rhs);
@@ -614,16 +906,16 @@ impl AnimatableValue for Number {
 
 impl AnimatableValue for CgColor {
     #[inline]
-    fn add(&self, rhs: &Self) -> Self { CgColor::new(self.x + rhs.x, self.x + rhs.x, self.x + rhs.x, self.x + rhs.x) }
+    fn add(&self, rhs: &Self) -> Self { CgColor::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z, self.w + rhs.w) }
     #[inline]
-    fn scale(&self, other: f32) -> Self { CgColor::new(self.x * other, self.x * other, self.x * other, self.x * other) }
+    fn scale(&self, other: f32) -> Self { CgColor::new(self.x * other, self.y * other, self.z * other, self.w * other) }
 }
 
 impl AnimatableValue for Hsi {
     #[inline]
     fn add(&self, rhs: &Self) -> Self {
         Hsi {
-            hue_rotate: self.hue_rotate + rhs.hue_rotate,
+            hue_rotate: Turn(self.hue_rotate.0 + rhs.hue_rotate.0),
             saturate: self.saturate + rhs.saturate,
             bright_ness: self.bright_ness + rhs.bright_ness,
         }
@@ -631,7 +923,7 @@ impl AnimatableValue for Hsi {
     #[inline]
     fn scale(&self, other: f32) -> Self {
         Hsi {
-            hue_rotate: self.hue_rotate * other,
+            hue_rotate: Turn(self.hue_rotate.0 * other),
             saturate: self.saturate * other,
             bright_ness: self.bright_ness * other,
         }
@@ -712,16 +1004,15 @@ impl AnimatableValue for Color {
     fn add(&self, rhs: &Self) -> Self {
         match (self, rhs) {
             (Color::RGBA(r1), Color::RGBA(r2)) => Color::RGBA(r1.add(r2)),
-            (Color::RGBA(r1), Color::LinearGradient(_)) => Color::RGBA(r1.clone()),
-            (Color::LinearGradient(r1), Color::RGBA(_)) => Color::LinearGradient(r1.clone()),
-            (Color::LinearGradient(r1), Color::LinearGradient(_)) => Color::LinearGradient(r1.clone()),
+            // 渐变不做插值
+            (r1, _) => r1.clone(),
         }
     }
     #[inline]
     fn scale(&self, other: f32) -> Self {
         match self {
             Color::RGBA(r1) => Color::RGBA(r1.scale(other)),
-            Color::LinearGradient(r) => Color::LinearGradient(r.clone()),
+            r => r.clone(),
         }
     }
 }
@@ -820,9 +1111,9 @@ impl AnimatableValue for LengthUnit {
     fn add(&self, rhs: &Self) -> Self {
         match (self, rhs) {
             (LengthUnit::Pixel(r1), LengthUnit::Pixel(r2)) => LengthUnit::Pixel(r1 + r2),
-            (LengthUnit::Pixel(r1), LengthUnit::Percent(_)) => LengthUnit::Pixel(*r1),
-            (LengthUnit::Percent(r1), LengthUnit::Pixel(_)) => LengthUnit::Percent(*r1),
             (LengthUnit::Percent(r1), LengthUnit::Percent(r2)) => LengthUnit::Percent(r1 + r2),
+            // 单位不同时，结果为表达式，如：calc(50% + 10px)
+            _ => LengthUnit::from_calc(self.to_calc() + rhs.to_calc()),
         }
     }
     #[inline]
@@ -830,6 +1121,30 @@ impl AnimatableValue for LengthUnit {
         match self {
             LengthUnit::Pixel(r1) => LengthUnit::Pixel(r1 * other),
             LengthUnit::Percent(r1) => LengthUnit::Percent(r1 * other),
+            LengthUnit::Calc(r1) => LengthUnit::from_calc((**r1).clone() * other),
+        }
+    }
+}
+
+impl AnimatableValue for LayoutDimension {
+    fn add(&self, rhs: &Self) -> Self {
+        match (self, rhs) {
+            (LayoutDimension::Points(r1), LayoutDimension::Points(r2)) => LayoutDimension::Points(r1 + r2),
+            (LayoutDimension::Percent(r1), LayoutDimension::Percent(r2)) => LayoutDimension::Percent(r1 + r2),
+            // 单位不同时，结果为表达式，如：calc(50% + 10px)；Undefined、Auto不插值
+            _ => match (self.to_calc(), rhs.to_calc()) {
+                (Some(r1), Some(r2)) => LayoutDimension::from_calc(r1 + r2),
+                _ => self.clone(),
+            },
+        }
+    }
+    fn scale(&self, other: f32) -> Self {
+        match self {
+            LayoutDimension::Undefined => LayoutDimension::Undefined,
+            LayoutDimension::Auto => LayoutDimension::Auto,
+            LayoutDimension::Points(r1) => LayoutDimension::Points(r1 * other),
+            LayoutDimension::Percent(r1) => LayoutDimension::Percent(r1 * other),
+            LayoutDimension::Calc(r1) => LayoutDimension::from_calc((**r1).clone() * other),
         }
     }
 }
@@ -851,6 +1166,13 @@ impl AnimatableValue for TransformOrigin {
     }
 }
 
+// 函数列表不对应时，分解矩阵后插值，见style_transform
+impl InterpolateValue for TransformFuncs {
+    fn interpolate(&self, rhs: &Self, amount: f32, _space: ColorSpace) -> Self {
+        interpolate_transform_funcs(self, rhs, amount).unwrap_or_else(|| discrete(self, rhs, amount))
+    }
+}
+
 impl AnimatableValue for TransformFuncs {
     fn add(&self, rhs: &Self) -> Self {
         if self.len() != rhs.len() {
@@ -872,6 +1194,24 @@ impl AnimatableValue for TransformFuncs {
                 (TransformFunc::RotateZ(t1), TransformFunc::RotateZ(t2)) => vec.push(TransformFunc::RotateZ(t1 + t2)),
                 (TransformFunc::SkewX(t1), TransformFunc::SkewX(t2)) => vec.push(TransformFunc::SkewX(t1 + t2)),
                 (TransformFunc::SkewY(t1), TransformFunc::SkewY(t2)) => vec.push(TransformFunc::SkewY(t1 + t2)),
+                (TransformFunc::TranslateZ(t1), TransformFunc::TranslateZ(t2)) => vec.push(TransformFunc::TranslateZ(t1 + t2)),
+                (TransformFunc::Translate3D(x1, y1, z1), TransformFunc::Translate3D(x2, y2, z2)) => vec.push(TransformFunc::Translate3D(x1.add(x2), y1.add(y2), z1 + z2)),
+                (TransformFunc::ScaleZ(t1), TransformFunc::ScaleZ(t2)) => vec.push(TransformFunc::ScaleZ(t1 + t2)),
+                (TransformFunc::Scale3D(x1, y1, z1), TransformFunc::Scale3D(x2, y2, z2)) => vec.push(TransformFunc::Scale3D(x1 + x2, y1 + y2, z1 + z2)),
+                // 旋转轴相同时才能相加
+                (TransformFunc::Rotate3D(x1, y1, z1, t1), TransformFunc::Rotate3D(x2, y2, z2, t2)) if x1 == x2 && y1 == y2 && z1 == z2 => {
+                    vec.push(TransformFunc::Rotate3D(*x1, *y1, *z1, t1 + t2))
+                }
+                (TransformFunc::Skew(x1, y1), TransformFunc::Skew(x2, y2)) => vec.push(TransformFunc::Skew(x1 + x2, y1 + y2)),
+                (TransformFunc::Perspective(t1), TransformFunc::Perspective(t2)) => vec.push(TransformFunc::Perspective(t1 + t2)),
+                (TransformFunc::Matrix(a1, b1, c1, d1, e1, f1), TransformFunc::Matrix(a2, b2, c2, d2, e2, f2)) => {
+                    vec.push(TransformFunc::Matrix(a1 + a2, b1 + b2, c1 + c2, d1 + d2, e1 + e2, f1 + f2))
+                }
+                (TransformFunc::Matrix3D(m1), TransformFunc::Matrix3D(m2)) => {
+                    let mut m = m1.clone();
+                    m.iter_mut().zip(m2.iter()).for_each(|(r1, r2)| *r1 += r2);
+                    vec.push(TransformFunc::Matrix3D(m))
+                }
                 _ => return self.clone(), // 其他情况无法插值，则返回原值
             }
         }
@@ -894,6 +1234,15 @@ impl AnimatableValue for TransformFuncs {
                 TransformFunc::RotateZ(t1) => vec.push(TransformFunc::RotateZ(t1 * other)),
                 TransformFunc::SkewX(t1) => vec.push(TransformFunc::SkewX(t1 * other)),
                 TransformFunc::SkewY(t1) => vec.push(TransformFunc::SkewY(t1 * other)),
+                TransformFunc::TranslateZ(t1) => vec.push(TransformFunc::TranslateZ(t1 * other)),
+                TransformFunc::Translate3D(t1, t2, t3) => vec.push(TransformFunc::Translate3D(t1.scale(other), t2.scale(other), t3 * other)),
+                TransformFunc::ScaleZ(t1) => vec.push(TransformFunc::ScaleZ(t1 * other)),
+                TransformFunc::Scale3D(t1, t2, t3) => vec.push(TransformFunc::Scale3D(t1 * other, t2 * other, t3 * other)),
+                TransformFunc::Rotate3D(x, y, z, t1) => vec.push(TransformFunc::Rotate3D(*x, *y, *z, t1 * other)),
+                TransformFunc::Skew(t1, t2) => vec.push(TransformFunc::Skew(t1 * other, t2 * other)),
+                TransformFunc::Perspective(t1) => vec.push(TransformFunc::Perspective(t1 * other)),
+                TransformFunc::Matrix(a, b, c, d, e, f) => vec.push(TransformFunc::Matrix(a * other, b * other, c * other, d * other, e * other, f * other)),
+                TransformFunc::Matrix3D(m) => vec.push(TransformFunc::Matrix3D(Box::new(m.map(|r| r * other)))),
             }
         }
         vec
@@ -935,8 +1284,8 @@ impl AnimatableValue for BaseShape {
 				}
 			},
 			(BaseShape::Sector{angle: angle1, rotate: rotate1, radius: radius1,  center: center1}, BaseShape::Sector{angle: angle2, rotate: rotate2, radius: radius2, center: center2}) => BaseShape::Sector {
-				angle: angle1 + angle2,
-				rotate: rotate1 + rotate2,
+				angle: Deg(angle1.0 + angle2.0),
+				rotate: Deg(rotate1.0 + rotate2.0),
 				radius: radius1.add(radius2),
 				center: Center {x:  center1.x.add(&center2.x), y: center1.y.add(&center2.y)}
 			},
@@ -967,7 +1316,7 @@ impl AnimatableValue for BaseShape {
 					border_radius.y[3].scale(other),
 				]
 			} } ,
-			BaseShape::Sector { angle, rotate, radius, center } => BaseShape::Sector { angle: angle * other, rotate: rotate * other, radius: radius.scale(other), center: Center {x:  center.x.scale(other), y: center.y.scale(other)} },
+			BaseShape::Sector { angle, rotate, radius, center } => BaseShape::Sector { angle: Deg(angle.0 * other), rotate: Deg(rotate.0 * other), radius: radius.scale(other), center: Center {x:  center.x.scale(other), y: center.y.scale(other)} },
 		}
         // match self {
         //     LengthUnit::Pixel(r1) => LengthUnit::Pixel(r1 * other),
@@ -985,6 +1334,8 @@ impl FrameValueScale for TranslateType {
 	fn scale(&self, rhs: KeyFrameCurveValue) -> Self { Self([self.0[0].scale(rhs), self.0[1].scale(rhs)]) }
 }
 
+impl InterpolateIn for TranslateType {}
+
 impl Add for ScaleType {
 	type Output = Self;
 	fn add(self, rhs: Self) -> Self::Output { Self([self.0[0].add(&rhs.0[0]), self.0[1].add(&rhs.0[1])]) }
@@ -994,6 +1345,8 @@ impl FrameValueScale for ScaleType {
 	fn scale(&self, rhs: KeyFrameCurveValue) -> Self { Self([AnimatableValue::scale(&self.0[0], rhs), AnimatableValue::scale(&self.0[1], rhs)]) }
 }
 
+impl InterpolateIn for ScaleType {}
+
 
 impl Add for EmptyType {
 	type Output = Self;
@@ -1003,3 +1356,103 @@ impl Add for EmptyType {
 impl FrameValueScale for EmptyType {
 	fn scale(&self, _rhs: KeyFrameCurveValue) -> Self { Self }
 }
+
+#[test]
+fn test_color_interpolation() {
+    use crate::style_parse::{parse_class_map_from_string, Attribute};
+    use std::collections::VecDeque;
+
+    let near = |r: &CgColor, expect: [f32; 4]| (0..4).all(|i| (r[i] - expect[i]).abs() < 2e-3);
+
+    // add、scale按分量计算
+    let r = CgColor::new(0.1, 0.2, 0.3, 0.4).scale(2.0).add(&CgColor::new(0.1, 0.1, 0.1, 0.1));
+    assert!(near(&r, [0.3, 0.5, 0.7, 0.9]));
+
+    let class_map = parse_class_map_from_string(
+        "@keyframes a {
+            0% { color: #ff0000; border-color: #ff0000; box-shadow: 0px 0px 0px 0px #00000000; background: linear-gradient(90deg, #ff0000, #0000ff); }
+            50% { color: #0000ff; border-color: rgba(0, 0, 255, 0); box-shadow: 10px 20px 0px 0px #000000; background: linear-gradient(90deg, #ffffff, #000000 50%, #ffffff); }
+            100% { color: #00ff00; border-color: #0000ff; box-shadow: 10px 20px 0px 0px #000000; background-color: #00ff00; }
+        }",
+        0,
+    )
+    .unwrap();
+    let frames = &class_map.key_frames.frames[&Atom::from("a")];
+    let mut frames: Vec<(f32, &VecDeque<Attribute>)> = frames.iter().map(|(k, v)| (**k, v)).collect();
+    frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // 在关键帧曲线上取值（线性，sRGB空间）
+    fn sample<T: FrameDataValue>(frames: &[(f32, &VecDeque<Attribute>)], progress: f32, f: impl Fn(&Attribute) -> Option<T>) -> T {
+        let value = |attrs: &VecDeque<Attribute>| attrs.iter().find_map(&f).unwrap();
+        let i = frames.iter().rposition(|r| r.0 <= progress).unwrap().min(frames.len() - 2);
+        let (p0, p1) = (frames[i].0, frames[i + 1].0);
+        value(frames[i].1).interpolate(&value(frames[i + 1].1), (progress - p0) / (p1 - p0))
+    }
+    let color = |progress: f32| -> CgColor {
+        match sample(&frames, progress, |r| if let Attribute::Color(r) = r { Some(r.clone()) } else { None }).0 {
+            Color::RGBA(r) => r,
+            r => panic!("{:?}", r),
+        }
+    };
+    let border_color = |progress: f32| sample(&frames, progress, |r| if let Attribute::BorderColor(r) = r { Some(r.clone()) } else { None }).0;
+    let box_shadow = |progress: f32| sample(&frames, progress, |r| if let Attribute::BoxShadow(r) = r { Some(r.clone()) } else { None }).0;
+    let background = |progress: f32| sample(&frames, progress, |r| if let Attribute::BackgroundColor(r) = r { Some(r.clone()) } else { None }).0;
+
+    assert!(near(&color(0.0), [1.0, 0.0, 0.0, 1.0]));
+    assert!(near(&color(0.25), [0.5, 0.0, 0.5, 1.0]));
+    assert!(near(&color(0.75), [0.0, 0.5, 0.5, 1.0]));
+    assert!(near(&color(1.0), [0.0, 1.0, 0.0, 1.0]));
+
+    // 与透明色插值时，颜色不会变暗
+    assert!(near(&border_color(0.25), [1.0, 0.0, 0.0, 0.5]));
+    assert!(near(&border_color(0.75), [0.0, 0.0, 1.0, 0.5]));
+    let r = box_shadow(0.25);
+    assert!((r.h - 5.0).abs() < 1e-4 && (r.v - 10.0).abs() < 1e-4);
+    assert!(near(&r.color, [0.0, 0.0, 0.0, 0.5]));
+
+    // 线性空间，颜色空间按曲线设置，不影响其它曲线
+    let mut curves = class_map.key_frames.build_curves(|_| None).remove(&Atom::from("a")).unwrap();
+    let linear = curves.iter_mut().find(|r| r.get_type() == StyleType::Color).unwrap();
+    linear.set_color_space(ColorSpace::LinearSrgb);
+    match linear.sample(0.25, &AnimationTimingFunction::Linear) {
+        Some(Attribute::Color(ColorType(Color::RGBA(r)))) => assert!(near(&r, [0.735, 0.0, 0.735, 1.0])),
+        r => panic!("{:?}", r),
+    }
+    assert!(near(&color(0.25), [0.5, 0.0, 0.5, 1.0]));
+    let red = ColorType(Color::RGBA(CgColor::new(1.0, 0.0, 0.0, 1.0)));
+    let blue = ColorType(Color::RGBA(CgColor::new(0.0, 0.0, 1.0, 1.0)));
+    match red.interpolate_in(&blue, 0.5, ColorSpace::Hsl).0 {
+        Color::RGBA(r) => assert!(near(&r, [1.0, 0.0, 1.0, 1.0])),
+        r => panic!("{:?}", r),
+    }
+
+    // 渐变之间对齐色标：[red 0%, blue 100%]与[white 0%, black 50%, white 100%]
+    match background(0.25) {
+        Color::LinearGradient(r) => {
+            assert_eq!(r.list.iter().map(|r| r.position).collect::<Vec<f32>>(), vec![0.0, 0.5, 1.0]);
+            assert!(near(&r.list[0].rgba, [1.0, 0.5, 0.5, 1.0]));
+            assert!(near(&r.list[1].rgba, [0.25, 0.0, 0.25, 1.0]));
+            assert!(near(&r.list[2].rgba, [0.5, 0.5, 1.0, 1.0]));
+        }
+        r => panic!("{:?}", r),
+    }
+    // 渐变与纯色之间
+    match background(0.75) {
+        Color::LinearGradient(r) => {
+            assert!(near(&r.list[0].rgba, [0.5, 1.0, 0.5, 1.0]));
+            assert!(near(&r.list[1].rgba, [0.0, 0.5, 0.0, 1.0]));
+        }
+        r => panic!("{:?}", r),
+    }
+    // 端点处取端点的值
+    match background(1.0) {
+        Color::RGBA(r) => assert!(near(&r, [0.0, 1.0, 0.0, 1.0])),
+        r => panic!("{:?}", r),
+    }
+    let solid = Color::RGBA(CgColor::new(0.0, 1.0, 0.0, 1.0));
+    match solid.interpolate(&background(0.5), 0.0, ColorSpace::Srgb) {
+        Color::RGBA(r) => assert!(near(&r, [0.0, 1.0, 0.0, 1.0])),
+        r => panic!("{:?}", r),
+    }
+    assert!(matches!(solid.interpolate(&background(0.5), 1.0, ColorSpace::Srgb), Color::LinearGradient(r) if r.list.len() == 3));
+}
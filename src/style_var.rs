@@ -0,0 +1,448 @@
+//! css自定义属性（`--name: value`）及var()
+//!
+//! 自定义属性可以定义在`:root`中（全局），也可以定义在class中（只对该class中的var()生效，不会被子节点继承）。
+//! 含有var()的声明在解析时不会被解析为样式，而是记录其原始文本，编译为ClassSheet时才进行文本替换并解析，
+//! 因此可以在运行时切换主题（Theme），重新生成受影响的ClassMeta。
+//!
+//! 查找变量的顺序：class中的自定义属性 > Theme > `:root`
+//!
+//! 关键帧中的var()在整个样式表解析完成后使用`:root`中的自定义属性计算，不随主题变化
+//!
+//! 含有相对长度（em、rem、vw等）的声明也按同样的方式延迟计算，见style_length
+//!
+//! 含有`currentColor`的声明同样延迟计算，`currentColor`取同一样式列表中在其之前的最后一个`color`，
+//! 没有时取黑色（color的初始值）；不考虑从父节点继承的color
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+
+use cssparser::{CowRcStr, Parser, ParserInput, SourceLocation, Token};
+use pi_hash::XHashMap;
+
+use crate::style::{Color, FontSize};
+use crate::style_length::{has_viewport_units, resolve_relative_lengths, LengthContext};
+use crate::style_parse::{parse_style_item_value, Attribute};
+
+/// var()的最大嵌套层数，超过时认为变量循环引用
+const MAX_VAR_DEPTH: usize = 16;
+
+/// 主题，即一组自定义属性
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Theme {
+    /// 变量名（含`--`）到变量值（原始文本）
+    pub vars: XHashMap<String, String>,
+}
+
+impl Theme {
+    pub fn get(&self, name: &str) -> Option<&str> { self.vars.get(name).map(|r| r.as_str()) }
+
+    pub fn set(&mut self, name: &str, value: &str) { self.vars.insert(name.to_string(), value.to_string()); }
+
+    pub fn is_empty(&self) -> bool { self.vars.is_empty() }
+}
+
+/// 含有var()、相对长度（见style_length）或currentColor的声明
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VarDeclaration {
+    /// 在所属样式列表中的位置，计算得到的样式插入到该位置
+    pub index: usize,
+    /// 属性名
+    pub name: String,
+    /// 属性值的原始文本
+    pub value: String,
+    /// 在源文件中的位置（SourceLocation的行号和列号）
+    pub line: u32,
+    pub column: u32,
+}
+
+/// 一个样式列表中的自定义属性及含有var()的声明
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VarDeclarations {
+    /// 自定义属性，按定义顺序排列
+    pub vars: Vec<(String, String)>,
+    /// 含有var()、相对长度或currentColor的声明，按定义顺序排列
+    pub decls: Vec<VarDeclaration>,
+}
+
+impl VarDeclarations {
+    pub fn is_empty(&self) -> bool { self.vars.is_empty() && self.decls.is_empty() }
+
+    /// 追加另一个样式列表的声明，该样式列表被追加在长度为len的样式列表之后
+    pub fn extend(&mut self, other: VarDeclarations, len: usize) {
+        self.vars.extend(other.vars);
+        self.decls.extend(other.decls.into_iter().map(|mut r| {
+            r.index += len;
+            r
+        }));
+    }
+
+    /// 查找变量，先查找自身的自定义属性（后定义的优先），再依次查找themes
+    pub fn lookup<'a>(&'a self, name: &str, themes: &[&'a Theme]) -> Option<&'a str> {
+        if let Some((_, r)) = self.vars.iter().rev().find(|(r, _)| r == name) {
+            return Some(r.as_str());
+        }
+        themes.iter().find_map(|r| r.get(name))
+    }
+
+    /// 声明中是否（直接或间接）引用了changed返回true的变量，自身定义的自定义属性不受themes变化的影响
+    pub fn references<'a>(&'a self, themes: &[&'a Theme], changed: &dyn Fn(&str) -> bool) -> bool {
+        let found = Cell::new(false);
+        let lookup = |name: &str| {
+            if !self.vars.iter().any(|(r, _)| r == name) && changed(name) {
+                found.set(true);
+            }
+            self.lookup(name, themes)
+        };
+        self.decls.iter().any(|decl| {
+            let _ = substitute_vars(&decl.value, &lookup);
+            found.get()
+        })
+    }
+
+    /// 是否有声明在替换var()后含有vw、vh、vmin、vmax
+    pub fn uses_viewport(&self, themes: &[&Theme]) -> bool {
+        self.decls
+            .iter()
+            .any(|decl| substitute_vars(&decl.value, &|name| self.lookup(name, themes)).is_ok_and(|r| has_viewport_units(&r)))
+    }
+
+    /// 只替换声明中的var()，相对长度、currentColor保持不变，之后可以不再依赖themes计算
+    /// 替换失败的声明被移除，返回其位置和错误信息
+    pub fn substitute(&mut self, themes: &[&Theme]) -> Vec<(SourceLocation, String)> {
+        let values: Vec<Result<String, String>> = self.decls.iter().map(|decl| substitute_vars(&decl.value, &|name| self.lookup(name, themes))).collect();
+        let mut values = values.into_iter();
+        let mut errors = Vec::new();
+        self.decls.retain_mut(|decl| match values.next().unwrap() {
+            Ok(r) => {
+                decl.value = r;
+                true
+            }
+            Err(e) => {
+                errors.push((SourceLocation { line: decl.line, column: decl.column }, format!("{}: {}", decl.name, e)));
+                false
+            }
+        });
+        errors
+    }
+
+    /// 计算所有声明（依次替换var()、相对长度、currentColor），将得到的样式插入到attrs中
+    /// 计算失败的声明被忽略，返回其位置和错误信息
+    pub fn resolve(&self, attrs: &mut VecDeque<Attribute>, themes: &[&Theme], lengths: &LengthContext, scope_hash: usize) -> Vec<(SourceLocation, String)> {
+        let mut errors = Vec::new();
+        let mut offset = 0;
+        for decl in self.decls.iter() {
+            let location = SourceLocation {
+                line: decl.line,
+                column: decl.column,
+            };
+            let index = (decl.index + offset).min(attrs.len());
+            // font-size中的em相对于父节点的字体大小，其余属性中的em相对于当前的字体大小
+            let font_size = match current_font_size(attrs, index) {
+                Some(font_size) if decl.name != "font-size" => Some(LengthContext { font_size, ..lengths.clone() }),
+                _ => None,
+            };
+            let value = substitute_vars(&decl.value, &|name| self.lookup(name, themes)).and_then(|r| resolve_relative_lengths(&r, font_size.as_ref().unwrap_or(lengths)));
+            let value = match value {
+                Ok(r) => r,
+                Err(e) => {
+                    errors.push((location, format!("{}: {}", decl.name, e)));
+                    continue;
+                }
+            };
+
+            let current_color = current_color(attrs, index);
+            let value = rewrite_tokens(&value, &mut |token| match token {
+                Token::Ident(r) if r.eq_ignore_ascii_case("currentcolor") => Some(current_color.clone()),
+                _ => None,
+            });
+
+            let mut list = VecDeque::new();
+            if let Err(e) = parse_value(location, &decl.name, &value, &mut list, scope_hash) {
+                errors.push((location, format!("{}: {}, value: {}", decl.name, e, value)));
+                continue;
+            }
+            for (i, attr) in list.into_iter().enumerate() {
+                attrs.insert(index + i, attr);
+                offset += 1;
+            }
+        }
+        errors
+    }
+}
+
+/// attrs中位于index之前的最后一个font-size（px）
+fn current_font_size(attrs: &VecDeque<Attribute>, index: usize) -> Option<f32> {
+    let font_size = attrs.range(..index).rev().find_map(|r| match r {
+        Attribute::FontSize(r) => Some(&r.0),
+        _ => None,
+    });
+    match font_size {
+        Some(FontSize::Length(r)) => Some(*r as f32),
+        _ => None,
+    }
+}
+
+/// attrs中位于index之前的最后一个color，没有时为黑色
+fn current_color(attrs: &VecDeque<Attribute>, index: usize) -> String {
+    let color = attrs.range(..index).rev().find_map(|r| match r {
+        Attribute::Color(r) => match &r.0 {
+            Color::RGBA(r) => Some(r.clone()),
+            _ => None,
+        },
+        _ => None,
+    });
+    match color {
+        Some(r) => format!("rgb({} {} {} / {})", r.x * 255.0, r.y * 255.0, r.z * 255.0, r.w),
+        None => "#000000".to_string(),
+    }
+}
+
+fn parse_value(location: SourceLocation, name: &str, value: &str, buffer: &mut VecDeque<Attribute>, scope_hash: usize) -> Result<(), String> {
+    let text = format!(":{}", value);
+    let mut input = ParserInput::new(&text);
+    let mut parser = Parser::new(&mut input);
+    let r = parse_style_item_value(location, CowRcStr::from(name), buffer, scope_hash, &mut parser);
+    r.map_err(|e| e.to_string())
+}
+
+/// 将value中的var()替换为变量的值
+/// * lookup：根据变量名（含`--`）取到变量的值，变量的值中可以继续使用var()
+///
+/// 变量不存在且没有默认值，或变量循环引用时，返回错误
+pub fn substitute_vars<'a>(value: &str, lookup: &dyn Fn(&str) -> Option<&'a str>) -> Result<String, String> { substitute(value, lookup, 0) }
+
+fn substitute<'a>(value: &str, lookup: &dyn Fn(&str) -> Option<&'a str>, depth: usize) -> Result<String, String> {
+    if depth > MAX_VAR_DEPTH {
+        return Err("var() is recursive".to_string());
+    }
+
+    let mut ret = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = find_var(rest) {
+        ret.push_str(&rest[..start]);
+        let args_start = start + "var(".len();
+        let end = args_start + find_close(&rest[args_start..]).ok_or_else(|| "var() is not closed".to_string())?;
+        let args = &rest[args_start..end];
+        let (name, fallback) = match find_comma(args) {
+            Some(i) => (args[..i].trim(), Some(args[i + 1..].trim())),
+            None => (args.trim(), None),
+        };
+        if !name.starts_with("--") {
+            return Err(format!("invalid var name: {}", name));
+        }
+        let r = match lookup(name).or(fallback) {
+            Some(r) => r,
+            None => return Err(format!("undefined var: {}", name)),
+        };
+        ret.push_str(&substitute(r, lookup, depth + 1)?);
+        rest = &rest[end + 1..];
+    }
+    ret.push_str(rest);
+    Ok(ret)
+}
+
+fn is_ident_byte(c: u8) -> bool { c.is_ascii_alphanumeric() || c == b'-' || c == b'_' || c >= 0x80 }
+
+/// 遍历字符串中不在引号内的字节，f返回true时停止，返回当时的位置
+fn scan(s: &str, mut f: impl FnMut(usize, u8) -> bool) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut quote = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match quote {
+            Some(q) => {
+                if c == b'\\' {
+                    i += 1;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None if c == b'"' || c == b'\'' => quote = Some(c),
+            None if f(i, c) => return Some(i),
+            None => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// 遍历value中的token（含嵌套的token），f返回Some时，用其替换该token的文本，其余文本保持不变
+pub(crate) fn rewrite_tokens(value: &str, f: &mut dyn FnMut(&Token) -> Option<String>) -> String {
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    let mut ret = String::with_capacity(value.len());
+    rewrite(&mut parser, f, &mut ret);
+    ret
+}
+
+fn rewrite<'i, 't>(input: &mut Parser<'i, 't>, f: &mut dyn FnMut(&Token) -> Option<String>, ret: &mut String) {
+    loop {
+        let start = input.position();
+        let closing = match input.next_including_whitespace_and_comments() {
+            Ok(token) if let Some(r) = f(token) => {
+                ret.push_str(&r);
+                continue;
+            }
+            Ok(Token::Function(_)) | Ok(Token::ParenthesisBlock) => Some(')'),
+            Ok(Token::SquareBracketBlock) => Some(']'),
+            Ok(Token::CurlyBracketBlock) => Some('}'),
+            Ok(_) => None,
+            Err(_) => break,
+        };
+        ret.push_str(input.slice_from(start));
+        if let Some(closing) = closing {
+            let _ = input.parse_nested_block::<_, _, ()>(|i| {
+                rewrite(i, f, ret);
+                Ok(())
+            });
+            ret.push(closing);
+        }
+    }
+}
+
+/// 查找第一个var(
+fn find_var(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    scan(s, |i, _| bytes.len() - i >= 4 && bytes[i..i + 4].eq_ignore_ascii_case(b"var(") && (i == 0 || !is_ident_byte(bytes[i - 1])))
+}
+
+/// 查找与之前的`(`匹配的`)`
+fn find_close(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    scan(s, |_, c| {
+        match c {
+            b'(' => depth += 1,
+            b')' if depth == 0 => return true,
+            b')' => depth -= 1,
+            _ => (),
+        }
+        false
+    })
+}
+
+/// 查找不在括号内的第一个`,`
+fn find_comma(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    scan(s, |_, c| {
+        match c {
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b',' if depth == 0 => return true,
+            _ => (),
+        }
+        false
+    })
+}
+
+#[test]
+fn test_var() {
+    use crate::style_parse::{parse_class_map_from_string_with_report, parse_style_list_from_string, ClassStyle};
+    use crate::style_type::ClassSheet;
+
+    let mut theme = Theme::default();
+    theme.set("--a", "1px");
+    theme.set("--b", "var(--a) var(--c, 2px)");
+    theme.set("--r", "var(--r)");
+    let lookup = |name: &str| theme.get(name);
+    assert_eq!(substitute_vars("var(--b) \"var(--a)\" somevar(--a)", &lookup).unwrap(), "1px 2px \"var(--a)\" somevar(--a)");
+    assert_eq!(substitute_vars("var(--x, rgba(0, 0, 0, 1))", &lookup).unwrap(), "rgba(0, 0, 0, 1)");
+    assert!(substitute_vars("var(--x)", &lookup).is_err());
+    assert!(substitute_vars("var(--r)", &lookup).is_err());
+
+    let s = ":root {
+	--main: #ff0000;
+	--size: 10px;
+}
+.c1 {
+	width: var(--size);
+	height: 5px;
+	background-color: var(--main);
+}
+.c2 {
+	--size: 20px;
+	width: var(--size);
+	height: var(--missing, 3px);
+}
+.c3 {
+	width: var(--missing);
+}
+";
+    // class中的var()在编译时才计算，解析时不报错
+    let (class_map, report) = parse_class_map_from_string_with_report(s, 0);
+    assert!(report.is_empty());
+    assert_eq!(class_map.root_vars.get("--size"), Some("10px"));
+    assert_eq!(class_map.classes[0].count, 1);
+    assert_eq!(class_map.classes[0].vars.decls.len(), 2);
+    assert_eq!(class_map.to_css_string(), s);
+
+    let styles = |class_sheet: &ClassSheet, class_name: usize| -> String {
+        let list: Vec<Attribute> = unsafe { class_sheet.iter_class(class_name) }
+            .unwrap()
+            .map(|r| match r.unwrap() {
+                ClassStyle::Set(r) => r,
+                ClassStyle::Reset(_) => unreachable!(),
+            })
+            .collect();
+        format!("{:?}", list)
+    };
+    let expect = |s: &str| -> String { format!("{:?}", parse_style_list_from_string(s, 0).unwrap().into_iter().collect::<Vec<Attribute>>()) };
+
+    // 静态主题，使用:root中的定义
+    let mut class_sheet = ClassSheet::default();
+    let report = class_map.clone().to_class_sheet(&mut class_sheet);
+    assert_eq!(styles(&class_sheet, 1), expect("width: 10px; height: 5px; background-color: #ff0000"));
+    assert_eq!(styles(&class_sheet, 2), expect("width: 20px; height: 3px"));
+    assert_eq!(styles(&class_sheet, 3), expect(""));
+    // 无法计算的var()记录在编译的report中
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].class_name.as_deref(), Some("c3"));
+    assert_eq!(report.diagnostics[0].line, 16);
+    assert!(report.diagnostics[0].message.contains("--missing"));
+
+    // 切换主题，只重新计算引用了变化的变量的class，新样式放在旧样式的位置
+    let mut theme = Theme::default();
+    theme.set("--main", "#00ff00");
+    let (c1, c2, len) = (class_sheet.class_map[&1].clone(), class_sheet.class_map[&2].clone(), class_sheet.style_buffer.len());
+    let report = class_map.apply_theme(&theme, &mut class_sheet);
+    assert!(report.is_empty());
+    assert_eq!(styles(&class_sheet, 1), expect("width: 10px; height: 5px; background-color: #00ff00"));
+    assert_eq!(styles(&class_sheet, 2), expect("width: 20px; height: 3px"));
+    assert_eq!(class_sheet.class_map[&1].start, c1.start);
+    assert_eq!(class_sheet.class_map[&2].start, c2.start);
+    assert_eq!(class_sheet.style_buffer.len(), len);
+
+    // c2中定义了--size，不受主题中--size的影响
+    theme.set("--size", "30px");
+    class_map.apply_theme(&theme, &mut class_sheet);
+    assert_eq!(styles(&class_sheet, 1), expect("width: 30px; height: 5px; background-color: #00ff00"));
+    assert_eq!(styles(&class_sheet, 2), expect("width: 20px; height: 3px"));
+    assert_eq!(class_sheet.class_map[&2].start, c2.start);
+
+    // 新样式放不下时（c3原来没有样式），整理style_buffer，不再保留旧样式的空间
+    theme.set("--missing", "5px");
+    class_map.apply_theme(&theme, &mut class_sheet);
+    assert_eq!(styles(&class_sheet, 1), expect("width: 30px; height: 5px; background-color: #00ff00"));
+    assert_eq!(styles(&class_sheet, 2), expect("width: 20px; height: 5px"));
+    assert_eq!(styles(&class_sheet, 3), expect("width: 5px"));
+    let live: usize = class_sheet.class_map.values().map(|r| r.end - r.start).sum();
+    assert_eq!(class_sheet.style_buffer.len(), live);
+
+    // 关键帧中的var()使用`:root`中的自定义属性，无法计算时记录在解析的report中
+    let (class_map, report) = parse_class_map_from_string_with_report(
+        "@keyframes k { from { width: var(--size); } to { height: var(--none); animation-timing-function: var(--ease); } } :root { --size: 10px; --ease: ease-in; }",
+        0,
+    );
+    let frames = &class_map.key_frames.frames[&pi_atom::Atom::from("k")];
+    assert_eq!(frames.len(), 1);
+    assert_eq!(format!("{:?}", frames.values().next().unwrap()), expect("width: 10px"));
+    assert!(class_map.key_frames.timing_functions.get(&pi_atom::Atom::from("k")).is_none());
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].key_frames.as_deref(), Some("k"));
+
+    // 样式列表中的var()在解析时计算
+    assert_eq!(
+        format!("{:?}", parse_style_list_from_string("--w: 7px; width: var(--w); height: var(--h, 8px)", 0).unwrap()),
+        format!("{:?}", parse_style_list_from_string("width: 7px; height: 8px", 0).unwrap())
+    );
+}